@@ -604,7 +604,13 @@ macro_rules! merge_settings_impl {
                 }
             }
         }
+    };
+}
 
+/// Implements the nullable variant of a setting type; the non-null values are
+/// merged through the `Setting<$type>` implementation.
+macro_rules! merge_settings_option_impl {
+    ($type:ty) => {
         impl MergeSettings for Setting<Option<$type>> {
             type Intermediate = Option<Interpolated<$type>>;
 
@@ -709,9 +715,15 @@ macro_rules! merge_settings_impl {
 macro_rules! merge_settings {
     ($type:ty, $from_str:expr) => {
         merge_settings_impl!($type, $from_str);
+        merge_settings_option_impl!($type);
     };
 }
 
+// Paths have a dedicated implementation (they're resolved relative to the
+// folder of the configuration file that defines them), but the nullable
+// variant can be generated as usual.
+merge_settings_option_impl!(Path);
+
 include!(concat!(env!("OUT_DIR"), "/schema.rs"));
 
 impl SupportedArchitectures {
@@ -967,3 +979,4 @@ merge_settings!(zpm_utils::Secret<String>, |s: &str| FromFileString::from_file_s
 
 merge_settings!(crate::types::NodeLinker, |s: &str| FromFileString::from_file_string(s).unwrap());
 merge_settings!(crate::types::PnpFallbackMode, |s: &str| FromFileString::from_file_string(s).unwrap());
+merge_settings!(crate::types::ScriptShell, |s: &str| FromFileString::from_file_string(s).unwrap());
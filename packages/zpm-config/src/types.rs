@@ -27,3 +27,13 @@ pub enum PnpFallbackMode {
     #[literal("all")]
     All,
 }
+
+#[zpm_enum(error = ConfigurationError, or_else = |s| Err(ConfigurationError::EnumError(s.to_string())))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptShell {
+    #[literal("bash")]
+    Bash,
+
+    #[literal("builtin")]
+    Builtin,
+}
@@ -0,0 +1,92 @@
+use rkyv::Archive;
+use zpm_utils::{impl_file_string_from_str, impl_file_string_serialization, FromFileString, ToFileString};
+
+#[cfg(test)]
+#[path = "./custom_protocol.test.rs"]
+mod custom_protocol_tests;
+
+/// Protocols that have a dedicated range or reference variant; they can't be
+/// delegated to the `customProtocols` executables, otherwise a malformed
+/// native range (or one listed before its variant) would be misparsed.
+const NATIVE_PROTOCOLS: &[&str] = &[
+    "builtin",
+    "catalog",
+    "file",
+    "git",
+    "http",
+    "https",
+    "link",
+    "npm",
+    "patch",
+    "portal",
+    "virtual",
+    "workspace",
+];
+
+#[derive(thiserror::Error, Clone, Debug)]
+pub enum CustomProtocolError {
+    #[error("Invalid custom protocol: {0}")]
+    SyntaxError(String),
+
+    #[error("The {0} protocol is natively supported and cannot be overridden")]
+    NativeProtocol(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[rkyv(derive(PartialEq, Eq, PartialOrd, Ord, Hash))]
+pub struct CustomProtocol(String);
+
+impl CustomProtocol {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for CustomProtocol {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::ops::Deref for CustomProtocol {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for CustomProtocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromFileString for CustomProtocol {
+    type Error = CustomProtocolError;
+
+    fn from_file_string(src: &str) -> Result<Self, Self::Error> {
+        let is_valid
+            = src.starts_with(|c: char| c.is_ascii_lowercase())
+                && src.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+
+        if !is_valid {
+            return Err(CustomProtocolError::SyntaxError(src.to_string()));
+        }
+
+        if NATIVE_PROTOCOLS.contains(&src) {
+            return Err(CustomProtocolError::NativeProtocol(src.to_string()));
+        }
+
+        Ok(CustomProtocol(src.to_string()))
+    }
+}
+
+impl ToFileString for CustomProtocol {
+    fn to_file_string(&self) -> String {
+        self.0.clone()
+    }
+}
+
+impl_file_string_from_str!(CustomProtocol);
+impl_file_string_serialization!(CustomProtocol);
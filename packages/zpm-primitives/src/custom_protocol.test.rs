@@ -0,0 +1,60 @@
+use rstest::rstest;
+use zpm_utils::{FromFileString, ToFileString};
+
+use crate::{Range, Reference};
+
+#[rstest]
+#[case("virtual:npm:^1.0.0#0123456789abcdef")]
+#[case("virtual:my-proto:some-selector#0123456789abcdef")]
+fn virtual_ranges_are_not_custom(#[case] src: &str) {
+    let range
+        = Range::from_file_string(src).unwrap();
+
+    assert!(!matches!(range, Range::Custom(_)), "{:?}", range);
+    assert_eq!(range.to_file_string(), src);
+}
+
+#[rstest]
+#[case("my-proto:some-selector", "my-proto", "some-selector")]
+#[case("my-proto:1.0.0", "my-proto", "1.0.0")]
+fn custom_ranges_are_parsed(#[case] src: &str, #[case] protocol: &str, #[case] selector: &str) {
+    let range
+        = Range::from_file_string(src).unwrap();
+
+    let Range::Custom(params) = &range else {
+        panic!("Expected a custom range, got {:?}", range);
+    };
+
+    assert_eq!(params.protocol.as_str(), protocol);
+    assert_eq!(params.selector, selector);
+    assert_eq!(range.to_file_string(), src);
+}
+
+#[rstest]
+#[case("virtual:0123456789abcdef#npm:1.0.0")]
+#[case("virtual:0123456789abcdef#my-proto:some-payload")]
+fn virtual_references_are_not_custom(#[case] src: &str) {
+    let reference
+        = Reference::from_file_string(src).unwrap();
+
+    assert!(matches!(reference, Reference::Virtual(_)), "{:?}", reference);
+    assert_eq!(reference.to_file_string(), src);
+}
+
+#[rstest]
+#[case("my-proto:some-payload")]
+fn custom_references_are_parsed(#[case] src: &str) {
+    let reference
+        = Reference::from_file_string(src).unwrap();
+
+    assert!(matches!(reference, Reference::Custom(_)), "{:?}", reference);
+    assert_eq!(reference.to_file_string(), src);
+}
+
+#[rstest]
+#[case("virtual")]
+#[case("npm")]
+#[case("workspace")]
+fn native_protocols_are_rejected(#[case] protocol: &str) {
+    assert!(crate::CustomProtocol::from_file_string(protocol).is_err());
+}
@@ -1,5 +1,6 @@
 pub mod testing;
 
+mod custom_protocol;
 mod descriptor_filter;
 mod descriptor_resolution;
 mod descriptor_semver;
@@ -12,6 +13,7 @@ mod ident_glob;
 mod ident;
 mod locator;
 
+pub use custom_protocol::*;
 pub use descriptor_filter::*;
 pub use descriptor_resolution::*;
 pub use descriptor_semver::*;
@@ -5,7 +5,7 @@ use rkyv::Archive;
 use zpm_macro_enum::zpm_enum;
 use zpm_utils::{DataType, Hash64, Path, ToFileString, UrlEncoded};
 
-use crate::{PeerRange, SemverPeerRange};
+use crate::{CustomProtocol, PeerRange, SemverPeerRange};
 
 use super::{Descriptor, Ident};
 
@@ -183,6 +183,16 @@ pub enum Range {
         url: String,
     },
 
+    // Protocols that aren't natively supported; they're delegated to the
+    // executables declared in the `customProtocols` setting
+    #[pattern(r"(?<protocol>[a-z][a-z0-9-]*):(?<selector>.*)")]
+    #[to_file_string(|params| format!("{}:{}", params.protocol, params.selector))]
+    #[to_print_string(|params| DataType::Range.colorize(&format!("{}:{}", params.protocol, params.selector)))]
+    Custom {
+        protocol: CustomProtocol,
+        selector: String,
+    },
+
     #[pattern(r"(?<tag>.*)")]
     #[to_file_string(|params| params.tag.clone())]
     #[to_print_string(|params| DataType::Range.colorize(&params.tag))]
@@ -4,7 +4,7 @@ use rkyv::Archive;
 use zpm_macro_enum::zpm_enum;
 use zpm_utils::{DataType, Hash64, Path, ToFileString, UrlEncoded};
 
-use super::{Ident, Locator};
+use super::{CustomProtocol, Ident, Locator};
 
 fn format_patch(inner: &UrlEncoded<Locator>, path: &str, checksum: &Option<Hash64>) -> String {
     match checksum {
@@ -144,6 +144,14 @@ pub enum Reference {
     Url {
         url: String,
     },
+
+    #[pattern(r"(?<protocol>[a-z][a-z0-9-]*):(?<payload>.*)")]
+    #[to_file_string(|params| format!("{}:{}", params.protocol, params.payload))]
+    #[to_print_string(|params| DataType::Reference.colorize(&format!("{}:{}", params.protocol, params.payload)))]
+    Custom {
+        protocol: CustomProtocol,
+        payload: String,
+    },
 }
 
 impl Reference {
@@ -233,6 +241,10 @@ impl Reference {
                 "url".to_string()
             },
 
+            Reference::Custom(params) => {
+                params.protocol.to_string()
+            },
+
             Reference::Virtual(_) => {
                 "virtual".to_string()
             },
@@ -64,8 +64,14 @@ pub struct SyncCheck {
     pub must_create: bool,
 }
 
+/// Callback used to write the files of the tree on disk; lets the caller store
+/// the file contents elsewhere and link them into place rather than copying them.
+pub type FileMaterializer
+    = Arc<dyn Fn(&Path, &[u8], bool) -> Result<(), SyncError> + Send + Sync>;
+
 pub struct SyncTree<'a> {
     pub dry_run: bool,
+    pub file_materializer: Option<FileMaterializer>,
     nodes: Vec<SyncNode<'a>>,
 }
 
@@ -105,6 +111,7 @@ impl<'a> SyncTree<'a> {
     pub fn new() -> Self {
         Self {
             dry_run: true,
+            file_materializer: None,
             nodes: vec![SyncNode::Folder {
                 template: None,
                 children: BTreeMap::new(),
@@ -349,6 +356,7 @@ impl<'a> SyncTree<'a> {
                                 = SyncTree::from_entries(&zip_entries)?;
 
                             template_tree.dry_run = self.dry_run;
+                            template_tree.file_materializer = self.file_materializer.clone();
 
                             // We must instruct the template tree to ignore the entries
                             // that our side of the tree expects to handle
@@ -395,10 +403,12 @@ impl<'a> SyncTree<'a> {
                 Ok(next_tasks)
             },
 
-            SyncNode::File {data, ..} => {
+            SyncNode::File {data, is_exec} => {
                 if check.must_create {
                     if self.dry_run {
                         file_ops.push(FileOp::CreateFile(path.clone(), data[..data.len().min(20)].to_vec()));
+                    } else if let Some(file_materializer) = &self.file_materializer {
+                        file_materializer(&path, data, *is_exec)?;
                     } else {
                         path.fs_write(data)?;
                     }
@@ -2,7 +2,7 @@ use std::collections::{BTreeMap, BTreeSet};
 
 use zpm_parsers::JsonDocument;
 use zpm_primitives::Locator;
-use zpm_utils::{CollectHash, Hash64, IoResultExt, Path, ToFileString};
+use zpm_utils::{CollectHash, Hash64, IoResultExt, Path, System, ToFileString, ToHumanString};
 use rkyv::Archive;
 use futures::{future::BoxFuture, stream::FuturesUnordered, FutureExt, StreamExt};
 use serde::{ser::SerializeMap, Deserialize, Deserializer, Serialize};
@@ -11,8 +11,12 @@ use crate::{
     algos,
     diff_finder::{DiffController, DiffFinder},
     error::Error,
+    install::InstallState,
+    linker::helpers::TopLevelConfiguration,
+    lockfile::Lockfile,
     project::Project,
-    report::{with_context_result, ReportContext},
+    report::{current_report, with_context_result, ReportContext},
+    sandbox::Sandbox,
     script::{ScriptEnvironment, ScriptResult},
 };
 
@@ -30,6 +34,120 @@ pub enum Command {
     },
 }
 
+impl Command {
+    /// Returns the command as it would be typed in a shell, prefixed with
+    /// the lifecycle event that triggers it (if any).
+    pub fn describe(&self) -> String {
+        match self {
+            Command::Program {name, args} => {
+                std::iter::once(name.as_str())
+                    .chain(args.iter().map(|arg| arg.as_str()))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            },
+
+            Command::Script {event: Some(event), script} => {
+                format!("{}: {}", event, script)
+            },
+
+            Command::Script {event: None, script} => {
+                script.clone()
+            },
+        }
+    }
+}
+
+/// Returns the hash identifying the build scripts of a package; an approval
+/// only holds as long as the scripts keep the same content.
+pub fn build_commands_hash(commands: &[Command]) -> Hash64 {
+    let serialized_commands
+        = serde_json::to_string(commands)
+            .expect("The build commands should be serializable");
+
+    Hash64::from_data(serialized_commands)
+}
+
+/// Whether the build scripts of the given package may run as far as the
+/// approvals are concerned. Packages stored on disk (workspaces, portals,
+/// links) are under the user's control, so only the third-party packages
+/// need to be approved.
+pub fn is_build_approved(project: &Project, lockfile: &Lockfile, locator: &Locator, commands: &[Command]) -> bool {
+    let physical_locator
+        = locator.physical_locator();
+
+    if !project.config.settings.enable_build_approvals.value || physical_locator.reference.is_disk_reference() {
+        return true;
+    }
+
+    lockfile.approved_builds.get(&physical_locator) == Some(&build_commands_hash(commands))
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockedBuild {
+    pub locator: Locator,
+    pub commands: Vec<Command>,
+    pub hash: Hash64,
+
+    /// Whether a different version of the build scripts had been approved.
+    pub was_approved: bool,
+}
+
+/// Lists the packages that would be built if it wasn't for their missing
+/// approval; the packages explicitly configured through `dependenciesMeta`
+/// and the ones incompatible with the current system aren't reported.
+pub fn find_blocked_builds(project: &Project, lockfile: &Lockfile, install_state: &InstallState) -> Vec<BlockedBuild> {
+    if !project.config.settings.enable_scripts.value {
+        return vec![];
+    }
+
+    let dependencies_meta
+        = TopLevelConfiguration::from_project(project);
+
+    let system
+        = System::from_current();
+
+    install_state.content_flags.iter()
+        .filter(|(_, flags)| !flags.build_commands.is_empty())
+        .filter(|(locator, flags)| !is_build_approved(project, lockfile, locator, &flags.build_commands))
+        .filter(|(locator, _)| {
+            let Some(resolution) = install_state.normalized_resolutions.get(*locator) else {
+                return false;
+            };
+
+            let is_configured = dependencies_meta.iter()
+                .find(|(selector, _)| selector.check(&locator.ident, &resolution.version))
+                .is_some_and(|(_, meta)| meta.built.is_some());
+
+            !is_configured && resolution.requirements.validate_system(&system)
+        })
+        .map(|(locator, flags)| BlockedBuild {
+            locator: locator.clone(),
+            commands: flags.build_commands.clone(),
+            hash: build_commands_hash(&flags.build_commands),
+            was_approved: lockfile.approved_builds.contains_key(locator),
+        })
+        .collect()
+}
+
+pub async fn report_blocked_builds(blocked_builds: &[BlockedBuild]) {
+    if blocked_builds.is_empty() {
+        return;
+    }
+
+    current_report().await.as_ref().map(|report| {
+        for blocked_build in blocked_builds {
+            let reason = match blocked_build.was_approved {
+                true => "its build scripts changed since they got approved",
+                false => "its build scripts haven't been approved",
+            };
+
+            report.warn(format!("{} won't be built, as {}", blocked_build.locator.to_print_string(), reason));
+        }
+
+        report.warn("Run `yarn approve-builds` to review the blocked packages".to_string());
+    });
+}
+
 pub struct ArtifactFinder;
 
 impl DiffController for ArtifactFinder {
@@ -55,6 +173,7 @@ pub struct BuildRequest {
     pub commands: Vec<Command>,
     pub allowed_to_fail: bool,
     pub force_rebuild: bool,
+    pub allow_network: bool,
 }
 
 impl BuildRequest {
@@ -68,6 +187,31 @@ impl BuildRequest {
             .with_env_variable("INIT_CWD", cwd_abs.as_str())
             .with_cwd(cwd_abs.clone());
 
+        // The workspaces and other local packages are under the user's
+        // control, so only the third-party packages are sandboxed
+        let must_sandbox
+            = project.config.settings.enable_build_sandbox.value
+                && Sandbox::is_supported()
+                && !self.locator.reference.is_disk_reference();
+
+        if must_sandbox {
+            let mut read_only_paths
+                = vec![project.project_cwd.clone()];
+
+            // The archives from the global cache are required to load the
+            // dependencies of the package being built
+            if project.config.settings.enable_global_cache.value {
+                read_only_paths.push(project.global_cache_path());
+            }
+
+            script_env = script_env.with_sandbox(Sandbox {
+                hidden_paths: Path::home_dir()?.into_iter().collect(),
+                read_only_paths,
+                writable_paths: vec![cwd_abs.clone()],
+                allow_network: self.allow_network,
+            });
+        }
+
         let res = with_context_result(ReportContext::Locator(self.locator.clone()), async {
             let build_cache_folder = if self.locator.reference.is_disk_reference() {
                 None
@@ -390,6 +534,12 @@ impl<'a> BuildManager<'a> {
             self.queued.push(idx);
         }
 
+        if project.config.settings.enable_build_sandbox.value && !Sandbox::is_supported() && !self.requests.entries.is_empty() {
+            current_report().await.as_ref().map(|report| {
+                report.warn("The build sandbox is only supported on Linux; the build scripts will run without it".to_string());
+            });
+        }
+
         self.trigger(project, &build_state_in);
 
         let mut current_build_state_out
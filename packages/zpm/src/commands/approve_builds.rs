@@ -0,0 +1,125 @@
+use std::io::IsTerminal;
+
+use clipanion::cli;
+use zpm_primitives::IdentGlob;
+use zpm_utils::{DataType, ToHumanString};
+
+use crate::{
+    build,
+    error::Error,
+    project::{Project, RunInstallOptions},
+    report::{current_report, with_report_result, PromptType, StreamReport, StreamReportConfig},
+};
+
+/// Approve the build scripts of dependencies
+///
+/// The build scripts of third-party packages (`preinstall`, `install`, `postinstall`, or the implicit `node-gyp rebuild`) don't run until they
+/// get approved. This command displays the build scripts of each blocked package and asks whether to approve them; the approvals are recorded
+/// in the lockfile along with a hash of the approved scripts, so a package whose scripts change will be blocked again until re-approved.
+///
+/// Reviewing the packages one by one requires an interactive terminal; use `--all` (optionally along with patterns) in other environments.
+///
+/// The approved packages are built right away. Packages explicitly configured through `dependenciesMeta.built` aren't subject to approvals, and
+/// approvals can be disabled altogether through the `enableBuildApprovals` setting.
+///
+#[cli::command]
+#[cli::path("approve-builds")]
+#[cli::category("Dependency management")]
+pub struct ApproveBuilds {
+    /// Approve all the blocked packages without asking
+    #[cli::option("-A,--all", default = false)]
+    all: bool,
+
+    /// The packages to review
+    patterns: Vec<IdentGlob>,
+}
+
+impl ApproveBuilds {
+    pub async fn execute(&self) -> Result<(), Error> {
+        let mut project
+            = Project::new(None).await?;
+
+        project.lazy_install().await?;
+
+        let mut lockfile
+            = project.lockfile()?;
+
+        let install_state = project.install_state.as_ref()
+            .ok_or(Error::InstallStateNotFound)?;
+
+        let blocked_builds = build::find_blocked_builds(&project, &lockfile, install_state).into_iter()
+            .filter(|blocked_build| self.patterns.is_empty() || self.patterns.iter().any(|pattern| pattern.check(&blocked_build.locator.ident)))
+            .collect::<Vec<_>>();
+
+        if blocked_builds.is_empty() {
+            println!("No build is waiting for approval");
+            return Ok(());
+        }
+
+        if !self.all && !std::io::stdin().is_terminal() {
+            return Err(Error::ApproveBuildsRequiresTerminal);
+        }
+
+        let report
+            = StreamReport::new(StreamReportConfig {
+                ..StreamReportConfig::from_config(&project.config)
+            });
+
+        let approved_builds = with_report_result(report, async {
+            let report_guard
+                = current_report().await;
+
+            let report
+                = report_guard.as_ref()
+                    .expect("No report set");
+
+            let mut approved_builds
+                = vec![];
+
+            for blocked_build in blocked_builds {
+                let status = match blocked_build.was_approved {
+                    true => "changed since the last approval",
+                    false => "not approved yet",
+                };
+
+                report.info(format!("{} ({}):", blocked_build.locator.to_print_string(), status));
+
+                for command in &blocked_build.commands {
+                    report.info(format!("- {}", DataType::Code.colorize(&command.describe())));
+                }
+
+                let is_approved = self.all || {
+                    let items
+                        = vec!["Skip".to_string(), "Approve".to_string()];
+
+                    let selection
+                        = report.prompt(PromptType::Select(format!("Approve the build of {}?", blocked_build.locator.to_print_string()), items)).await?
+                            .parse::<usize>()
+                            .expect("Select prompts always resolve to a valid index");
+
+                    selection == 1
+                };
+
+                if is_approved {
+                    approved_builds.push(blocked_build);
+                }
+            }
+
+            Ok(approved_builds)
+        }).await?;
+
+        if approved_builds.is_empty() {
+            return Ok(());
+        }
+
+        for approved_build in approved_builds {
+            lockfile.approved_builds.insert(approved_build.locator, approved_build.hash);
+        }
+
+        project.write_lockfile(&lockfile)?;
+
+        project.run_install(RunInstallOptions::default()).await?;
+
+        Ok(())
+    }
+}
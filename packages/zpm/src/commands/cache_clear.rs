@@ -39,6 +39,55 @@ impl CacheClear2 {
     }
 }
 
+/// Clear the registry metadata cache
+///
+/// This command removes the registry metadata stored inside the global folder (see `enableMetadataCache`), forcing the next commands to
+/// download them again in full.
+///
+#[cli::command]
+#[cli::path("cache", "metadata", "clear")]
+#[cli::path("cache", "metadata", "clean")]
+#[cli::category("Cache management")]
+pub struct CacheMetadataClear {
+}
+
+impl CacheMetadataClear {
+    pub async fn execute(&self) -> Result<(), Error> {
+        let project
+            = project::Project::new(None).await?;
+
+        let report = StreamReport::new(StreamReportConfig {
+            ..StreamReportConfig::from_config(&project.config)
+        });
+
+        with_report_result(report, async {
+            let metadata_cache_path
+                = &project.http_client.config.metadata_cache_path;
+
+            let cleared_entries = metadata_cache_path
+                .fs_read_dir()
+                .ok_missing()?
+                .map_or(0, |entries| entries.count());
+
+            metadata_cache_path
+                .fs_rm()
+                .ok_missing()?;
+
+            current_report().await.as_ref().map(|report| {
+                if cleared_entries > 0 {
+                    report.info(format!("Cleared {} entries from the metadata cache.", DataType::Number.colorize(&cleared_entries.to_string())))
+                } else {
+                    report.info("No entries to clear from the metadata cache.".to_string());
+                }
+            });
+
+            Ok(())
+        }).await?;
+
+        Ok(())
+    }
+}
+
 async fn clear_cache(old: bool) -> Result<(), Error> {
     let project
         = project::Project::new(None).await?;
@@ -3,9 +3,8 @@ use std::process::ExitCode;
 use clipanion::cli;
 use colored::Colorize;
 use zpm_utils::{tree, AbstractValue, DataType, ToFileString, ToHumanString};
-use zpm_parsers::{Document, JsonDocument, Value};
 
-use crate::{constraints::{check_constraints, structs::{ConstraintsOutput, WorkspaceError, WorkspaceOperation}}, error::Error, project::Project};
+use crate::{constraints::{apply::apply_constraints_operations, check_constraints, structs::{ConstraintsOutput, WorkspaceError}}, error::Error, project::Project};
 
 /// Check constraints
 ///
@@ -15,6 +14,9 @@ use crate::{constraints::{check_constraints, structs::{ConstraintsOutput, Worksp
 /// If the `--fix` flag is used, Yarn will attempt to automatically fix the issues the best it can, following a multi-pass process (with a maximum of
 /// 10 iterations). Some ambiguous patterns cannot be autofixed, in which case you'll have to manually specify the right resolution.
 ///
+/// Common rules (consistent dependency ranges across workspaces, enforced manifest fields, banned dependencies) can also be declared in a
+/// `yarn.constraints.yml` file at the root of the project. When this file exists, it's evaluated natively and Node isn't required.
+///
 /// For more information as to how to write constraints, please consult our dedicated page on our website: https://yarnpkg.com/features/constraints.
 ///
 #[cli::command]
@@ -48,35 +50,7 @@ impl Constraints {
             let output
                 = check_constraints(&project, self.fix).await?;
 
-            for (workspace_rel_path, operations) in &output.all_workspace_operations {
-                // Read the current manifest
-                let manifest_path = project.project_cwd
-                    .with_join(workspace_rel_path)
-                    .with_join_str("package.json");
-
-                let manifest_content = manifest_path
-                    .fs_read_prealloc()?;
-
-                let mut document
-                    = JsonDocument::new(manifest_content)?;
-
-                // Apply each operation
-                for operation in operations {
-                    match operation {
-                        WorkspaceOperation::Set { path, value } => {
-                            document.set_path(&zpm_parsers::Path::from_segments(path.clone()), value.into())?;
-                        },
-
-                        WorkspaceOperation::Unset { path } => {
-                            document.set_path(&zpm_parsers::Path::from_segments(path.clone()), Value::Undefined)?;
-                        },
-                    }
-                }
-
-                // Write the formatted result back
-                manifest_path
-                    .fs_change(&document.input, false)?;
-            }
+            apply_constraints_operations(&project, &output)?;
 
             let should_break = false
                 || output.all_workspace_operations.is_empty()
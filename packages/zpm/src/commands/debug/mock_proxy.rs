@@ -1,7 +1,7 @@
 use std::{collections::HashMap, net::SocketAddr, sync::Arc};
 
 use clipanion::cli;
-use http_body_util::Full;
+use http_body_util::{BodyExt, Full};
 use hyper::{body::Bytes, server::conn::http1, service::service_fn, Method, Request, Response, StatusCode};
 use hyper_util::rt::TokioIo;
 use reqwest::Url;
@@ -98,7 +98,7 @@ impl MockProxy {
 fn not_allowed() -> Response<Full<Bytes>> {
     Response::builder()
         .status(StatusCode::METHOD_NOT_ALLOWED)
-        .body(Full::new(Bytes::from("Only GET requests are supported")))
+        .body(Full::new(Bytes::from("Only GET and POST requests are supported")))
         .unwrap()
 }
 
@@ -118,26 +118,37 @@ async fn handle_request(state: Arc<ProxyState>, req: Request<hyper::body::Incomi
         .unwrap_or("/")
         .to_string();
 
-    if method != Method::GET {
+    if method != Method::GET && method != Method::POST {
         return Ok(not_allowed());
     }
 
+    let body
+        = req.into_body().collect().await?.to_bytes();
+
+    // POST requests (for example the bulk advisory endpoint used by `yarn npm audit`)
+    // are cached alongside their payload, since the same path can yield different results
+    let cache_key = if method == Method::POST {
+        format!("POST {path}\n{}", String::from_utf8_lossy(&body))
+    } else {
+        path.clone()
+    };
+
     {
         let cache
             = state.cache.read().await;
 
-        if let Some(cached) = cache.get(&path) {
-            println!("[CACHE] {path}");
+        if let Some(cached) = cache.get(&cache_key) {
+            println!("[CACHE] {method} {path}");
             return Ok(build_response(cached, &state));
         }
     }
 
-    println!("[FETCH] {path}");
+    println!("[FETCH] {method} {path}");
 
     let remote_url
         = format!("{}{}", state.remote_url.as_str().trim_end_matches('/'), path);
 
-    match fetch_and_cache(&state, &path, &remote_url).await {
+    match fetch_and_cache(&state, &cache_key, method, &remote_url, body).await {
         Ok(cached) => {
             Ok(build_response(&cached, &state))
         },
@@ -149,9 +160,17 @@ async fn handle_request(state: Arc<ProxyState>, req: Request<hyper::body::Incomi
     }
 }
 
-async fn fetch_and_cache(state: &ProxyState, path: &str, remote_url: &str) -> Result<CachedResponse, String> {
-    let response = state.client
-        .get(remote_url)
+async fn fetch_and_cache(state: &ProxyState, cache_key: &str, method: Method, remote_url: &str, body: Bytes) -> Result<CachedResponse, String> {
+    let mut request
+        = state.client.request(method.clone(), remote_url);
+
+    if method == Method::POST {
+        request = request
+            .header("content-type", "application/json")
+            .body(body.to_vec());
+    }
+
+    let response = request
         .send()
         .await
         .map_err(|e| format!("Request failed: {e}"))?;
@@ -211,7 +230,7 @@ async fn fetch_and_cache(state: &ProxyState, path: &str, remote_url: &str) -> Re
             = state.cache.write().await;
 
         cache.insert(
-            path.to_string(),
+            cache_key.to_string(),
             cached.clone(),
         );
     }
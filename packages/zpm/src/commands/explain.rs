@@ -0,0 +1,74 @@
+use clipanion::cli;
+use serde_json::json;
+use zpm_utils::DataType;
+
+use crate::{error::Error, error_codes::ErrorCode};
+
+/// List the error codes
+///
+/// This command prints the list of all the codes that Yarn may attach to the errors it reports, along with a short description of each of
+/// them. Run `yarn explain <code>` to get more details about a specific code.
+///
+#[cli::command]
+#[cli::path("explain")]
+#[cli::category("General commands")]
+pub struct ExplainList {
+    /// Format the output as an NDJSON stream
+    #[cli::option("--json", default = false)]
+    json: bool,
+}
+
+impl ExplainList {
+    pub async fn execute(&self) -> Result<(), Error> {
+        for code in ErrorCode::ALL {
+            match self.json {
+                true => println!("{}", json!({
+                    "code": code.to_string(),
+                    "name": code.name(),
+                    "summary": code.summary(),
+                })),
+
+                false => println!("{} {} - {}", DataType::Code.colorize(&code.to_string()), code.name(), code.summary()),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Explain an error code
+///
+/// This command prints a detailed explanation of the given error code, including its usual causes and how to address them. The code can be
+/// given either in its full form (`YN0028`) or as a number (`28`). The explanations are embedded into Yarn, so this command works offline.
+///
+#[cli::command]
+#[cli::path("explain")]
+#[cli::category("General commands")]
+pub struct Explain {
+    /// Format the output as a JSON object
+    #[cli::option("--json", default = false)]
+    json: bool,
+
+    /// The error code to explain
+    code: String,
+}
+
+impl Explain {
+    pub async fn execute(&self) -> Result<(), Error> {
+        let code: ErrorCode = self.code.parse()
+            .map_err(|_| Error::UnknownErrorCode(self.code.clone()))?;
+
+        match self.json {
+            true => println!("{}", json!({
+                "code": code.to_string(),
+                "name": code.name(),
+                "summary": code.summary(),
+                "explanation": code.explanation(),
+            })),
+
+            false => print!("{}", code.explanation()),
+        }
+
+        Ok(())
+    }
+}
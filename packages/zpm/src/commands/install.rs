@@ -26,6 +26,8 @@ use crate::{error::Error, project::{self, InstallMode, RunInstallOptions}};
 ///
 /// If the `--check-cache` option is set, Yarn will always refetch the packages and will ensure that their checksum matches what's 1/ described in the lockfile 2/ inside the existing cache files (if present). This is recommended as part of your CI workflow if you're both following the Zero-Installs model and accepting PRs from third-parties, as they'd otherwise have the ability to alter the checked-in packages before submitting them.
 ///
+/// If the `--offline` option is set, Yarn will refuse any network access and will install the project purely from the offline mirror, ie the packages and registry metadata stored inside the local cache folder by previous installs made with `enableOfflineMirror` set. Packages missing from the mirror are all reported at once.
+///
 /// If the `--inline-builds` option is set, Yarn will verbosely print the output of the build steps of your dependencies (instead of writing them into individual files). This is likely useful mostly for debug purposes only when using Docker-like environments.
 ///
 /// If the `--mode=<mode>` option is set, Yarn will change which artifacts are generated. The modes currently supported are:
@@ -57,6 +59,10 @@ pub struct Install {
     #[cli::option("--refresh-lockfile", default = false)]
     refresh_lockfile: bool,
 
+    /// Install from the offline mirror without any network access
+    #[cli::option("--offline", default = false)]
+    offline: bool,
+
     /// Select the artifacts this install will generate
     #[cli::option("--mode")]
     mode: Option<InstallMode>,
@@ -93,6 +99,10 @@ impl Install {
             project.config.settings.enable_immutable_cache.source = Source::Cli;
         }
 
+        if self.offline {
+            project.enable_offline_mode()?;
+        }
+
         sort_workspace_dependencies(&project)?;
 
         project.run_install(RunInstallOptions {
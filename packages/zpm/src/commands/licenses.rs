@@ -0,0 +1,177 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use clipanion::cli;
+use zpm_primitives::Locator;
+use zpm_utils::{tree, AbstractValue};
+
+use crate::{
+    error::Error,
+    manifest::license::{is_license_allowed, LicenseManifest},
+    project::Project,
+};
+
+/// Label used for the packages that don't declare any license
+const UNKNOWN_LICENSE: &str = "UNKNOWN";
+
+/// List the licenses of all dependencies
+///
+/// This command prints the license declared by each package resolved by the project (workspaces excluded), grouped by SPDX expression. Packages
+/// that don't declare a license are listed under `UNKNOWN`. Packages that aren't installed on the current system (for example optional
+/// dependencies targeting another platform) are omitted.
+///
+#[cli::command]
+#[cli::path("licenses", "list")]
+#[cli::category("Dependency management")]
+pub struct LicensesList {
+    /// Format the output as an NDJSON stream
+    #[cli::option("--json", default = false)]
+    json: bool,
+}
+
+impl LicensesList {
+    pub async fn execute(&self) -> Result<(), Error> {
+        let mut project
+            = Project::new(None).await?;
+
+        project.lazy_install().await?;
+
+        let packages_by_license
+            = collect_packages_by_license(&project)?;
+
+        print_licenses(&packages_by_license, self.json);
+
+        Ok(())
+    }
+}
+
+/// Check the licenses of all dependencies against a policy
+///
+/// This command checks that the license declared by each package resolved by the project satisfies the given allowlist. The allowlist is read
+/// from the `--allow` option (which accepts comma-separated values and may be repeated), or from the `licensePolicy` setting if the option
+/// isn't set.
+///
+/// SPDX expressions are evaluated: `MIT OR GPL-3.0` is accepted as long as `MIT` is allowed, whereas `MIT AND GPL-3.0` requires both licenses
+/// to be allowed. Packages that don't declare a license are always reported.
+///
+#[cli::command]
+#[cli::path("licenses", "check")]
+#[cli::category("Dependency management")]
+pub struct LicensesCheck {
+    /// Format the output as an NDJSON stream
+    #[cli::option("--json", default = false)]
+    json: bool,
+
+    /// The licenses to allow, overriding the `licensePolicy` setting
+    #[cli::option("--allow", default = vec![])]
+    allow: Vec<String>,
+}
+
+impl LicensesCheck {
+    pub async fn execute(&self) -> Result<(), Error> {
+        let mut project
+            = Project::new(None).await?;
+
+        project.lazy_install().await?;
+
+        let allowed_licenses = match self.allow.is_empty() {
+            true => project.config.settings.license_policy.iter()
+                .map(|setting| setting.value.as_str())
+                .collect::<Vec<_>>(),
+
+            false => self.allow.iter()
+                .flat_map(|value| value.split(','))
+                .map(|license| license.trim())
+                .filter(|license| !license.is_empty())
+                .collect::<Vec<_>>(),
+        };
+
+        if allowed_licenses.is_empty() {
+            return Err(Error::MissingLicensePolicy);
+        }
+
+        let packages_by_license
+            = collect_packages_by_license(&project)?;
+
+        let violations = packages_by_license.into_iter()
+            .filter(|(license, _)| license == UNKNOWN_LICENSE || !is_license_allowed(license, &allowed_licenses))
+            .collect::<BTreeMap<_, _>>();
+
+        if violations.is_empty() {
+            if !self.json {
+                println!("All packages comply with the license policy");
+            }
+
+            return Ok(());
+        }
+
+        print_licenses(&violations, self.json);
+
+        let violation_count
+            = violations.values().map(|locators| locators.len()).sum();
+
+        Err(Error::LicensePolicyViolation(violation_count))
+    }
+}
+
+/// Group the physical packages of the project by the license expression they declare
+fn collect_packages_by_license(project: &Project) -> Result<BTreeMap<String, BTreeSet<Locator>>, Error> {
+    let install_state
+        = project.install_state.as_ref()
+            .ok_or(Error::InstallStateNotFound)?;
+
+    let physical_locators = install_state.resolution_tree.locator_resolutions.keys()
+        .map(|locator| locator.physical_locator())
+        .collect::<BTreeSet<_>>();
+
+    let mut packages_by_license: BTreeMap<String, BTreeSet<Locator>>
+        = BTreeMap::new();
+
+    for locator in physical_locators {
+        if project.try_workspace_by_locator(&locator)?.is_some() {
+            continue;
+        }
+
+        if !install_state.locations_by_package.contains_key(&locator) {
+            continue;
+        }
+
+        let manifest: LicenseManifest
+            = project.package_manifest(&locator)?;
+
+        let license
+            = manifest.license_expression()
+                .unwrap_or_else(|| UNKNOWN_LICENSE.to_string());
+
+        packages_by_license.entry(license)
+            .or_default()
+            .insert(locator);
+    }
+
+    Ok(packages_by_license)
+}
+
+fn print_licenses(packages_by_license: &BTreeMap<String, BTreeSet<Locator>>, json: bool) {
+    let license_nodes = packages_by_license.iter()
+        .map(|(license, locators)| tree::Node {
+            label: None,
+            value: Some(AbstractValue::new(license.clone())),
+            children: Some(tree::TreeNodeChildren::Vec(locators.iter().map(|locator| tree::Node {
+                label: None,
+                value: Some(AbstractValue::new(locator.clone())),
+                children: None,
+            }).collect())),
+        })
+        .collect::<Vec<_>>();
+
+    let root_node = tree::Node {
+        label: None,
+        value: None,
+        children: Some(tree::TreeNodeChildren::Vec(license_nodes)),
+    };
+
+    let rendering
+        = tree::TreeRenderer::new()
+            .render(&root_node, json);
+
+    print!("{}", rendering);
+}
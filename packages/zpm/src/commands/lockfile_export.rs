@@ -0,0 +1,57 @@
+use clipanion::cli;
+use zpm_macro_enum::zpm_enum;
+
+use crate::{
+    error::Error,
+    lockfile_export::{to_npm_lockfile, to_pnpm_lockfile},
+    project::Project,
+};
+
+#[zpm_enum(or_else = |s| Err(Error::InvalidLockfileExportFormat(s.to_string())))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive_variants(Debug)]
+pub enum LockfileExportFormat {
+    #[literal("npm")]
+    Npm,
+
+    #[literal("pnpm")]
+    Pnpm,
+}
+
+/// Export the lockfile in another format
+///
+/// This command converts the resolutions of the project into a lockfile understood by other package managers, so that tools which only support
+/// those formats (security scanners, update bots, ...) can operate on the project. The generated lockfile is printed on the standard output:
+///
+/// - `npm` generates a `package-lock.json` file (lockfileVersion 3), using a hoisted `node_modules` layout.
+///
+/// - `pnpm` generates a `pnpm-lock.yaml` file (lockfileVersion 9), in which packages with peer dependencies are listed once per set of peers.
+///
+/// Note that the integrity hashes aren't included, as Yarn only stores the checksums of the archives it generates.
+///
+#[cli::command]
+#[cli::path("lockfile", "export")]
+#[cli::category("Project management")]
+pub struct LockfileExport {
+    /// The format of the generated lockfile (npm or pnpm)
+    #[cli::option("--format", default = LockfileExportFormat::Npm)]
+    format: LockfileExportFormat,
+}
+
+impl LockfileExport {
+    pub async fn execute(&self) -> Result<(), Error> {
+        let mut project
+            = Project::new(None).await?;
+
+        project.lazy_install().await?;
+
+        let output = match self.format {
+            LockfileExportFormat::Npm => to_npm_lockfile(&project)?,
+            LockfileExportFormat::Pnpm => to_pnpm_lockfile(&project)?,
+        };
+
+        print!("{}", output);
+
+        Ok(())
+    }
+}
@@ -3,10 +3,13 @@ use std::process::ExitCode;
 use clipanion::{prelude::*, Environment};
 use zpm_switch::{extract_bin_meta, BinMeta};
 
+use crate::report::enable_json_report;
+
 mod debug;
 mod npm;
 
 mod add;
+mod approve_builds;
 mod bin;
 mod cache_clear;
 mod config;
@@ -16,23 +19,29 @@ mod constraints;
 mod dedupe;
 mod dlx;
 mod exec;
+mod explain;
 mod info;
 mod init;
 mod install;
+mod licenses;
 mod link;
+mod lockfile_export;
 mod node;
+mod outdated;
 mod pack;
 mod patch_commit;
 mod patch;
 mod rebuild;
 mod remove;
 mod run;
+mod sbom;
 mod set_resolution;
 mod set_version;
 mod set_version_from_sources;
 mod unlink;
 mod unplug;
 mod up;
+mod upgrade_interactive;
 mod version;
 mod workspaces_focus;
 mod workspaces_foreach;
@@ -65,10 +74,18 @@ pub enum YarnCli {
     PrintPlatform(debug::print_platform::PrintPlatform),
     SyncFs(debug::sync_fs::SyncFs),
 
+    Audit(npm::audit::Audit),
+    Deprecate(npm::deprecate::Deprecate),
+    NpmInfo(npm::info::NpmInfo),
     Login(npm::login::Login),
     LogoutAll(npm::logout_all::LogoutAll),
     Logout(npm::logout::Logout),
     Publish(npm::publish::Publish),
+    NpmTagAdd(npm::tag::NpmTagAdd),
+    NpmTagList(npm::tag::NpmTagList),
+    NpmTagListPackage(npm::tag::NpmTagListPackage),
+    NpmTagRemove(npm::tag::NpmTagRemove),
+    Unpublish(npm::unpublish::Unpublish),
     Whoami(npm::whoami::Whoami),
 
     VersionApply(version::apply::VersionApply),
@@ -76,10 +93,12 @@ pub enum YarnCli {
     VersionDeferred(version::deferred::VersionDeferred),
 
     Add(add::Add),
+    ApproveBuilds(approve_builds::ApproveBuilds),
     BinList(bin::BinList),
     Bin(bin::Bin),
     CacheClear(cache_clear::CacheClear),
     CacheClear2(cache_clear::CacheClear2),
+    CacheMetadataClear(cache_clear::CacheMetadataClear),
     Config(config::Config),
     ConfigGet(config_get::ConfigGet),
     ConfigSet(config_set::ConfigSet),
@@ -88,24 +107,32 @@ pub enum YarnCli {
     DlxWithPackages(dlx::DlxWithPackages),
     Dlx(dlx::Dlx),
     Exec(exec::Exec),
+    ExplainList(explain::ExplainList),
+    Explain(explain::Explain),
     Info(info::Info),
     InitWithTemplate(init::InitWithTemplate),
     Init(init::Init),
     Install(install::Install),
+    LicensesCheck(licenses::LicensesCheck),
+    LicensesList(licenses::LicensesList),
     Link(link::Link),
+    LockfileExport(lockfile_export::LockfileExport),
     SetResolution(set_resolution::SetResolution),
     SetVersion(set_version::SetVersion),
     SetVersionFromSources(set_version_from_sources::SetVersionFromSources),
     Node(node::Node),
+    Outdated(outdated::Outdated),
     Pack(pack::Pack),
     PatchCommit(patch_commit::PatchCommit),
     Patch(patch::Patch),
     Rebuild(rebuild::Rebuild),
     Remove(remove::Remove),
     Run(run::Run),
+    Sbom(sbom::Sbom),
     Unlink(unlink::Unlink),
     Unplug(unplug::Unplug),
     Up(up::Up),
+    UpgradeInteractive(upgrade_interactive::UpgradeInteractive),
     WorkspacesFocus(workspaces_focus::WorkspacesFocus),
     WorkspacesForeach(workspaces_foreach::WorkspacesForeach),
     WorkspacesList(workspaces_list::WorkspacesList),
@@ -113,12 +140,43 @@ pub enum YarnCli {
     Why(why::Why),
 }
 
+/// Remove the global `--json` flag if it's set before the command path
+/// (`yarn --json install`); occurrences after it belong to the command itself
+/// (`yarn why foo --json`).
+fn strip_json_flag(args: &mut Vec<String>) -> bool {
+    let json_flag_idx = args.iter()
+        .take_while(|arg| arg.starts_with('-'))
+        .position(|arg| arg == "--json");
+
+    if let Some(json_flag_idx) = json_flag_idx {
+        args.remove(json_flag_idx);
+    }
+
+    json_flag_idx.is_some()
+}
+
 pub async fn run_default(args: Option<Vec<String>>) -> ExitCode {
+    let mut args = args.unwrap_or_else(|| {
+        std::env::args()
+            .skip(1)
+            .collect::<Vec<_>>()
+    });
+
+    // The flag may be set on either side of the explicit path (`yarn --json ./pkg install`)
+    let mut json
+        = strip_json_flag(&mut args);
+
     let BinMeta {
         cwd,
-        args,
+        mut args,
         version,
-    } = extract_bin_meta(args);
+    } = extract_bin_meta(Some(args));
+
+    json |= strip_json_flag(&mut args);
+
+    if json {
+        enable_json_report();
+    }
 
     if let Some(cwd) = cwd {
         cwd.sys_set_current_dir()
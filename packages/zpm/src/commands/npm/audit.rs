@@ -0,0 +1,513 @@
+use std::{collections::{BTreeMap, BTreeSet}, process::ExitCode};
+
+use clipanion::cli;
+use indexmap::IndexMap;
+use serde::Deserialize;
+use serde_with::{serde_as, DefaultOnError};
+use zpm_macro_enum::zpm_enum;
+use zpm_parsers::JsonDocument;
+use zpm_primitives::{Ident, IdentGlob, Locator, Reference};
+use zpm_utils::{tree, AbstractValue, FromFileString, ToFileString};
+
+use crate::{
+    error::Error,
+    http_npm::{self, AuthorizationMode, GetAuthorizationOptions, NpmHttpParams, get_authorization, get_registry},
+    install::InstallState,
+    npm,
+    project::{Project, Workspace},
+};
+
+#[zpm_enum(or_else = |s| Err(Error::InvalidAuditEnvironment(s.to_string())))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive_variants(Debug)]
+pub enum AuditEnvironment {
+    #[literal("all")]
+    All,
+
+    #[literal("production")]
+    Production,
+
+    #[literal("development")]
+    Development,
+}
+
+#[zpm_enum(or_else = |s| Err(Error::InvalidAuditSeverity(s.to_string())))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive_variants(Debug)]
+pub enum AuditSeverity {
+    #[literal("info")]
+    Info,
+
+    #[literal("low")]
+    Low,
+
+    #[literal("moderate")]
+    Moderate,
+
+    #[literal("high")]
+    High,
+
+    #[literal("critical")]
+    Critical,
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+struct NpmAdvisory {
+    id: serde_json::Value,
+
+    #[serde(default)]
+    url: String,
+
+    #[serde(default)]
+    title: String,
+
+    severity: AuditSeverity,
+
+    #[serde(default)]
+    #[serde_as(deserialize_as = "DefaultOnError")]
+    vulnerable_versions: Option<String>,
+}
+
+#[derive(Debug)]
+struct AuditFinding {
+    id: String,
+    url: String,
+    title: String,
+    severity: AuditSeverity,
+    vulnerable_versions: String,
+    tree_versions: BTreeSet<zpm_semver::Version>,
+    dependents: BTreeSet<Locator>,
+}
+
+/// The dependents of each version of each package found in the dependency tree
+type AuditedPackages = BTreeMap<Ident, BTreeMap<zpm_semver::Version, BTreeSet<Locator>>>;
+
+/// Perform a vulnerability audit against the installed packages
+///
+/// This command checks for known security reports on the packages you use. The reports are by default extracted from the npm registry, and may or may not be relevant to your actual program (not all vulnerabilities affect all code paths).
+///
+/// For consistency with our other commands the default is to only check the direct dependencies for the active workspace. To extend this search to all workspaces, use `-A,--all`. To extend this search to both direct and transitive dependencies, use `-R,--recursive`.
+///
+/// Applying the `--severity` flag will limit the audit table to vulnerabilities of the corresponding severity and above. Valid values are `info`, `low`, `moderate`, `high` and `critical`.
+///
+/// If the `--json` flag is set, Yarn will print each advisory as an NDJSON stream. Regardless of this flag, the process will exit with a non-zero exit code if a report is found for the selected packages.
+///
+/// If certain packages produce false positives for a particular environment, the `--exclude` flag can be used to exclude any number of packages from the audit. This can also be set in the configuration file with the `npmAuditExcludePackages` option.
+///
+/// If particular advisories are needed to be ignored, the `--ignore` flag can be used with advisory IDs to ignore any number of advisories in the audit report. This can also be set in the configuration file with the `npmAuditIgnoreAdvisories` option.
+///
+/// To understand the dependency tree requiring vulnerable packages, check the raw report with the `--json` flag or use `yarn why package` to get more information as to who depends on them.
+///
+#[cli::command]
+#[cli::path("npm", "audit")]
+#[cli::category("Npm-related commands")]
+pub struct Audit {
+    /// Audit dependencies from all workspaces
+    #[cli::option("-A,--all", default = false)]
+    all: bool,
+
+    /// Audit transitive dependencies as well
+    #[cli::option("-R,--recursive", default = false)]
+    recursive: bool,
+
+    /// Which environments to cover (all, production, or development)
+    #[cli::option("--environment", default = AuditEnvironment::All)]
+    environment: AuditEnvironment,
+
+    /// Format the output as an NDJSON stream
+    #[cli::option("--json", default = false)]
+    json: bool,
+
+    /// Also report deprecated packages
+    #[cli::option("--deprecations", default = true)]
+    deprecations: bool,
+
+    /// Minimal severity requested for packages to be displayed
+    #[cli::option("--severity", default = AuditSeverity::Info)]
+    severity: AuditSeverity,
+
+    /// Array of glob patterns of packages to exclude from audit
+    #[cli::option("--exclude", default = vec![])]
+    exclude: Vec<IdentGlob>,
+
+    /// Array of advisory IDs to ignore
+    #[cli::option("--ignore", default = vec![])]
+    ignore: Vec<String>,
+}
+
+impl Audit {
+    pub async fn execute(&self) -> Result<ExitCode, Error> {
+        let mut project
+            = Project::new(None).await?;
+
+        project.lazy_install().await?;
+
+        let install_state
+            = project.install_state.as_ref()
+                .ok_or(Error::InstallStateNotFound)?;
+
+        let workspaces = if self.all {
+            project.workspaces.iter().collect::<Vec<_>>()
+        } else {
+            vec![project.active_workspace()?]
+        };
+
+        let packages
+            = self.collect_packages(&project, install_state, &workspaces);
+
+        let mut findings
+            = self.fetch_advisories(&project, &packages).await?;
+
+        if self.deprecations {
+            findings.extend(self.fetch_deprecations(&project, &packages).await?);
+        }
+
+        let ignored_advisories = self.ignore.iter()
+            .chain(project.config.settings.npm_audit_ignore_advisories.iter().map(|setting| &setting.value))
+            .collect::<BTreeSet<_>>();
+
+        let mut findings_by_ident: BTreeMap<Ident, Vec<AuditFinding>>
+            = BTreeMap::new();
+
+        for (ident, finding) in findings {
+            if finding.severity < self.severity || ignored_advisories.contains(&&finding.id) {
+                continue;
+            }
+
+            findings_by_ident.entry(ident)
+                .or_default()
+                .push(finding);
+        }
+
+        if findings_by_ident.is_empty() {
+            if !self.json {
+                println!("No audit suggestions");
+            }
+
+            return Ok(ExitCode::SUCCESS);
+        }
+
+        let mut root_children
+            = vec![];
+
+        for (ident, findings) in findings_by_ident {
+            for finding in findings {
+                root_children.push(tree::Node {
+                    label: None,
+                    value: Some(AbstractValue::new(ident.clone())),
+                    children: Some(tree::TreeNodeChildren::Map(render_finding(finding))),
+                });
+            }
+        }
+
+        let root_node = tree::Node {
+            label: None,
+            value: None,
+            children: Some(tree::TreeNodeChildren::Vec(root_children)),
+        };
+
+        let rendering
+            = tree::TreeRenderer::new()
+                .render(&root_node, self.json);
+
+        print!("{}", rendering);
+
+        Ok(ExitCode::FAILURE)
+    }
+
+    fn collect_packages(&self, project: &Project, install_state: &InstallState, workspaces: &[&Workspace]) -> AuditedPackages {
+        let excluded_packages = self.exclude.iter()
+            .chain(project.config.settings.npm_audit_exclude_packages.iter().map(|setting| &setting.value))
+            .collect::<Vec<_>>();
+
+        let mut packages: AuditedPackages
+            = BTreeMap::new();
+
+        let mut queue: Vec<(Locator, Locator)>
+            = vec![];
+
+        for workspace in workspaces {
+            let workspace_locator
+                = workspace.locator();
+
+            let Some(resolution) = install_state.resolution_tree.locator_resolutions.get(&workspace_locator) else {
+                continue;
+            };
+
+            for (ident, descriptor) in &resolution.dependencies {
+                if resolution.peer_dependencies.contains_key(ident) {
+                    continue;
+                }
+
+                let is_production
+                    = workspace.manifest.remote.dependencies.contains_key(ident)
+                        || workspace.manifest.remote.optional_dependencies.contains_key(ident);
+
+                let is_development
+                    = workspace.manifest.dev_dependencies.contains_key(ident);
+
+                let is_selected = match self.environment {
+                    AuditEnvironment::All => true,
+                    AuditEnvironment::Production => is_production || !is_development,
+                    AuditEnvironment::Development => is_development,
+                };
+
+                if !is_selected {
+                    continue;
+                }
+
+                if let Some(locator) = install_state.resolution_tree.descriptor_to_locator.get(descriptor) {
+                    queue.push((locator.clone(), workspace_locator.clone()));
+                }
+            }
+        }
+
+        let mut seen
+            = BTreeSet::new();
+
+        while let Some((locator, parent)) = queue.pop() {
+            if let Some((ident, version)) = extract_audited_version(&locator.physical_locator()) {
+                if !excluded_packages.iter().any(|glob| glob.check(&ident)) {
+                    packages.entry(ident)
+                        .or_default()
+                        .entry(version)
+                        .or_default()
+                        .insert(parent.physical_locator());
+                }
+            }
+
+            if !self.recursive || !seen.insert(locator.clone()) {
+                continue;
+            }
+
+            let Some(resolution) = install_state.resolution_tree.locator_resolutions.get(&locator) else {
+                continue;
+            };
+
+            for descriptor in resolution.dependencies.values() {
+                if let Some(dependency) = install_state.resolution_tree.descriptor_to_locator.get(descriptor) {
+                    queue.push((dependency.clone(), locator.clone()));
+                }
+            }
+        }
+
+        packages
+    }
+
+    async fn fetch_advisories(&self, project: &Project, packages: &AuditedPackages) -> Result<Vec<(Ident, AuditFinding)>, Error> {
+        if packages.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let registry = match project.config.settings.npm_audit_registry.value.as_deref() {
+            Some(registry) => registry.strip_suffix('/').unwrap_or(registry),
+            None => get_registry(&project.config, None, false)?,
+        };
+
+        let authorization
+            = get_authorization(&GetAuthorizationOptions {
+                configuration: &project.config,
+                http_client: &project.http_client,
+                registry,
+                ident: None,
+                auth_mode: AuthorizationMode::RespectConfiguration,
+                allow_oidc: false,
+            }).await?;
+
+        let payload = packages.iter()
+            .map(|(ident, versions)| (ident, versions.keys().collect::<Vec<_>>()))
+            .collect::<BTreeMap<_, _>>();
+
+        let response = http_npm::post(&NpmHttpParams {
+            http_client: &project.http_client,
+            registry,
+            path: "/-/npm/v1/security/advisories/bulk",
+            authorization: authorization.as_deref(),
+            otp: None,
+        }, JsonDocument::to_string(&payload)?).await?;
+
+        let body
+            = response.bytes().await?;
+
+        let advisories: BTreeMap<Ident, Vec<NpmAdvisory>>
+            = JsonDocument::hydrate_from_slice(&body[..])?;
+
+        let mut findings
+            = vec![];
+
+        for (ident, advisories) in advisories {
+            let Some(versions) = packages.get(&ident) else {
+                continue;
+            };
+
+            for advisory in advisories {
+                let vulnerable_range
+                    = advisory.vulnerable_versions.as_deref()
+                        .and_then(|range| zpm_semver::Range::from_file_string(range).ok());
+
+                let affected_versions = versions.iter()
+                    .filter(|(version, _)| vulnerable_range.as_ref().is_none_or(|range| range.check(version)))
+                    .collect::<Vec<_>>();
+
+                if affected_versions.is_empty() {
+                    continue;
+                }
+
+                let id = match advisory.id {
+                    serde_json::Value::String(id) => id,
+                    id => id.to_string(),
+                };
+
+                findings.push((ident.clone(), AuditFinding {
+                    id,
+                    url: advisory.url,
+                    title: advisory.title,
+                    severity: advisory.severity,
+                    vulnerable_versions: advisory.vulnerable_versions.unwrap_or_else(|| "*".to_string()),
+                    tree_versions: affected_versions.iter().map(|(version, _)| (*version).clone()).collect(),
+                    dependents: affected_versions.iter().flat_map(|(_, dependents)| dependents.iter().cloned()).collect(),
+                }));
+            }
+        }
+
+        Ok(findings)
+    }
+
+    async fn fetch_deprecations(&self, project: &Project, packages: &AuditedPackages) -> Result<Vec<(Ident, AuditFinding)>, Error> {
+        let futures = packages.iter()
+            .map(|(ident, versions)| fetch_deprecation(project, ident, versions));
+
+        let findings
+            = futures::future::join_all(futures).await
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(findings.into_iter().flatten().collect())
+    }
+}
+
+async fn fetch_deprecation(project: &Project, ident: &Ident, versions: &BTreeMap<zpm_semver::Version, BTreeSet<Locator>>) -> Result<Option<(Ident, AuditFinding)>, Error> {
+    let registry
+        = get_registry(&project.config, ident.scope(), false)?;
+
+    let authorization
+        = get_authorization(&GetAuthorizationOptions {
+            configuration: &project.config,
+            http_client: &project.http_client,
+            registry,
+            ident: Some(ident),
+            auth_mode: AuthorizationMode::RespectConfiguration,
+            allow_oidc: false,
+        }).await?;
+
+    let bytes = http_npm::get(&NpmHttpParams {
+        http_client: &project.http_client,
+        registry,
+        path: &npm::registry_url_for_all_versions(ident),
+        authorization: authorization.as_deref(),
+        otp: None,
+    }).await?;
+
+    #[serde_as]
+    #[derive(Deserialize)]
+    struct VersionMetadata {
+        #[serde(default)]
+        #[serde_as(deserialize_as = "DefaultOnError")]
+        deprecated: Option<String>,
+    }
+
+    #[derive(Deserialize)]
+    struct RegistryMetadata {
+        versions: BTreeMap<zpm_semver::Version, VersionMetadata>,
+    }
+
+    let registry_data: RegistryMetadata
+        = JsonDocument::hydrate_from_slice(&bytes[..])?;
+
+    // An empty string is how the registry represents a package that got un-deprecated, but
+    // whitespace-only messages are still deprecations (just without a useful message).
+    let deprecated_versions = versions.iter()
+        .filter_map(|(version, dependents)| {
+            registry_data.versions.get(version)
+                .and_then(|metadata| metadata.deprecated.as_deref())
+                .filter(|message| !message.is_empty())
+                .map(|message| (version, dependents, message))
+        })
+        .collect::<Vec<_>>();
+
+    let Some((_, _, message)) = deprecated_versions.last() else {
+        return Ok(None);
+    };
+
+    let title = match message.trim() {
+        "" => "This package has been deprecated.".to_string(),
+        message => message.to_string(),
+    };
+
+    let vulnerable_versions
+        = deprecated_versions.iter()
+            .map(|(version, _, _)| version.to_file_string())
+            .collect::<Vec<_>>()
+            .join(" || ");
+
+    Ok(Some((ident.clone(), AuditFinding {
+        id: format!("{} (deprecation)", ident.to_file_string()),
+        url: format!("https://www.npmjs.com/package/{}", ident.to_file_string()),
+        title,
+        severity: AuditSeverity::Moderate,
+        vulnerable_versions,
+        tree_versions: deprecated_versions.iter().map(|(version, _, _)| (*version).clone()).collect(),
+        dependents: deprecated_versions.iter().flat_map(|(_, dependents, _)| dependents.iter().cloned()).collect(),
+    })))
+}
+
+fn extract_audited_version(locator: &Locator) -> Option<(Ident, zpm_semver::Version)> {
+    match &locator.reference {
+        Reference::Shorthand(params)
+            => Some((locator.ident.clone(), params.version.clone())),
+
+        Reference::Registry(params)
+            => Some((params.ident.clone(), params.version.clone())),
+
+        Reference::Patch(_)
+            => locator.reference.inner_locator().and_then(extract_audited_version),
+
+        _ => None,
+    }
+}
+
+fn render_finding<'a>(finding: AuditFinding) -> IndexMap<String, tree::Node<'a>> {
+    let fields: [(&str, AbstractValue<'a>); 5] = [
+        ("ID", AbstractValue::new(finding.id)),
+        ("Issue", AbstractValue::new(finding.title)),
+        ("URL", AbstractValue::new(finding.url)),
+        ("Severity", AbstractValue::new(finding.severity)),
+        ("Vulnerable Versions", AbstractValue::new(finding.vulnerable_versions)),
+    ];
+
+    let mut children
+        = IndexMap::new();
+
+    for (label, value) in fields {
+        children.insert(label.to_string(), tree::Node {
+            label: Some(label.to_string()),
+            value: Some(value),
+            children: None,
+        });
+    }
+
+    children.insert("Tree Versions".to_string(), tree::Node {
+        label: Some("Tree Versions".to_string()),
+        value: None,
+        children: Some(tree::TreeNodeChildren::Vec(finding.tree_versions.into_iter().map(tree::Node::new_value).collect())),
+    });
+
+    children.insert("Dependents".to_string(), tree::Node {
+        label: Some("Dependents".to_string()),
+        value: None,
+        children: Some(tree::TreeNodeChildren::Vec(finding.dependents.into_iter().map(tree::Node::new_value).collect())),
+    });
+
+    children
+}
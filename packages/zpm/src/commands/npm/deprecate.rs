@@ -0,0 +1,132 @@
+use clipanion::cli;
+use serde_json::json;
+use zpm_parsers::JsonDocument;
+use zpm_primitives::{Descriptor, Range};
+use zpm_utils::{DataType, FromFileString, ToFileString, ToHumanString};
+
+use crate::{
+    error::Error,
+    http_npm::{self, AuthorizationMode, GetAuthorizationOptions, NpmHttpParams},
+    npm,
+    project::Project,
+    report::{current_report, with_report_result, StreamReport, StreamReportConfig},
+};
+
+/// Deprecate versions of a package
+///
+/// This command marks all the versions of the given package that match the given range as deprecated on the registry; the message will be
+/// displayed to the users installing them. Passing an empty message removes the deprecation.
+///
+/// Use `--dry-run` to list the versions that would be affected without modifying anything.
+///
+#[cli::command]
+#[cli::path("npm", "deprecate")]
+#[cli::category("Npm-related commands")]
+pub struct Deprecate {
+    /// The OTP token to use with the command
+    #[cli::option("--otp")]
+    otp: Option<String>,
+
+    /// Print the versions that would be deprecated without deprecating them
+    #[cli::option("--dry-run", default = false)]
+    dry_run: bool,
+
+    /// The package and range to deprecate, as in `name@^1.0.0`
+    descriptor: Descriptor,
+
+    /// The deprecation message
+    message: String,
+}
+
+impl Deprecate {
+    pub async fn execute(&self) -> Result<(), Error> {
+        let project
+            = Project::new(None).await?;
+
+        let Range::AnonymousSemver(semver_range) = &self.descriptor.range else {
+            return Err(Error::InvalidRange(self.descriptor.range.to_file_string()));
+        };
+
+        let ident
+            = &self.descriptor.ident;
+
+        let registry
+            = http_npm::get_registry(&project.config, ident.scope(), true)?;
+
+        let report = StreamReport::new(StreamReportConfig {
+            ..StreamReportConfig::from_config(&project.config)
+        });
+
+        with_report_result(report, async {
+            let authorization
+                = http_npm::get_authorization(&GetAuthorizationOptions {
+                    configuration: &project.config,
+                    http_client: &project.http_client,
+                    registry,
+                    ident: Some(ident),
+                    auth_mode: AuthorizationMode::AlwaysAuthenticate,
+                    allow_oidc: false,
+                }).await?
+                .ok_or_else(|| Error::AuthenticationError("No authentication configured".to_string()))?;
+
+            let package_path
+                = npm::registry_url_for_all_versions(ident);
+
+            let params = NpmHttpParams {
+                http_client: &project.http_client,
+                registry,
+                path: &package_path,
+                authorization: Some(&authorization),
+                otp: self.otp.as_deref(),
+            };
+
+            let bytes = http_npm::get(&NpmHttpParams {
+                path: &format!("{}?write=true", package_path),
+                ..params
+            }).await?;
+
+            let mut packument: serde_json::Value
+                = JsonDocument::hydrate_from_slice(&bytes[..])?;
+
+            let versions = packument["versions"].as_object_mut()
+                .map(|versions| versions.iter_mut()
+                    .filter_map(|(version, manifest)| zpm_semver::Version::from_file_string(version).ok().map(|version| (version, manifest)))
+                    .filter(|(version, _)| semver_range.range.check(version))
+                    .map(|(version, manifest)| {
+                        manifest["deprecated"] = json!(self.message);
+                        version
+                    })
+                    .collect::<Vec<_>>())
+                .unwrap_or_default();
+
+            if versions.is_empty() {
+                return Err(Error::NoCandidatesFound(self.descriptor.range.clone()));
+            }
+
+            let verb = match self.dry_run {
+                true => "Would deprecate",
+                false => "Deprecating",
+            };
+
+            current_report().await.as_ref().map(|report| {
+                report.info(format!("{} the following versions of {}:", verb, ident.to_print_string()));
+
+                for version in &versions {
+                    report.info(format!("- {}", version.to_print_string()));
+                }
+            });
+
+            if self.dry_run {
+                return Ok(());
+            }
+
+            http_npm::put(&params, packument.to_string()).await?;
+
+            current_report().await.as_ref().map(|report| {
+                report.info(format!("Deprecated {} version(s) on {}", versions.len(), DataType::Url.colorize(registry)));
+            });
+
+            Ok(())
+        }).await
+    }
+}
@@ -0,0 +1,275 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use clipanion::cli;
+use indexmap::IndexMap;
+use serde::Deserialize;
+use serde_with::{serde_as, MapSkipError};
+use zpm_parsers::JsonDocument;
+use zpm_primitives::{AnonymousSemverRange, Ident};
+use zpm_utils::{tree, AbstractValue, FromFileString, ToFileString};
+
+use crate::{
+    error::Error,
+    http_npm::{self, AuthorizationMode, GetAuthorizationOptions, NpmHttpParams},
+    npm,
+    project::Project,
+    resolvers::npm::is_version_approved,
+};
+
+/// Show information about a package from the registry
+///
+/// This command fetches the metadata of the given package from the npm registry and prints the information about the version that would be
+/// selected for the given range or tag (or `latest` if omitted) along with the package dist-tags, versions, maintainers, and publication times.
+/// The registry and credentials are selected the same way as during installs, so the `npmScopes` and `npmRegistries` settings are honored.
+///
+/// Specific fields can be selected by listing them after the package name; nested fields can be accessed using dots (`dist.tarball`).
+///
+#[cli::command]
+#[cli::path("npm", "info")]
+#[cli::category("Npm-related commands")]
+pub struct NpmInfo {
+    /// Format the output as a JSON object
+    #[cli::option("--json", default = false)]
+    json: bool,
+
+    /// The package to query, optionally followed by a range or a tag
+    package: String,
+
+    /// The fields to print
+    fields: Vec<String>,
+}
+
+enum Selector {
+    Range(zpm_semver::Range),
+    Tag(String),
+}
+
+#[serde_as]
+#[derive(Deserialize)]
+struct RegistryMetadata {
+    #[serde(default, rename(deserialize = "dist-tags"))]
+    #[serde_as(as = "MapSkipError<_, _>")]
+    dist_tags: BTreeMap<String, zpm_semver::Version>,
+    #[serde(default)]
+    #[serde_as(as = "MapSkipError<_, _>")]
+    time: BTreeMap<String, DateTime<Utc>>,
+    #[serde(default)]
+    #[serde_as(as = "MapSkipError<_, _>")]
+    versions: BTreeMap<zpm_semver::Version, serde_json::Value>,
+}
+
+impl NpmInfo {
+    pub async fn execute(&self) -> Result<(), Error> {
+        let project
+            = Project::new(None).await?;
+
+        let (ident, selector)
+            = self.parse_package()?;
+
+        let registry
+            = http_npm::get_registry(&project.config, ident.scope(), false)?;
+
+        let authorization
+            = http_npm::get_authorization(&GetAuthorizationOptions {
+                configuration: &project.config,
+                http_client: &project.http_client,
+                registry,
+                ident: Some(&ident),
+                auth_mode: AuthorizationMode::RespectConfiguration,
+                allow_oidc: false,
+            }).await?;
+
+        let bytes = http_npm::get_metadata(&NpmHttpParams {
+            http_client: &project.http_client,
+            registry,
+            path: &npm::registry_url_for_all_versions(&ident),
+            authorization: authorization.as_deref(),
+            otp: None,
+        }).await?;
+
+        let packument: serde_json::Value
+            = JsonDocument::hydrate_from_slice(&bytes[..])?;
+
+        let registry_data: RegistryMetadata
+            = JsonDocument::hydrate_from_slice(&bytes[..])?;
+
+        let version
+            = select_version(&project, &ident, &selector, &registry_data)?;
+
+        let fields
+            = build_fields(&packument, &registry_data, &version);
+
+        let fields = match self.fields.is_empty() {
+            true => fields.into_iter()
+                .filter(|(key, _)| key != "readme" && !key.starts_with('_'))
+                .collect::<IndexMap<_, _>>(),
+
+            false => self.fields.iter()
+                .map(|field| (field.clone(), lookup_field(&fields, &packument, field)))
+                .collect::<IndexMap<_, _>>(),
+        };
+
+        if self.json {
+            println!("{}", serde_json::Value::Object(fields.into_iter().collect()));
+            return Ok(());
+        }
+
+        let root_node = tree::Node {
+            label: None,
+            value: None,
+            children: Some(tree::TreeNodeChildren::Map(fields.into_iter()
+                .map(|(key, value)| (key.clone(), value_to_node(key, value)))
+                .collect())),
+        };
+
+        let rendering
+            = tree::TreeRenderer::new()
+                .render(&root_node, false);
+
+        print!("{}", rendering);
+
+        Ok(())
+    }
+
+    fn parse_package(&self) -> Result<(Ident, Selector), Error> {
+        let (name, selector)
+            = npm::split_package_selector(&self.package);
+
+        let ident
+            = Ident::from_file_string(name)
+                .map_err(|_| Error::InvalidIdent(name.to_string()))?;
+
+        let selector = match selector.unwrap_or_default() {
+            "" => Selector::Tag("latest".to_string()),
+            selector => match zpm_semver::Range::from_file_string(selector) {
+                Ok(range) => Selector::Range(range),
+                Err(_) => Selector::Tag(selector.to_string()),
+            },
+        };
+
+        Ok((ident, selector))
+    }
+}
+
+/**
+ * Mirrors the logic used by the npm resolver, so that the printed version is
+ * the one that `yarn add` would pick (including the `npmMinimalAgeGate`
+ * restrictions).
+ */
+fn select_version(project: &Project, ident: &Ident, selector: &Selector, registry_data: &RegistryMetadata) -> Result<zpm_semver::Version, Error> {
+    let now
+        = Utc::now();
+
+    let is_approved = |version: &zpm_semver::Version| {
+        let release_time
+            = registry_data.time.get(&version.to_file_string());
+
+        is_version_approved(project, &now, ident, version, release_time)
+    };
+
+    match selector {
+        Selector::Range(range) => {
+            registry_data.versions.keys()
+                .rev()
+                .find(|version| range.check(version) && is_approved(version))
+                .cloned()
+                .ok_or_else(|| Error::NoCandidatesFound(AnonymousSemverRange {range: range.clone()}.into()))
+        },
+
+        Selector::Tag(tag) => {
+            let tagged_version
+                = registry_data.dist_tags.get(tag)
+                    .ok_or_else(|| Error::TagNotFound(tag.clone()))?;
+
+            registry_data.versions.keys()
+                .rev()
+                .filter(|version| version <= &tagged_version)
+                .filter(|version| !version.rc.is_some() || tagged_version.rc.is_some())
+                .find(|version| is_approved(version))
+                .cloned()
+                .ok_or_else(|| Error::NoCandidatesFound(AnonymousSemverRange {range: zpm_semver::Range::lte(tagged_version.clone())}.into()))
+        },
+    }
+}
+
+/**
+ * The printed fields are those from the manifest of the selected version,
+ * followed by the package-wide information from the registry. Only the
+ * creation, last modification, and selected version publication times are
+ * kept, as the full history would be too verbose.
+ */
+fn build_fields(packument: &serde_json::Value, registry_data: &RegistryMetadata, version: &zpm_semver::Version) -> IndexMap<String, serde_json::Value> {
+    let mut fields = match registry_data.versions.get(version) {
+        Some(serde_json::Value::Object(manifest)) => manifest.clone().into_iter().collect::<IndexMap<_, _>>(),
+        _ => IndexMap::new(),
+    };
+
+    let version_string
+        = version.to_file_string();
+
+    fields.insert("dist-tags".to_string(), serde_json::json!(registry_data.dist_tags.iter()
+        .map(|(tag, version)| (tag.clone(), version.to_file_string()))
+        .collect::<BTreeMap<_, _>>()));
+
+    fields.insert("versions".to_string(), serde_json::json!(registry_data.versions.keys()
+        .map(|version| version.to_file_string())
+        .collect::<Vec<_>>()));
+
+    if let Some(maintainers) = packument.get("maintainers") {
+        fields.insert("maintainers".to_string(), maintainers.clone());
+    }
+
+    let time = ["created", "modified", version_string.as_str()].into_iter()
+        .filter_map(|key| registry_data.time.get(key).map(|time| (key.to_string(), serde_json::json!(time.to_rfc3339()))))
+        .collect::<serde_json::Map<_, _>>();
+
+    fields.insert("time".to_string(), serde_json::Value::Object(time));
+
+    fields
+}
+
+fn lookup_field(fields: &IndexMap<String, serde_json::Value>, packument: &serde_json::Value, field: &str) -> serde_json::Value {
+    let (key, rest) = match field.split_once('.') {
+        Some((key, rest)) => (key, Some(rest)),
+        None => (field, None),
+    };
+
+    let Some(value) = fields.get(key).or_else(|| packument.get(key)) else {
+        return serde_json::Value::Null;
+    };
+
+    let pointer = rest
+        .map(|rest| format!("/{}", rest.replace('.', "/")))
+        .unwrap_or_default();
+
+    value.pointer(&pointer)
+        .cloned()
+        .unwrap_or(serde_json::Value::Null)
+}
+
+fn value_to_node<'a>(label: String, value: serde_json::Value) -> tree::Node<'a> {
+    match value {
+        serde_json::Value::Object(map) if !map.is_empty() => tree::Node {
+            label: Some(label),
+            value: None,
+            children: Some(tree::TreeNodeChildren::Map(map.into_iter()
+                .map(|(key, value)| (key.clone(), value_to_node(key, value)))
+                .collect())),
+        },
+
+        serde_json::Value::Array(items) if !items.is_empty() => tree::Node {
+            label: Some(label),
+            value: None,
+            children: Some(tree::TreeNodeChildren::Vec(items.into_iter()
+                .map(tree::Node::new_value)
+                .collect())),
+        },
+
+        value => tree::Node {
+            label: Some(label),
+            value: Some(AbstractValue::new(value)),
+            children: None,
+        },
+    }
+}
@@ -320,10 +320,10 @@ async fn get_credentials(is_token: bool) -> Result<Credentials, Error> {
             .expect("No report set");
 
     let username
-        = report.prompt(PromptType::Input("Username".to_string())).await;
+        = report.prompt(PromptType::Input("Username".to_string())).await?;
 
     let password
-        = report.prompt(PromptType::Password(if is_token {"Token"} else {"Password"}.to_string())).await;
+        = report.prompt(PromptType::Password(if is_token {"Token"} else {"Password"}.to_string())).await?;
 
     Ok(Credentials {
         username,
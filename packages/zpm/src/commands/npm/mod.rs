@@ -1,5 +1,10 @@
+pub mod audit;
+pub mod deprecate;
+pub mod info;
 pub mod login;
 pub mod logout_all;
 pub mod logout;
 pub mod publish;
+pub mod tag;
+pub mod unpublish;
 pub mod whoami;
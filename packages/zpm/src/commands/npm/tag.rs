@@ -0,0 +1,253 @@
+use std::collections::BTreeMap;
+
+use clipanion::cli;
+use serde_json::json;
+use zpm_parsers::JsonDocument;
+use zpm_primitives::Ident;
+use zpm_utils::{DataType, FromFileString, ToFileString, ToHumanString};
+
+use crate::{
+    error::Error,
+    http_npm::{self, AuthorizationMode, GetAuthorizationOptions, NpmHttpParams},
+    npm,
+    project::Project,
+};
+
+/// List all dist-tags of a package
+///
+/// This command lists all the dist-tags of the current workspace package from the registry. The registry is selected the same way as when
+/// publishing (`npmPublishRegistry`, then `npmRegistryServer`), honoring the `npmScopes` and `npmRegistries` settings.
+///
+#[cli::command]
+#[cli::path("npm", "tag", "list")]
+#[cli::category("Npm-related commands")]
+pub struct NpmTagList {
+    /// Format the output as an NDJSON stream
+    #[cli::option("--json", default = false)]
+    json: bool,
+}
+
+impl NpmTagList {
+    pub async fn execute(&self) -> Result<(), Error> {
+        let project
+            = Project::new(None).await?;
+
+        let ident = project.active_workspace()?.manifest.name.clone()
+            .ok_or(Error::MissingPackageName)?;
+
+        list_tags(&project, &ident, self.json).await
+    }
+}
+
+/// List all dist-tags of a package
+///
+/// This command lists all the dist-tags of the given package from the registry. The registry is selected the same way as when publishing
+/// (`npmPublishRegistry`, then `npmRegistryServer`), honoring the `npmScopes` and `npmRegistries` settings.
+///
+#[cli::command]
+#[cli::path("npm", "tag", "list")]
+#[cli::category("Npm-related commands")]
+pub struct NpmTagListPackage {
+    /// Format the output as an NDJSON stream
+    #[cli::option("--json", default = false)]
+    json: bool,
+
+    /// The package whose dist-tags should be listed
+    package: Ident,
+}
+
+impl NpmTagListPackage {
+    pub async fn execute(&self) -> Result<(), Error> {
+        let project
+            = Project::new(None).await?;
+
+        list_tags(&project, &self.package, self.json).await
+    }
+}
+
+/// Add a dist-tag to a package version
+///
+/// This command sets the given dist-tag to point to the given version of a package on the registry, replacing its previous target if any. It
+/// can for example be used to promote a prerelease from `next` to `latest` without having to publish it again.
+///
+#[cli::command]
+#[cli::path("npm", "tag", "add")]
+#[cli::category("Npm-related commands")]
+pub struct NpmTagAdd {
+    /// The OTP token to use with the command
+    #[cli::option("--otp")]
+    otp: Option<String>,
+
+    /// The package version to tag, as in `name@1.2.3`
+    package: String,
+
+    /// The dist-tag to set
+    tag: String,
+}
+
+impl NpmTagAdd {
+    pub async fn execute(&self) -> Result<(), Error> {
+        let project
+            = Project::new(None).await?;
+
+        let (name, version) = match npm::split_package_selector(&self.package) {
+            (name, Some(version)) if !version.is_empty() => (name, version),
+            _ => return Err(Error::InvalidDistTagTarget(self.package.clone())),
+        };
+
+        let ident
+            = Ident::from_file_string(name)
+                .map_err(|_| Error::InvalidIdent(name.to_string()))?;
+
+        let version
+            = zpm_semver::Version::from_file_string(version)?;
+
+        validate_tag(&self.tag)?;
+
+        let registry
+            = http_npm::get_registry(&project.config, ident.scope(), true)?;
+
+        let dist_tags
+            = fetch_tags(&project, registry, &ident).await?;
+
+        if dist_tags.get(&self.tag) == Some(&version) {
+            println!("Tag {} already points to {}; skipping.", DataType::Code.colorize(&self.tag), version.to_print_string());
+            return Ok(());
+        }
+
+        let authorization
+            = get_write_authorization(&project, registry, &ident).await?;
+
+        http_npm::put(&NpmHttpParams {
+            http_client: &project.http_client,
+            registry,
+            path: &npm::registry_url_for_dist_tag(&ident, &self.tag),
+            authorization: authorization.as_deref(),
+            otp: self.otp.as_deref(),
+        }, json!(version.to_file_string()).to_string()).await?;
+
+        println!("Tag {} added to version {} of package {}", DataType::Code.colorize(&self.tag), version.to_print_string(), ident.to_print_string());
+
+        Ok(())
+    }
+}
+
+/// Remove a dist-tag from a package
+///
+/// This command removes the given dist-tag from a package on the registry. The `latest` dist-tag cannot be removed, as the registry relies on
+/// it to select the default version of the package.
+///
+#[cli::command]
+#[cli::path("npm", "tag", "remove")]
+#[cli::category("Npm-related commands")]
+pub struct NpmTagRemove {
+    /// The OTP token to use with the command
+    #[cli::option("--otp")]
+    otp: Option<String>,
+
+    /// The package whose dist-tag should be removed
+    package: Ident,
+
+    /// The dist-tag to remove
+    tag: String,
+}
+
+impl NpmTagRemove {
+    pub async fn execute(&self) -> Result<(), Error> {
+        let project
+            = Project::new(None).await?;
+
+        if self.tag == "latest" {
+            return Err(Error::CannotRemoveLatestDistTag);
+        }
+
+        let registry
+            = http_npm::get_registry(&project.config, self.package.scope(), true)?;
+
+        let dist_tags
+            = fetch_tags(&project, registry, &self.package).await?;
+
+        if !dist_tags.contains_key(&self.tag) {
+            return Err(Error::TagNotFound(self.tag.clone()));
+        }
+
+        let authorization
+            = get_write_authorization(&project, registry, &self.package).await?;
+
+        http_npm::delete(&NpmHttpParams {
+            http_client: &project.http_client,
+            registry,
+            path: &npm::registry_url_for_dist_tag(&self.package, &self.tag),
+            authorization: authorization.as_deref(),
+            otp: self.otp.as_deref(),
+        }).await?;
+
+        println!("Tag {} removed from package {}", DataType::Code.colorize(&self.tag), self.package.to_print_string());
+
+        Ok(())
+    }
+}
+
+/// The registry refuses tags that could be mistaken for ranges, since
+/// `name@tag` must be unambiguous.
+fn validate_tag(tag: &str) -> Result<(), Error> {
+    if zpm_semver::Range::from_file_string(tag).is_ok() {
+        return Err(Error::InvalidDistTag(tag.to_string()));
+    }
+
+    Ok(())
+}
+
+async fn list_tags(project: &Project, ident: &Ident, json: bool) -> Result<(), Error> {
+    let registry
+        = http_npm::get_registry(&project.config, ident.scope(), true)?;
+
+    let dist_tags
+        = fetch_tags(project, registry, ident).await?;
+
+    for (tag, version) in dist_tags {
+        match json {
+            true => println!("{}", json!({
+                "tag": tag,
+                "version": version.to_file_string(),
+            })),
+
+            false => println!("{}: {}", DataType::Code.colorize(&tag), version.to_print_string()),
+        }
+    }
+
+    Ok(())
+}
+
+async fn fetch_tags(project: &Project, registry: &str, ident: &Ident) -> Result<BTreeMap<String, zpm_semver::Version>, Error> {
+    let authorization
+        = http_npm::get_authorization(&GetAuthorizationOptions {
+            configuration: &project.config,
+            http_client: &project.http_client,
+            registry,
+            ident: Some(ident),
+            auth_mode: AuthorizationMode::RespectConfiguration,
+            allow_oidc: false,
+        }).await?;
+
+    let bytes = http_npm::get(&NpmHttpParams {
+        http_client: &project.http_client,
+        registry,
+        path: &npm::registry_url_for_dist_tags(ident),
+        authorization: authorization.as_deref(),
+        otp: None,
+    }).await?;
+
+    Ok(JsonDocument::hydrate_from_slice(&bytes[..])?)
+}
+
+async fn get_write_authorization(project: &Project, registry: &str, ident: &Ident) -> Result<Option<String>, Error> {
+    http_npm::get_authorization(&GetAuthorizationOptions {
+        configuration: &project.config,
+        http_client: &project.http_client,
+        registry,
+        ident: Some(ident),
+        auth_mode: AuthorizationMode::AlwaysAuthenticate,
+        allow_oidc: false,
+    }).await
+}
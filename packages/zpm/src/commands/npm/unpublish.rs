@@ -0,0 +1,217 @@
+use clipanion::cli;
+use serde::Deserialize;
+use serde_json::json;
+use zpm_parsers::JsonDocument;
+use zpm_primitives::Ident;
+use zpm_utils::{DataType, FromFileString, ToFileString, ToHumanString};
+
+use crate::{
+    error::Error,
+    http_npm::{self, AuthorizationMode, GetAuthorizationOptions, NpmHttpParams},
+    npm,
+    project::Project,
+    report::{current_report, with_report_result, StreamReport, StreamReportConfig},
+};
+
+/// Remove versions of a package from the registry
+///
+/// This command removes the given version of a package from the registry. The dist-tags that pointed to it are removed as well, except for
+/// `latest` which is moved to the highest remaining version. If it was the last version of the package, the package itself is removed.
+///
+/// To remove the whole package at once, omit the version and pass `--force`; this can't be undone, and most registries forbid publishing the
+/// same versions again afterwards.
+///
+/// Use `--dry-run` to list the versions that would be affected without modifying anything.
+///
+#[cli::command]
+#[cli::path("npm", "unpublish")]
+#[cli::category("Npm-related commands")]
+pub struct Unpublish {
+    /// The OTP token to use with the command
+    #[cli::option("--otp")]
+    otp: Option<String>,
+
+    /// Print the versions that would be removed without removing them
+    #[cli::option("--dry-run", default = false)]
+    dry_run: bool,
+
+    /// Remove every version of the package when no version is specified
+    #[cli::option("-f,--force", default = false)]
+    force: bool,
+
+    /// The package version to remove, as in `name@1.2.3`, or the package name alone to remove it entirely
+    package: String,
+}
+
+#[derive(Deserialize)]
+struct PackumentRevision {
+    #[serde(rename = "_rev")]
+    rev: String,
+}
+
+impl Unpublish {
+    pub async fn execute(&self) -> Result<(), Error> {
+        let project
+            = Project::new(None).await?;
+
+        let (name, version)
+            = npm::split_package_selector(&self.package);
+
+        let ident
+            = &Ident::from_file_string(name)
+                .map_err(|_| Error::InvalidIdent(name.to_string()))?;
+
+        let version = match version {
+            Some(version) => Some(zpm_semver::Version::from_file_string(version)?),
+            None => None,
+        };
+
+        if version.is_none() && !self.force {
+            return Err(Error::UnpublishRequiresForce(ident.clone()));
+        }
+
+        let registry
+            = http_npm::get_registry(&project.config, ident.scope(), true)?;
+
+        let report = StreamReport::new(StreamReportConfig {
+            ..StreamReportConfig::from_config(&project.config)
+        });
+
+        with_report_result(report, async {
+            let authorization
+                = http_npm::get_authorization(&GetAuthorizationOptions {
+                    configuration: &project.config,
+                    http_client: &project.http_client,
+                    registry,
+                    ident: Some(ident),
+                    auth_mode: AuthorizationMode::AlwaysAuthenticate,
+                    allow_oidc: false,
+                }).await?
+                .ok_or_else(|| Error::AuthenticationError("No authentication configured".to_string()))?;
+
+            let package_path
+                = npm::registry_url_for_all_versions(ident);
+
+            let params = NpmHttpParams {
+                http_client: &project.http_client,
+                registry,
+                path: &package_path,
+                authorization: Some(&authorization),
+                otp: self.otp.as_deref(),
+            };
+
+            let packument_bytes
+                = fetch_packument(&params, &package_path).await?;
+
+            let mut packument: serde_json::Value
+                = JsonDocument::hydrate_from_slice(&packument_bytes[..])?;
+
+            let mut all_versions = packument["versions"].as_object()
+                .map(|versions| versions.keys()
+                    .filter_map(|version| zpm_semver::Version::from_file_string(version).ok())
+                    .collect::<Vec<_>>())
+                .unwrap_or_default();
+
+            all_versions.sort();
+
+            let (removed_versions, remaining_versions): (Vec<_>, Vec<_>)
+                = all_versions.into_iter()
+                    .partition(|candidate| version.as_ref().is_none_or(|version| candidate == version));
+
+            if let Some(version) = version.as_ref().filter(|_| removed_versions.is_empty()) {
+                return Err(Error::VersionNotPublished(ident.clone(), version.clone()));
+            }
+
+            let verb = match self.dry_run {
+                true => "Would remove",
+                false => "Removing",
+            };
+
+            current_report().await.as_ref().map(|report| {
+                report.info(format!("{} the following versions of {}:", verb, ident.to_print_string()));
+
+                for version in &removed_versions {
+                    report.info(format!("- {}", version.to_print_string()));
+                }
+
+                if remaining_versions.is_empty() {
+                    report.info("No version would remain; the package itself will be removed".to_string());
+                }
+            });
+
+            if self.dry_run {
+                return Ok(());
+            }
+
+            let revision: PackumentRevision
+                = JsonDocument::hydrate_from_slice(&packument_bytes[..])?;
+
+            if remaining_versions.is_empty() {
+                http_npm::delete(&NpmHttpParams {
+                    path: &format!("{}/-rev/{}", package_path, revision.rev),
+                    ..params
+                }).await?;
+            } else {
+                let removed_strings = removed_versions.iter()
+                    .map(|version| version.to_file_string())
+                    .collect::<Vec<_>>();
+
+                for field in ["versions", "time"] {
+                    if let Some(entries) = packument[field].as_object_mut() {
+                        entries.retain(|version, _| !removed_strings.contains(version));
+                    }
+                }
+
+                if let Some(dist_tags) = packument["dist-tags"].as_object_mut() {
+                    dist_tags.retain(|tag, version| tag == "latest" || !version.as_str().map_or(false, |version| removed_strings.iter().any(|removed| removed == version)));
+
+                    let latest_removed = dist_tags.get("latest")
+                        .and_then(|version| version.as_str())
+                        .map_or(true, |version| removed_strings.iter().any(|removed| removed == version));
+
+                    if latest_removed {
+                        let highest_version
+                            = remaining_versions.last().expect("Expected at least one remaining version");
+
+                        dist_tags.insert("latest".to_string(), json!(highest_version.to_file_string()));
+                    }
+                }
+
+                if let Some(packument) = packument.as_object_mut() {
+                    packument.remove("_revisions");
+                    packument.remove("_attachments");
+                }
+
+                http_npm::put(&NpmHttpParams {
+                    path: &format!("{}/-rev/{}", package_path, revision.rev),
+                    ..params
+                }, packument.to_string()).await?;
+
+                // Each tarball removal bumps the document revision, so we need to
+                // fetch it again before each request
+                for version in &removed_versions {
+                    let revision: PackumentRevision
+                        = JsonDocument::hydrate_from_slice(&fetch_packument(&params, &package_path).await?[..])?;
+
+                    http_npm::delete(&NpmHttpParams {
+                        path: &format!("{}/-rev/{}", npm::registry_url_for_package_data(ident, version), revision.rev),
+                        ..params
+                    }).await?;
+                }
+            }
+
+            current_report().await.as_ref().map(|report| {
+                report.info(format!("Removed {} version(s) from {}", removed_versions.len(), DataType::Url.colorize(registry)));
+            });
+
+            Ok(())
+        }).await
+    }
+}
+
+async fn fetch_packument(params: &NpmHttpParams<'_>, package_path: &str) -> Result<bytes::Bytes, Error> {
+    http_npm::get(&NpmHttpParams {
+        path: &format!("{}?write=true", package_path),
+        ..*params
+    }).await
+}
@@ -0,0 +1,288 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use clipanion::cli;
+use indexmap::IndexMap;
+use serde::Deserialize;
+use serde_with::{serde_as, MapSkipError};
+use zpm_parsers::JsonDocument;
+use zpm_primitives::{Descriptor, Ident, IdentGlob, Range};
+use zpm_utils::{tree, AbstractValue, ToFileString};
+
+use crate::{
+    error::Error,
+    http_npm::{self, AuthorizationMode, GetAuthorizationOptions, NpmHttpParams},
+    install::InstallState,
+    npm,
+    project::{Project, Workspace},
+};
+
+#[derive(Debug, Clone)]
+pub struct OutdatedDependency {
+    pub workspace: Ident,
+    pub dependency_type: &'static str,
+    pub descriptor: Descriptor,
+    pub current: zpm_semver::Version,
+    pub wanted: zpm_semver::Version,
+    pub latest: zpm_semver::Version,
+}
+
+impl OutdatedDependency {
+    /// The registry range declared in the manifest, along with the ident of the package it targets (if aliased)
+    pub fn registry_range(&self) -> Option<(Option<&Ident>, &zpm_semver::Range)> {
+        extract_registry_range(&self.descriptor)
+    }
+}
+
+struct RegistryVersions {
+    latest: Option<zpm_semver::Version>,
+    versions: Vec<zpm_semver::Version>,
+}
+
+/// List the dependencies that lag behind their latest versions
+///
+/// This command checks, for each workspace of the project, which of the dependencies listed in the manifest (`dependencies`, `devDependencies` and
+/// `optionalDependencies`) are resolved to a version older than what the registry offers.
+///
+/// Two versions are reported for each dependency: the "wanted" version is the highest version still satisfying the range listed in the manifest,
+/// whereas the "latest" version is the one currently associated with the `latest` tag on the registry. Only dependencies using semver ranges are
+/// checked; other protocols (git, tarballs, workspaces, ...) are ignored.
+///
+/// This command accepts glob patterns as arguments to only check a subset of the dependencies. To upgrade the reported dependencies, use
+/// `yarn upgrade-interactive` or `yarn up`.
+///
+#[cli::command]
+#[cli::path("outdated")]
+#[cli::category("Dependency management")]
+pub struct Outdated {
+    /// Format the output as an NDJSON stream
+    #[cli::option("--json", default = false)]
+    json: bool,
+
+    /// The packages to check
+    patterns: Vec<IdentGlob>,
+}
+
+impl Outdated {
+    pub async fn execute(&self) -> Result<(), Error> {
+        let mut project
+            = Project::new(None).await?;
+
+        project.lazy_install().await?;
+
+        let outdated_dependencies
+            = collect_outdated_dependencies(&project, &self.patterns).await?;
+
+        if outdated_dependencies.is_empty() {
+            if !self.json {
+                println!("All dependencies are up to date");
+            }
+
+            return Ok(());
+        }
+
+        let mut workspace_nodes: IndexMap<String, tree::Node>
+            = IndexMap::new();
+
+        for dependency in outdated_dependencies {
+            let workspace_node = workspace_nodes.entry(dependency.workspace.to_file_string())
+                .or_insert_with(|| tree::Node {
+                    label: None,
+                    value: Some(AbstractValue::new(dependency.workspace.clone())),
+                    children: Some(tree::TreeNodeChildren::Map(IndexMap::new())),
+                });
+
+            let Some(tree::TreeNodeChildren::Map(children)) = &mut workspace_node.children else {
+                unreachable!("Workspace nodes are always created with a map of children");
+            };
+
+            let fields: [(&str, AbstractValue); 5] = [
+                ("Range", AbstractValue::new(dependency.descriptor.range.clone())),
+                ("Current", AbstractValue::new(dependency.current)),
+                ("Wanted", AbstractValue::new(dependency.wanted)),
+                ("Latest", AbstractValue::new(dependency.latest)),
+                ("Type", AbstractValue::new(dependency.dependency_type.to_string())),
+            ];
+
+            let fields = fields.into_iter()
+                .map(|(label, value)| (label.to_string(), tree::Node {
+                    label: Some(label.to_string()),
+                    value: Some(value),
+                    children: None,
+                }))
+                .collect::<IndexMap<_, _>>();
+
+            children.insert(dependency.descriptor.ident.to_file_string(), tree::Node {
+                label: None,
+                value: Some(AbstractValue::new(dependency.descriptor.ident.clone())),
+                children: Some(tree::TreeNodeChildren::Map(fields)),
+            });
+        }
+
+        let root_node = tree::Node {
+            label: None,
+            value: None,
+            children: Some(tree::TreeNodeChildren::Vec(workspace_nodes.into_values().collect())),
+        };
+
+        let rendering
+            = tree::TreeRenderer::new()
+                .render(&root_node, self.json);
+
+        print!("{}", rendering);
+
+        Ok(())
+    }
+}
+
+/// Compare the dependencies of each workspace against the registry, returning those for which either the wanted
+/// or the latest version is more recent than the version currently resolved in the install state.
+pub async fn collect_outdated_dependencies(project: &Project, patterns: &[IdentGlob]) -> Result<Vec<OutdatedDependency>, Error> {
+    let install_state
+        = project.install_state.as_ref()
+            .ok_or(Error::InstallStateNotFound)?;
+
+    let mut candidates
+        = vec![];
+
+    for workspace in &project.workspaces {
+        let dependency_sets = [
+            ("dependencies", &workspace.manifest.remote.dependencies),
+            ("devDependencies", &workspace.manifest.dev_dependencies),
+            ("optionalDependencies", &workspace.manifest.remote.optional_dependencies),
+        ];
+
+        for (dependency_type, dependencies) in dependency_sets {
+            for descriptor in dependencies.values() {
+                if !patterns.is_empty() && !patterns.iter().any(|pattern| pattern.check(&descriptor.ident)) {
+                    continue;
+                }
+
+                if extract_registry_range(descriptor).is_none() {
+                    continue;
+                }
+
+                let Some(current) = find_current_version(install_state, workspace, &descriptor.ident) else {
+                    continue;
+                };
+
+                candidates.push((workspace.name.clone(), dependency_type, descriptor.clone(), current));
+            }
+        }
+    }
+
+    let package_idents = candidates.iter()
+        .filter_map(|(_, _, descriptor, _)| extract_registry_range(descriptor).map(|(alias, _)| alias.unwrap_or(&descriptor.ident).clone()))
+        .collect::<BTreeSet<_>>();
+
+    let registry_versions
+        = futures::future::join_all(package_idents.iter().map(|ident| fetch_registry_versions(project, ident))).await
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+
+    let registry_versions = package_idents.into_iter()
+        .zip(registry_versions)
+        .collect::<BTreeMap<_, _>>();
+
+    let mut outdated_dependencies
+        = vec![];
+
+    for (workspace, dependency_type, descriptor, current) in candidates {
+        let Some((alias, range)) = extract_registry_range(&descriptor) else {
+            continue;
+        };
+
+        let registry_data
+            = &registry_versions[alias.unwrap_or(&descriptor.ident)];
+
+        let wanted = registry_data.versions.iter()
+            .filter(|version| range.check(version))
+            .max()
+            .cloned()
+            .unwrap_or_else(|| current.clone());
+
+        let latest = registry_data.latest.clone()
+            .unwrap_or_else(|| wanted.clone());
+
+        if wanted <= current && latest <= current {
+            continue;
+        }
+
+        outdated_dependencies.push(OutdatedDependency {
+            workspace,
+            dependency_type,
+            descriptor,
+            current,
+            wanted,
+            latest,
+        });
+    }
+
+    Ok(outdated_dependencies)
+}
+
+fn extract_registry_range(descriptor: &Descriptor) -> Option<(Option<&Ident>, &zpm_semver::Range)> {
+    match &descriptor.range {
+        Range::AnonymousSemver(params)
+            => Some((None, &params.range)),
+
+        Range::RegistrySemver(params)
+            => Some((params.ident.as_ref(), &params.range)),
+
+        _ => None,
+    }
+}
+
+fn find_current_version(install_state: &InstallState, workspace: &Workspace, ident: &Ident) -> Option<zpm_semver::Version> {
+    let workspace_resolution
+        = install_state.resolution_tree.locator_resolutions.get(&workspace.locator())?;
+
+    let descriptor
+        = workspace_resolution.dependencies.get(ident)?;
+
+    let locator
+        = install_state.resolution_tree.descriptor_to_locator.get(descriptor)?;
+
+    install_state.resolution_tree.locator_resolutions.get(locator)
+        .map(|resolution| resolution.version.clone())
+}
+
+async fn fetch_registry_versions(project: &Project, ident: &Ident) -> Result<RegistryVersions, Error> {
+    let registry
+        = http_npm::get_registry(&project.config, ident.scope(), false)?;
+
+    let authorization
+        = http_npm::get_authorization(&GetAuthorizationOptions {
+            configuration: &project.config,
+            http_client: &project.http_client,
+            registry,
+            ident: Some(ident),
+            auth_mode: AuthorizationMode::RespectConfiguration,
+            allow_oidc: false,
+        }).await?;
+
+    let bytes = http_npm::get_metadata(&NpmHttpParams {
+        http_client: &project.http_client,
+        registry,
+        path: &npm::registry_url_for_all_versions(ident),
+        authorization: authorization.as_deref(),
+        otp: None,
+    }).await?;
+
+    #[serde_as]
+    #[derive(Deserialize)]
+    struct RegistryMetadata {
+        #[serde(default, rename(deserialize = "dist-tags"))]
+        #[serde_as(as = "MapSkipError<_, _>")]
+        dist_tags: BTreeMap<String, zpm_semver::Version>,
+        #[serde_as(as = "MapSkipError<_, _>")]
+        versions: BTreeMap<zpm_semver::Version, serde::de::IgnoredAny>,
+    }
+
+    let registry_data: RegistryMetadata
+        = JsonDocument::hydrate_from_slice(&bytes[..])?;
+
+    Ok(RegistryVersions {
+        latest: registry_data.dist_tags.get("latest").cloned(),
+        versions: registry_data.versions.into_keys().collect(),
+    })
+}
@@ -3,7 +3,7 @@ use std::{os::unix::process::ExitStatusExt, process::ExitStatus};
 use zpm_utils::Path;
 use clipanion::cli;
 
-use crate::{error::Error, project, script::ScriptEnvironment};
+use crate::{error::Error, project, script::{ScriptEnvironment, ScriptResult}, task_cache::TaskCache};
 
 /// Run a dependency binary or local script
 ///
@@ -19,6 +19,11 @@ use crate::{error::Error, project, script::ScriptEnvironment};
 /// Whatever happens, the cwd of the spawned process will be the workspace that declares the script (which makes it possible to call commands
 /// cross-workspaces using the third syntax).
 ///
+/// Scripts whose inputs are listed in the `scriptsMeta` field of their workspace are cached: when their input files, arguments, declared
+/// environment variables (`env`), and dependencies (including the content of the workspaces they depend on) didn't change since a previous
+/// successful run, its logs are replayed and its declared outputs are restored instead of running the script again. This also applies to the
+/// scripts executed through `yarn workspaces foreach`.
+///
 #[cli::command(default, proxy)]
 #[cli::path("run")]
 #[cli::category("Scripting commands")]
@@ -35,6 +40,10 @@ pub struct Run {
     #[cli::option("--error-if-missing", default = true)]
     error_if_missing: bool,
 
+    /// If set (the default), the results of scripts declaring their inputs will be restored from the task cache when possible
+    #[cli::option("--cache", default = true)]
+    cache: bool,
+
     /// The directory in which to run the script or binary
     #[cli::option("--run-cwd")]
     run_cwd: Option<Path>,
@@ -146,14 +155,41 @@ impl Run {
                     return Err(Error::InvalidRunScriptOptions(node_args));
                 }
 
-                Ok(ScriptEnvironment::new()?
+                let script_env = ScriptEnvironment::new()?
                     .with_project(&project)
                     .with_package(&project, &locator)?
-                    .with_env_variable("npm_lifecycle_event", &self.name)
-                    .enable_shell_forwarding()
+                    .with_env_variable("npm_lifecycle_event", &self.name);
+
+                let task_cache
+                    = TaskCache::new(&project);
+
+                let cached_task = match self.cache {
+                    true => task_cache.prepare(&locator, &self.name, &script, &self.args)?,
+                    false => None,
+                };
+
+                let Some(cached_task) = cached_task else {
+                    return Ok(script_env
+                        .enable_shell_forwarding()
+                        .run_script(&script, &self.args)
+                        .await?
+                        .into());
+                };
+
+                if let Some(status) = task_cache.restore(&cached_task)? {
+                    return Ok(status);
+                }
+
+                let script_result = script_env
+                    .enable_output_recording()
                     .run_script(&script, &self.args)
-                    .await?
-                    .into())
+                    .await?;
+
+                if let ScriptResult::Success(output) = &script_result {
+                    task_cache.store(&cached_task, output)?;
+                }
+
+                Ok(script_result.into())
             },
 
             Err(Error::ScriptNotFound(_)) | Err(Error::GlobalScriptNotFound(_))
@@ -0,0 +1,74 @@
+use clipanion::cli;
+use zpm_macro_enum::zpm_enum;
+
+use crate::{
+    error::Error,
+    project::Project,
+    sbom::{Sbom as SoftwareBillOfMaterials, SbomOptions},
+};
+
+#[zpm_enum(or_else = |s| Err(Error::InvalidSbomFormat(s.to_string())))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive_variants(Debug)]
+pub enum SbomFormat {
+    #[literal("cyclonedx")]
+    CycloneDx,
+
+    #[literal("spdx")]
+    Spdx,
+}
+
+/// Generate a software bill of materials
+///
+/// This command prints a software bill of materials (SBOM) listing all packages the active workspace depends on, based on the data from the
+/// install state. Each package is reported with its package url (purl), its download location, its checksum from the lockfile, and the
+/// license declared in its manifest. Packages that don't come from the npm registry (workspaces, folders, tarballs, git repositories, ...)
+/// get `pkg:github` or `pkg:generic` purls.
+///
+/// The checksums are the BLAKE2b-512 hashes stored in the lockfile, which Yarn computes over the archives from its cache rather than over
+/// the tarballs published on the registry; they can be checked against the cache with `yarn install --check-cache`.
+///
+/// Two formats are supported: CycloneDX 1.5 (the default) and SPDX 2.3, both serialized as JSON. Use `yarn workspace <name> sbom` to generate
+/// the SBOM of a specific workspace.
+///
+/// If the `--production` flag is set, the dev dependencies of the traversed workspaces are omitted from the report, similar to what
+/// `yarn workspaces focus --production` would install.
+///
+#[cli::command]
+#[cli::path("sbom")]
+#[cli::category("Dependency management")]
+pub struct Sbom {
+    /// The format of the generated document (cyclonedx or spdx)
+    #[cli::option("--format", default = SbomFormat::CycloneDx)]
+    format: SbomFormat,
+
+    /// Omit the dev dependencies from the report
+    #[cli::option("--production", default = false)]
+    production: bool,
+}
+
+impl Sbom {
+    pub async fn execute(&self) -> Result<(), Error> {
+        let mut project
+            = Project::new(None).await?;
+
+        project.lazy_install().await?;
+
+        let workspace
+            = project.active_workspace()?;
+
+        let sbom
+            = SoftwareBillOfMaterials::from_workspace(&project, workspace, &SbomOptions {
+                prune_dev_dependencies: self.production,
+            })?;
+
+        let output = match self.format {
+            SbomFormat::CycloneDx => sbom.to_cyclonedx()?,
+            SbomFormat::Spdx => sbom.to_spdx()?,
+        };
+
+        println!("{}", output);
+
+        Ok(())
+    }
+}
@@ -2,12 +2,12 @@ use std::collections::BTreeSet;
 
 use clipanion::cli;
 use zpm_parsers::{Document, JsonDocument, Value};
-use zpm_primitives::Ident;
+use zpm_primitives::{Descriptor, Ident, Range};
 use zpm_semver::RangeKind;
 use zpm_utils::ToFileString;
 
 use crate::{
-    descriptor_loose::{self, LooseDescriptor},
+    descriptor_loose::{self, LooseDescriptor, LooseResolution},
     error::Error,
     install::InstallContext,
     project::{InstallMode, Project, RunInstallOptions, Workspace}
@@ -122,52 +122,7 @@ impl Up {
         let loose_resolutions
             = LooseDescriptor::resolve_all(&install_context, &resolve_options, &expanded_descriptors).await?;
 
-        for workspace in &project.workspaces {
-            let manifest_path = workspace.path
-                .with_join_str("package.json");
-
-            let manifest_content = manifest_path
-                .fs_read_prealloc()?;
-
-            let mut document
-                = JsonDocument::new(manifest_content)?;
-
-            for resolution in loose_resolutions.iter() {
-                document.update_path(
-                    &zpm_parsers::Path::from_segments(vec!["dependencies".to_string(), resolution.descriptor.ident.to_file_string()]),
-                    Value::String(resolution.descriptor.range.to_anonymous_range().to_file_string()),
-                )?;
-
-                document.update_path(
-                    &zpm_parsers::Path::from_segments(vec!["devDependencies".to_string(), resolution.descriptor.ident.to_file_string()]),
-                    Value::String(resolution.descriptor.range.to_anonymous_range().to_file_string()),
-                )?;
-
-                document.update_path(
-                    &zpm_parsers::Path::from_segments(vec!["optionalDependencies".to_string(), resolution.descriptor.ident.to_file_string()]),
-                    Value::String(resolution.descriptor.range.to_anonymous_range().to_file_string()),
-                )?;
-            }
-
-            manifest_path
-                .fs_change(&document.input, false)?;
-        }
-
-        let mut project
-            = Project::new(None).await?;
-
-        let enforced_resolutions
-            = loose_resolutions.into_iter()
-                .filter_map(|resolution| resolution.locator.map(|locator| (resolution.descriptor, locator)))
-                .collect();
-
-        project.run_install(RunInstallOptions {
-            mode: self.mode,
-            enforced_resolutions,
-            ..Default::default()
-        }).await?;
-
-        Ok(())
+        apply_upgrades(&project, loose_resolutions, self.mode).await
     }
 
     fn list_workspace_idents(&self, workspace: &Workspace) -> Vec<Ident> {
@@ -188,3 +143,72 @@ impl Up {
         idents
     }
 }
+
+/// The range to write in the manifests; aliases keep their `npm:<name>@` prefix,
+/// otherwise the dependency would switch to the package matching its key
+fn manifest_range(descriptor: &Descriptor) -> String {
+    let is_alias = match &descriptor.range {
+        Range::RegistrySemver(params) => params.ident.as_ref().is_some_and(|ident| ident != &descriptor.ident),
+        Range::RegistryTag(params) => params.ident.as_ref().is_some_and(|ident| ident != &descriptor.ident),
+        _ => false,
+    };
+
+    match is_alias {
+        true => descriptor.range.to_file_string(),
+        false => descriptor.range.to_anonymous_range().to_file_string(),
+    }
+}
+
+/// Write the given resolutions into the manifests of every workspace that already depends on them, then run an install
+/// that enforces the resolved locators (if any).
+pub async fn apply_upgrades(project: &Project, loose_resolutions: Vec<LooseResolution>, mode: Option<InstallMode>) -> Result<(), Error> {
+    for workspace in &project.workspaces {
+        let manifest_path = workspace.path
+            .with_join_str("package.json");
+
+        let manifest_content = manifest_path
+            .fs_read_prealloc()?;
+
+        let mut document
+            = JsonDocument::new(manifest_content)?;
+
+        for resolution in loose_resolutions.iter() {
+            let range
+                = manifest_range(&resolution.descriptor);
+
+            document.update_path(
+                &zpm_parsers::Path::from_segments(vec!["dependencies".to_string(), resolution.descriptor.ident.to_file_string()]),
+                Value::String(range.clone()),
+            )?;
+
+            document.update_path(
+                &zpm_parsers::Path::from_segments(vec!["devDependencies".to_string(), resolution.descriptor.ident.to_file_string()]),
+                Value::String(range.clone()),
+            )?;
+
+            document.update_path(
+                &zpm_parsers::Path::from_segments(vec!["optionalDependencies".to_string(), resolution.descriptor.ident.to_file_string()]),
+                Value::String(range.clone()),
+            )?;
+        }
+
+        manifest_path
+            .fs_change(&document.input, false)?;
+    }
+
+    let mut project
+        = Project::new(None).await?;
+
+    let enforced_resolutions
+        = loose_resolutions.into_iter()
+            .filter_map(|resolution| resolution.locator.map(|locator| (resolution.descriptor, locator)))
+            .collect();
+
+    project.run_install(RunInstallOptions {
+        mode,
+        enforced_resolutions,
+        ..Default::default()
+    }).await?;
+
+    Ok(())
+}
@@ -0,0 +1,151 @@
+use std::collections::BTreeMap;
+
+use clipanion::cli;
+use zpm_primitives::{Descriptor, IdentGlob, RegistrySemverRange};
+use zpm_utils::{ToFileString, ToHumanString};
+
+use crate::{
+    descriptor_loose::LooseResolution,
+    error::Error,
+    project::{InstallMode, Project},
+    report::{current_report, with_report_result, PromptType, StreamReport, StreamReportConfig},
+};
+
+use super::{outdated::{collect_outdated_dependencies, OutdatedDependency}, up::apply_upgrades};
+
+/// Open the upgrade interface
+///
+/// This command opens a prompt for each outdated dependency of your project (as reported by `yarn outdated`), letting you choose whether to keep
+/// the current range, to upgrade it to the highest version satisfying the current range, or to upgrade it to the latest version available on the
+/// registry.
+///
+/// The selected upgrades are then applied to all workspaces depending on the package, exactly like `yarn up` would do. The semver modifier of the
+/// current range (`^`, `~`, or none) is preserved.
+///
+#[cli::command]
+#[cli::path("upgrade-interactive")]
+#[cli::category("Dependency management")]
+pub struct UpgradeInteractive {
+    /// Change what artifacts this install will generate
+    #[cli::option("--mode")]
+    mode: Option<InstallMode>,
+
+    /// The packages to upgrade
+    patterns: Vec<IdentGlob>,
+}
+
+impl UpgradeInteractive {
+    pub async fn execute(&self) -> Result<(), Error> {
+        let mut project
+            = Project::new(None).await?;
+
+        project.lazy_install().await?;
+
+        let outdated_dependencies
+            = collect_outdated_dependencies(&project, &self.patterns).await?;
+
+        // The same range may be used by multiple workspaces; we only ask once since `up` applies the
+        // new range to all workspaces anyway.
+        let mut unique_dependencies: BTreeMap<Descriptor, OutdatedDependency>
+            = BTreeMap::new();
+
+        for dependency in outdated_dependencies {
+            unique_dependencies.entry(dependency.descriptor.clone())
+                .or_insert(dependency);
+        }
+
+        if unique_dependencies.is_empty() {
+            println!("All dependencies are up to date");
+            return Ok(());
+        }
+
+        let default_range_kind
+            = project.config.settings.default_semver_range_prefix.value;
+
+        let report
+            = StreamReport::new(StreamReportConfig {
+                ..StreamReportConfig::from_config(&project.config)
+            });
+
+        let loose_resolutions = with_report_result(report, async {
+            let report_guard
+                = current_report().await;
+
+            let report
+                = report_guard.as_ref()
+                    .expect("No report set");
+
+            let mut loose_resolutions
+                = vec![];
+
+            for dependency in unique_dependencies.values() {
+                let Some((alias, range)) = dependency.registry_range() else {
+                    continue;
+                };
+
+                let range_kind
+                    = range.kind().unwrap_or(default_range_kind);
+
+                let mut seen_ranges
+                    = vec![range.to_file_string()];
+
+                let mut choices
+                    = vec![];
+
+                for version in [&dependency.wanted, &dependency.latest] {
+                    let new_range
+                        = version.to_range(range_kind);
+
+                    let new_range_string
+                        = new_range.to_file_string();
+
+                    if seen_ranges.contains(&new_range_string) {
+                        continue;
+                    }
+
+                    seen_ranges.push(new_range_string);
+
+                    let label
+                        = format!("Upgrade to {}", new_range.to_print_string());
+
+                    let descriptor
+                        = Descriptor::new(dependency.descriptor.ident.clone(), RegistrySemverRange {ident: alias.cloned(), range: new_range}.into());
+
+                    choices.push((label, descriptor));
+                }
+
+                if choices.is_empty() {
+                    continue;
+                }
+
+                let mut items
+                    = vec![format!("Keep {}", range.to_print_string())];
+
+                items.extend(choices.iter().map(|(label, _)| label.clone()));
+
+                let prompt
+                    = format!("{} (currently {})", dependency.descriptor.to_print_string(), dependency.current.to_print_string());
+
+                let selection
+                    = report.prompt(PromptType::Select(prompt, items)).await?
+                        .parse::<usize>()
+                        .expect("Select prompts always resolve to a valid index");
+
+                if let Some((_, descriptor)) = selection.checked_sub(1).and_then(|index| choices.get(index)) {
+                    loose_resolutions.push(LooseResolution {
+                        descriptor: descriptor.clone(),
+                        locator: None,
+                    });
+                }
+            }
+
+            Ok(loose_resolutions)
+        }).await?;
+
+        if loose_resolutions.is_empty() {
+            return Ok(());
+        }
+
+        apply_upgrades(&project, loose_resolutions, self.mode).await
+    }
+}
@@ -1,15 +1,17 @@
-use std::{collections::{BTreeMap, BTreeSet}, io::{StdoutLock, Write}, process::{ExitCode, ExitStatus, Stdio}, sync::{Arc, atomic::{AtomicBool, Ordering}}, time::Instant};
+use std::{collections::{BTreeMap, BTreeSet}, io::{StdoutLock, Write}, process::{ExitCode, ExitStatus, Stdio}, sync::{Arc, atomic::{AtomicBool, Ordering}}, time::{Duration, Instant}};
 
 use clipanion::{cli, prelude::*};
 use futures::{StreamExt, stream::FuturesUnordered};
 use itertools::Itertools;
+use notify::{event::ModifyKind, EventKind, RecursiveMode, Watcher};
+use serde_json::json;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use zpm_macro_enum::zpm_enum;
 use zpm_primitives::Ident;
-use zpm_utils::{DataType, Path, ToFileString, ToHumanString, Unit};
+use zpm_utils::{DataType, Glob, Hash64, Path, ToFileString, ToHumanString, Unit};
 
 use crate::{
-    algos::scc_tarjan_pearce, commands::{PartialYarnCli, YarnCli}, error::Error, git_utils, project::{Project, Workspace}, workspace_glob::WorkspaceGlob
+    algos::scc_tarjan_pearce, commands::{PartialYarnCli, YarnCli}, error::Error, git_utils, project::{Project, Workspace}, report::is_json_report, task_cache::project_globs, workspace_glob::WorkspaceGlob
 };
 
 /// An SCC island containing workspaces that can be run together,
@@ -28,6 +30,15 @@ pub enum Selection {
     Topological(Vec<TopologicalIsland>),
 }
 
+impl Selection {
+    pub fn idents(&self) -> BTreeSet<Ident> {
+        match self {
+            Selection::List(idents) => idents.iter().cloned().collect(),
+            Selection::Topological(islands) => islands.iter().flat_map(|island| island.idents.iter().cloned()).collect(),
+        }
+    }
+}
+
 #[zpm_enum]
 #[derive(Debug)]
 pub enum FollowedDependencies {
@@ -105,6 +116,9 @@ pub struct WorkspacesForeach {
     #[cli::option("--private", default = true)]
     private: bool,
 
+    #[cli::option("--watch", default = false)]
+    watch: bool,
+
     command: String,
 
     args: Vec<String>,
@@ -125,17 +139,198 @@ impl WorkspacesForeach {
         let selection
             = self.selection(&project, args.clone()).await?;
 
+        if !self.watch {
+            return self.execute_selection(&project, selection, args).await;
+        }
+
+        let selected_idents
+            = selection.idents();
+
+        self.execute_selection(&project, selection, args.clone()).await?;
+
+        self.execute_watch(&project, selected_idents, args).await
+    }
+
+    async fn execute_selection(&self, project: &Project, selection: Selection, args: Vec<String>) -> Result<ExitCode, Error> {
         match selection {
             Selection::List(idents) => {
-                self.execute_list(&project, idents, args).await
+                self.execute_list(project, idents, args).await
             },
 
             Selection::Topological(islands) => {
-                self.execute_topological(&project, islands, args).await
+                self.execute_topological(project, islands, args).await
             },
         }
     }
 
+    /// Wait for file changes, then re-run the command in the selected
+    /// workspaces affected by them, either directly or through one of their
+    /// dependencies. Runs until interrupted.
+    async fn execute_watch(&self, project: &Project, selected_idents: BTreeSet<Ident>, args: Vec<String>) -> Result<ExitCode, Error> {
+        let (sender, mut receiver)
+            = tokio::sync::mpsc::unbounded_channel::<notify::Event>();
+
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let Ok(event) = event else {
+                return;
+            };
+
+            // Reads also generate events on some platforms; we only care about writes
+            let is_write = match event.kind {
+                EventKind::Modify(ModifyKind::Metadata(_)) => false,
+                EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) => true,
+                _ => false,
+            };
+
+            if is_write {
+                let _ = sender.send(event);
+            }
+        })?;
+
+        watcher.watch(&project.project_cwd.to_path_buf(), RecursiveMode::Recursive)?;
+
+        // The outputs declared by the script are never worth re-running for
+        let output_globs = project.workspaces.iter()
+            .filter_map(|workspace| {
+                workspace.manifest.scripts_meta.get(&self.command)
+                    .map(|meta| project_globs(workspace, &meta.outputs))
+            })
+            .flatten_ok()
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut fingerprints
+            = BTreeMap::new();
+
+        loop {
+            if self.verbose_level >= 2 && !is_json_report() {
+                println!();
+                println!("Waiting for changes...");
+            }
+
+            let Some(event) = receiver.recv().await else {
+                break;
+            };
+
+            let mut events
+                = vec![event];
+
+            // Saving a file often generates a burst of events; we wait a little
+            // to process them all at once rather than re-running multiple times
+            tokio::time::sleep(Duration::from_millis(100)).await;
+
+            while let Ok(event) = receiver.try_recv() {
+                events.push(event);
+            }
+
+            let created_paths = events.iter()
+                .filter(|event| matches!(event.kind, EventKind::Create(_)))
+                .flat_map(|event| event.paths.iter().cloned())
+                .collect::<BTreeSet<_>>();
+
+            let changed_paths = events.into_iter()
+                .flat_map(|event| event.paths)
+                .collect_vec();
+
+            // The events received while the scripts were running are kept, since
+            // they may be user edits; the files the scripts wrote are filtered out
+            // by their fingerprints, which only change when their content does
+            let changed_paths
+                = self.filter_changed_paths(project, &output_globs, &mut fingerprints, changed_paths, &created_paths);
+
+            let affected_idents
+                = self.affected_workspaces(project, &selected_idents, &changed_paths);
+
+            if affected_idents.is_empty() {
+                continue;
+            }
+
+            let selection = match self.is_topological {
+                true => Selection::Topological(self.topological_sort(project, &affected_idents)),
+                false => Selection::List(affected_idents.into_iter().collect()),
+            };
+
+            self.execute_selection(project, selection, args.clone()).await?;
+        }
+
+        Ok(ExitCode::SUCCESS)
+    }
+
+    /// Only keep the paths whose content differs from the last time we saw
+    /// them, and which aren't outputs of the script. A script rewriting the
+    /// same artifacts thus doesn't trigger new runs in a loop.
+    fn filter_changed_paths(&self, project: &Project, output_globs: &[Glob], fingerprints: &mut BTreeMap<std::path::PathBuf, Option<Hash64>>, changed_paths: Vec<std::path::PathBuf>, created_paths: &BTreeSet<std::path::PathBuf>) -> Vec<std::path::PathBuf> {
+        changed_paths.into_iter()
+            .unique()
+            .filter(|changed_path| {
+                let Some(path) = Path::try_from(changed_path.as_path()).ok() else {
+                    return false;
+                };
+
+                let Some(rel_path) = path.forward_relative_to(&project.project_cwd) else {
+                    return false;
+                };
+
+                // Checked before reading anything, since the install artifacts
+                // can be large and are rewritten all at once
+                let is_ignored = rel_path.as_str()
+                    .split('/')
+                    .any(|segment| segment == ".git" || segment == ".yarn" || segment == "node_modules");
+
+                if is_ignored || output_globs.iter().any(|glob| glob.is_match(rel_path.as_str())) {
+                    return false;
+                }
+
+                // We forget about the removed paths so that the temporary files
+                // don't pile up; the ones created and removed within the same
+                // batch of events never had any content worth comparing
+                if !path.fs_exists() {
+                    fingerprints.remove(changed_path);
+                    return !created_paths.contains(changed_path);
+                }
+
+                // Folders have no content to compare
+                let fingerprint = path.fs_read()
+                    .ok()
+                    .map(Hash64::from_data);
+
+                fingerprints.insert(changed_path.clone(), fingerprint.clone()) != Some(fingerprint)
+            })
+            .collect()
+    }
+
+    fn affected_workspaces(&self, project: &Project, selected_idents: &BTreeSet<Ident>, changed_paths: &[std::path::PathBuf]) -> BTreeSet<Ident> {
+        let mut affected_idents
+            = BTreeSet::new();
+
+        for changed_path in changed_paths {
+            let Some(rel_path) = Path::try_from(changed_path.as_path()).ok().and_then(|path| path.forward_relative_to(&project.project_cwd)) else {
+                continue;
+            };
+
+            let workspace = project.workspaces.iter()
+                .filter(|workspace| workspace.rel_path.is_empty() || workspace.rel_path.contains(&rel_path))
+                .max_by_key(|workspace| workspace.rel_path.as_str().len());
+
+            if let Some(workspace) = workspace {
+                affected_idents.insert(workspace.name.clone());
+            }
+        }
+
+        let mut queue
+            = affected_idents.iter().cloned().collect_vec();
+
+        while let Some(ident) = queue.pop() {
+            for workspace in project.workspaces.iter() {
+                if self.followed_dependencies(workspace).contains(&ident) && affected_idents.insert(workspace.name.clone()) {
+                    queue.push(workspace.name.clone());
+                }
+            }
+        }
+
+        affected_idents.retain(|ident| selected_idents.contains(ident));
+        affected_idents
+    }
+
     fn prefix_colors() -> impl Iterator<Item = &'static DataType> {
         static COLORS: [DataType; 5] = [
             DataType::Custom(46, 134, 171),
@@ -176,7 +371,7 @@ impl WorkspacesForeach {
     }
 
     fn print_epilogue(&self, project: &Project, start: Instant, task_count: usize) {
-        if self.verbose_level >= 2 && project.config.settings.enable_timers.value {
+        if self.verbose_level >= 2 && project.config.settings.enable_timers.value && !is_json_report() {
             let duration
                 = start.elapsed();
 
@@ -207,6 +402,7 @@ impl WorkspacesForeach {
         for ident in idents {
             let task = Task {
                 prefix: self.prefix_for_ident(&ident, color_it.next().unwrap()),
+                workspace: ident.to_file_string(),
 
                 cwd: project.workspace_by_ident(&ident)?.path.clone(),
                 args: args.clone(),
@@ -214,6 +410,7 @@ impl WorkspacesForeach {
                 enable_timers: project.config.settings.enable_timers.value,
                 verbose_level: self.verbose_level,
                 is_interlaced: self.is_interlaced(),
+                is_json: is_json_report(),
 
                 is_first_printed_task: is_first_printed_task.clone(),
             };
@@ -285,6 +482,7 @@ impl WorkspacesForeach {
 
                 let task = Task {
                     prefix: self.prefix_for_ident(&ident, color_it.next().unwrap()),
+                    workspace: ident.to_file_string(),
 
                     cwd: project.workspace_by_ident(&ident)?.path.clone(),
                     args: args.clone(),
@@ -292,6 +490,7 @@ impl WorkspacesForeach {
                     enable_timers: project.config.settings.enable_timers.value,
                     verbose_level: self.verbose_level,
                     is_interlaced: self.is_interlaced(),
+                    is_json: is_json_report(),
 
                     is_first_printed_task: is_first_printed_task.clone(),
                 };
@@ -616,12 +815,14 @@ impl WorkspacesForeach {
 
 struct Task {
     pub prefix: String,
+    pub workspace: String,
 
     pub cwd: Path,
     pub args: Vec<String>,
 
     pub enable_timers: bool,
     pub is_interlaced: bool,
+    pub is_json: bool,
     pub verbose_level: u8,
 
     pub is_first_printed_task: Arc<AtomicBool>,
@@ -629,7 +830,9 @@ struct Task {
 
 impl Task {
     fn write_ln(&mut self, writer: &mut StdoutLock<'_>, str: &str) {
-        if self.verbose_level >= 1 {
+        if self.is_json {
+            writeln!(writer, "{}", json!({"type": "taskOutput", "workspace": self.workspace, "line": str})).unwrap();
+        } else if self.verbose_level >= 1 {
             writeln!(writer, "{}{}", self.prefix, str).unwrap();
         } else {
             writeln!(writer, "{}", str).unwrap();
@@ -637,7 +840,9 @@ impl Task {
     }
 
     fn write_prologue(&mut self, writer: &mut StdoutLock<'_>) {
-        if self.verbose_level >= 2 {
+        if self.is_json {
+            writeln!(writer, "{}", json!({"type": "taskStart", "workspace": self.workspace})).unwrap();
+        } else if self.verbose_level >= 2 {
             self.write_ln(writer, "Process started");
         }
     }
@@ -646,6 +851,11 @@ impl Task {
         let duration
             = start.elapsed();
 
+        if self.is_json {
+            writeln!(writer, "{}", json!({"type": "taskEnd", "workspace": self.workspace, "exitCode": status.code(), "duration": duration.as_millis() as u64})).unwrap();
+            return Ok(status);
+        }
+
         let status_string = match status.code() {
             Some(code) => format!("exit code {}", DataType::Number.colorize(&format!("{}", code))),
             None => "exit code unknown".to_string(),
@@ -706,7 +916,7 @@ impl Task {
             let mut writer
                 = std::io::stdout().lock();
 
-            if self.verbose_level >= 2 && !self.is_first_printed_task.swap(false, Ordering::Relaxed) {
+            if self.verbose_level >= 2 && !self.is_json && !self.is_first_printed_task.swap(false, Ordering::Relaxed) {
                 println!();
             }
 
@@ -0,0 +1,43 @@
+use zpm_parsers::{Document, JsonDocument, Value};
+
+use crate::{
+    constraints::structs::{ConstraintsOutput, WorkspaceOperation},
+    error::Error,
+    project::{Project, MANIFEST_NAME},
+};
+
+/// Apply the operations computed by the constraints engine to the manifests of
+/// the affected workspaces, preserving their original formatting.
+pub fn apply_constraints_operations(project: &Project, output: &ConstraintsOutput) -> Result<(), Error> {
+    for (workspace_rel_path, operations) in &output.all_workspace_operations {
+        // Read the current manifest
+        let manifest_path = project.project_cwd
+            .with_join(workspace_rel_path)
+            .with_join_str(MANIFEST_NAME);
+
+        let manifest_content = manifest_path
+            .fs_read_prealloc()?;
+
+        let mut document
+            = JsonDocument::new(manifest_content)?;
+
+        // Apply each operation
+        for operation in operations {
+            match operation {
+                WorkspaceOperation::Set { path, value } => {
+                    document.set_path(&zpm_parsers::Path::from_segments(path.clone()), value.into())?;
+                },
+
+                WorkspaceOperation::Unset { path } => {
+                    document.set_path(&zpm_parsers::Path::from_segments(path.clone()), Value::Undefined)?;
+                },
+            }
+        }
+
+        // Write the formatted result back
+        manifest_path
+            .fs_change(&document.input, false)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,321 @@
+use std::collections::BTreeMap;
+
+use indexmap::IndexMap;
+use serde::Deserialize;
+use serde_json::Value;
+use zpm_parsers::{JsonDocument, YamlDocument};
+use zpm_primitives::{Ident, IdentGlob};
+use zpm_utils::{Path, ToFileString};
+
+use crate::{
+    constraints::structs::{Caller, ConstraintsOutput, ConstraintsWorkspace, PerValueInfo, WorkspaceError, WorkspaceOperation},
+    error::Error,
+    project::{Project, MANIFEST_NAME},
+};
+
+pub const DECLARATIVE_CONSTRAINTS_FILE: &str = "yarn.constraints.yml";
+
+/// Declarative constraints, read from `yarn.constraints.yml`:
+///
+/// ```yaml
+/// enforceConsistentDependencies: true
+///
+/// enforcedFields:
+///   - field: license
+///     value: MIT
+///   - field: scripts.postinstall
+///     value: null
+///     workspaces: ["@my-org/*"]
+///
+/// bannedDependencies:
+///   - ident: lodash
+/// ```
+///
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeclarativeConstraints {
+    /// Require all workspaces depending on a same package to use the same range;
+    /// the one they use the most often is the one applied by `--fix`
+    #[serde(default)]
+    pub enforce_consistent_dependencies: bool,
+
+    #[serde(default)]
+    pub enforced_fields: Vec<EnforcedField>,
+
+    #[serde(default)]
+    pub banned_dependencies: Vec<BannedDependency>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnforcedField {
+    pub field: zpm_parsers::Path,
+
+    /// The expected value; `null` means the field must be removed
+    pub value: Value,
+
+    /// Limit the rule to the matching workspaces; applies to all workspaces if empty
+    #[serde(default)]
+    pub workspaces: Vec<IdentGlob>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BannedDependency {
+    pub ident: IdentGlob,
+
+    /// Limit the rule to the matching workspaces; applies to all workspaces if empty
+    #[serde(default)]
+    pub workspaces: Vec<IdentGlob>,
+}
+
+fn matches_workspace(patterns: &[IdentGlob], ident: &Ident) -> bool {
+    patterns.is_empty() || patterns.iter().any(|pattern| pattern.check(ident))
+}
+
+/// The values requested for a single manifest field, along with the rules that
+/// requested them. A `None` value means that the field must be unset.
+#[derive(Default)]
+struct FieldUpdates {
+    values: IndexMap<String, (Option<Value>, Vec<Caller>)>,
+}
+
+/// Mirror of the engine used by the JavaScript constraints; rules register the
+/// values they expect for each field, and conflicting expectations are reported
+/// rather than applied.
+#[derive(Default)]
+struct ConstraintsEngine {
+    updates: BTreeMap<Path, BTreeMap<zpm_parsers::Path, FieldUpdates>>,
+}
+
+impl ConstraintsEngine {
+    fn update(&mut self, workspace_cwd: &Path, field_path: zpm_parsers::Path, value: Option<Value>, rule: &str) {
+        let key
+            = value.as_ref().map_or_else(|| "undefined".to_string(), |value| value.to_string());
+
+        let caller = Caller {
+            file: Some(DECLARATIVE_CONSTRAINTS_FILE.to_string()),
+            method_name: Some(rule.to_string()),
+            arguments: vec![],
+            line: None,
+            column: None,
+        };
+
+        self.updates.entry(workspace_cwd.clone())
+            .or_default()
+            .entry(field_path)
+            .or_default()
+            .values.entry(key)
+            .or_insert_with(|| (value, vec![]))
+            .1.push(caller);
+    }
+
+    fn into_output(self, manifests: &BTreeMap<Path, Value>, fix: bool) -> ConstraintsOutput {
+        let mut output
+            = ConstraintsOutput::default();
+
+        for (workspace_cwd, fields) in self.updates {
+            let manifest
+                = &manifests[&workspace_cwd];
+
+            let mut workspace_operations
+                = vec![];
+            let mut workspace_errors
+                = vec![];
+
+            for (field_path, updates) in fields {
+                if updates.values.len() > 1 {
+                    let mut unset_values
+                        = None;
+                    let mut set_values
+                        = vec![];
+
+                    for (value, callers) in updates.values.into_values() {
+                        match value {
+                            Some(value) => set_values.push((value.into(), PerValueInfo {callers})),
+                            None => unset_values = Some(PerValueInfo {callers}),
+                        }
+                    }
+
+                    workspace_errors.push(WorkspaceError::ConflictingValues {
+                        field_path,
+                        set_values,
+                        unset_values,
+                    });
+
+                    continue;
+                }
+
+                let Some((new_value, _)) = updates.values.into_values().next() else {
+                    continue;
+                };
+
+                let current_value
+                    = get_field(manifest, &field_path);
+
+                if current_value == new_value.as_ref() {
+                    continue;
+                }
+
+                if !fix {
+                    workspace_errors.push(match (current_value, new_value) {
+                        (None, Some(new_value)) => WorkspaceError::MissingField {field_path, expected: new_value.into()},
+                        (Some(current_value), None) => WorkspaceError::ExtraneousField {field_path, current_value: current_value.clone().into()},
+                        (Some(current_value), Some(new_value)) => WorkspaceError::InvalidField {field_path, expected: new_value.into(), current_value: current_value.clone().into()},
+                        (None, None) => unreachable!("Fields that must be unset and aren't set already match"),
+                    });
+
+                    continue;
+                }
+
+                workspace_operations.push(match new_value {
+                    Some(value) => WorkspaceOperation::Set {path: field_path.segments().to_vec(), value},
+                    None => WorkspaceOperation::Unset {path: field_path.segments().to_vec()},
+                });
+            }
+
+            if !workspace_operations.is_empty() {
+                output.all_workspace_operations.insert(workspace_cwd.clone(), workspace_operations);
+            }
+
+            if !workspace_errors.is_empty() {
+                output.all_workspace_errors.insert(workspace_cwd, workspace_errors);
+            }
+        }
+
+        output
+    }
+}
+
+/// Select the range that all workspaces should use for a given dependency: the
+/// one used the most often, so that fixing the constraints changes as few
+/// manifests as possible. Ties are broken by keeping the first one seen.
+fn most_common_range<'a>(ranges: impl Iterator<Item = &'a Value>) -> Option<&'a Value> {
+    let mut counts: IndexMap<String, (&'a Value, usize)>
+        = IndexMap::new();
+
+    for range in ranges {
+        counts.entry(range.to_string())
+            .or_insert((range, 0))
+            .1 += 1;
+    }
+
+    counts.into_values()
+        .rev()
+        .max_by_key(|(_, count)| *count)
+        .map(|(range, _)| range)
+}
+
+fn get_field<'a>(manifest: &'a Value, field_path: &zpm_parsers::Path) -> Option<&'a Value> {
+    field_path.segments().iter()
+        .try_fold(manifest, |value, segment| value.as_object()?.get(segment))
+}
+
+/// Evaluate the constraints from `yarn.constraints.yml` directly against the
+/// workspaces of the project, without spawning Node.
+pub fn check_declarative_constraints(project: &Project, config_path: &Path, workspaces: &[ConstraintsWorkspace], fix: bool) -> Result<ConstraintsOutput, Error> {
+    let config_content
+        = config_path.fs_read_text()?;
+
+    let constraints: DeclarativeConstraints
+        = YamlDocument::hydrate_from_str(&config_content)?;
+
+    let mut manifests
+        = BTreeMap::new();
+
+    for workspace in workspaces {
+        let manifest_content = project.project_cwd
+            .with_join(&workspace.cwd)
+            .with_join_str(MANIFEST_NAME)
+            .fs_read_text()?;
+
+        let manifest: Value
+            = JsonDocument::hydrate_from_str(&manifest_content)?;
+
+        manifests.insert(workspace.cwd.clone(), manifest);
+    }
+
+    let mut engine
+        = ConstraintsEngine::default();
+
+    if constraints.enforce_consistent_dependencies {
+        let mut ranges_by_ident: BTreeMap<&Ident, Vec<(&ConstraintsWorkspace, &str, Value)>>
+            = BTreeMap::new();
+
+        for workspace in workspaces {
+            for dependency in workspace.dependencies.iter().chain(workspace.dev_dependencies.iter()) {
+                let field_path
+                    = zpm_parsers::Path::from_segments(vec![dependency.dependency_type.clone(), dependency.ident.to_file_string()]);
+
+                // The dependency may be listed in both `dependencies` and `devDependencies`, in
+                // which case it'll be reported twice; we only compare the ranges that actually exist
+                let Some(range) = get_field(&manifests[&workspace.cwd], &field_path) else {
+                    continue;
+                };
+
+                ranges_by_ident.entry(&dependency.ident)
+                    .or_default()
+                    .push((workspace, dependency.dependency_type.as_str(), range.clone()));
+            }
+        }
+
+        for (ident, entries) in &ranges_by_ident {
+            let Some(expected_range) = most_common_range(entries.iter().map(|(_, _, range)| range)) else {
+                continue;
+            };
+
+            for (workspace, dependency_type, _) in entries {
+                let field_path
+                    = zpm_parsers::Path::from_segments(vec![dependency_type.to_string(), ident.to_file_string()]);
+
+                engine.update(&workspace.cwd, field_path, Some(expected_range.clone()), "enforceConsistentDependencies");
+            }
+        }
+    }
+
+    for enforced_field in &constraints.enforced_fields {
+        let value = match &enforced_field.value {
+            Value::Null => None,
+            value => Some(value.clone()),
+        };
+
+        for workspace in workspaces {
+            if !matches_workspace(&enforced_field.workspaces, &workspace.ident) {
+                continue;
+            }
+
+            engine.update(&workspace.cwd, enforced_field.field.clone(), value.clone(), "enforcedFields");
+        }
+    }
+
+    for banned_dependency in &constraints.banned_dependencies {
+        for workspace in workspaces {
+            if !matches_workspace(&banned_dependency.workspaces, &workspace.ident) {
+                continue;
+            }
+
+            let dependencies = workspace.dependencies.iter()
+                .chain(workspace.dev_dependencies.iter())
+                .chain(workspace.peer_dependencies.iter());
+
+            for dependency in dependencies {
+                if !banned_dependency.ident.check(&dependency.ident) {
+                    continue;
+                }
+
+                let field_path
+                    = zpm_parsers::Path::from_segments(vec![dependency.dependency_type.clone(), dependency.ident.to_file_string()]);
+
+                engine.update(&workspace.cwd, field_path, None, "bannedDependencies");
+            }
+        }
+    }
+
+    let mut output
+        = engine.into_output(&manifests, fix);
+
+    output.raw_json
+        = JsonDocument::to_string_pretty(&output)?.into_bytes();
+
+    Ok(output)
+}
@@ -3,10 +3,11 @@ use zpm_parsers::JsonDocument;
 use zpm_utils::{Path, ToFileString};
 
 use crate::{
-    constraints::structs::{ConstraintsContext, ConstraintsOutput}, error::Error, install::InstallState, project::{Project, Workspace}, resolvers::Resolution, script::ScriptEnvironment
+    constraints::{declarative::{check_declarative_constraints, DECLARATIVE_CONSTRAINTS_FILE}, structs::{ConstraintsContext, ConstraintsOutput}}, error::Error, install::InstallState, project::{Project, Workspace}, resolvers::Resolution, script::ScriptEnvironment
 };
 
 pub mod apply;
+pub mod declarative;
 pub mod structs;
 
 pub async fn check_constraints(project: &Project, fix: bool) -> Result<ConstraintsOutput, Error> {
@@ -19,6 +20,27 @@ pub async fn check_constraints(project: &Project, fix: bool) -> Result<Constrain
             .map(|workspace| to_constraints_workspace(workspace, install_state))
             .collect::<Result<Vec<_>, _>>()?;
 
+    let config_path =
+        [".ts", ".mjs", ".cjs"].iter()
+            .map(|ext| project.project_cwd.with_join_str(&format!("yarn.config{}", ext)))
+            .find(|path| path.fs_exists());
+
+    // Declarative constraints are evaluated natively, so they don't need Node
+    let declarative_config_path
+        = project.project_cwd.with_join_str(DECLARATIVE_CONSTRAINTS_FILE);
+
+    if declarative_config_path.fs_exists() {
+        // Evaluating only one of the two files would silently skip the rules of the other
+        if let Some(config_path) = config_path {
+            return Err(Error::ConflictingConstraintsConfigs(declarative_config_path, config_path));
+        }
+
+        return check_declarative_constraints(project, &declarative_config_path, &constraints_workspaces, fix);
+    }
+
+    let config_path
+        = config_path.ok_or(Error::ConstraintsConfigNotFound)?;
+
     let constraints_packages
         = install_state.resolution_tree.locator_resolutions.iter()
             .map(|(_, resolution)| to_constraints_package(&project, install_state, resolution))
@@ -29,12 +51,6 @@ pub async fn check_constraints(project: &Project, fix: bool) -> Result<Constrain
         packages: constraints_packages,
     };
 
-    let config_path =
-        [".ts", ".mjs", ".cjs"].iter()
-            .map(|ext| project.project_cwd.with_join_str(&format!("yarn.config{}", ext)))
-            .find(|path| path.fs_exists())
-            .ok_or(Error::ConstraintsConfigNotFound)?;
-
     let script
         = generate_constraints_adapter(&config_path, &constraints_context, fix);
 
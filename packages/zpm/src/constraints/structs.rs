@@ -5,7 +5,7 @@ use serde_with::serde_as;
 use zpm_primitives::{Ident, Locator, Range};
 use zpm_utils::{ColoredJsonValue, DataType, Path, ToFileString, ToHumanString};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 #[allow(dead_code)]
 pub struct Caller {
@@ -76,13 +76,13 @@ impl ToHumanString for Caller {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PerValueInfo {
     pub callers: Vec<Caller>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "type")]
 #[serde(rename_all = "camelCase")]
 #[serde(rename_all_fields = "camelCase")]
@@ -114,7 +114,7 @@ pub enum WorkspaceError {
     },
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "type")]
 #[serde(rename_all = "camelCase")]
 #[serde(rename_all_fields = "camelCase")]
@@ -129,7 +129,7 @@ pub enum WorkspaceOperation {
 }
 
 #[serde_as]
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 #[allow(dead_code)]
 pub struct ConstraintsOutput {
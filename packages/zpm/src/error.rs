@@ -4,6 +4,8 @@ use zpm_primitives::{Descriptor, Ident, Locator, Range};
 use zpm_utils::{DataType, Path, ToHumanString};
 use tokio::task::JoinError;
 
+use crate::error_codes::ErrorCode;
+
 fn render_backtrace(backtrace: &std::backtrace::Backtrace) -> String {
     if backtrace.status() == std::backtrace::BacktraceStatus::Captured {
         backtrace.to_string().trim_end().to_string()
@@ -63,6 +65,24 @@ pub enum Error {
     #[error("Invalid publish access: {0}")]
     InvalidNpmPublishAccess(String),
 
+    #[error("Invalid audit environment ({0}); expected one of: all, production, development")]
+    InvalidAuditEnvironment(String),
+
+    #[error("Invalid audit severity ({0}); expected one of: info, low, moderate, high, critical")]
+    InvalidAuditSeverity(String),
+
+    #[error("Invalid SBOM format ({0}); expected one of: cyclonedx, spdx")]
+    InvalidSbomFormat(String),
+
+    #[error("Invalid lockfile export format ({0}); expected one of: npm, pnpm")]
+    InvalidLockfileExportFormat(String),
+
+    #[error("No license policy configured; use --allow or set licensePolicy in your configuration")]
+    MissingLicensePolicy,
+
+    #[error("{0} package(s) don't comply with the license policy")]
+    LicensePolicyViolation(usize),
+
     #[error("Missing environment variable when creating the provenance payload: {0}")]
     MissingEnvironmentVariableForProvenancePayload(String),
 
@@ -87,7 +107,7 @@ pub enum Error {
     #[error("Checksum mismatch for {}", .0.to_print_string())]
     ChecksumMismatch(Locator),
 
-    #[error("[YN0028] The lockfile would have been created by this install, which is explicitly forbidden.")]
+    #[error("The lockfile would have been created by this install, which is explicitly forbidden.")]
     ImmutableLockfile,
 
     #[error("Cannot autofix a lockfile when running an immutable install.")]
@@ -96,9 +116,6 @@ pub enum Error {
     #[error("Found an incorrectly formatted package manifest when running an immutable install ({})", .0.to_print_string())]
     ImmutablePackageManifest(Path),
 
-    #[error("Git returned an error when attempting to autofix the lockfile: {0}")]
-    LockfileAutofixGitError(String),
-
     #[error("The argument folder didn't get created by 'yarn patch'")]
     NotAPatchFolder(Path),
 
@@ -111,19 +128,25 @@ pub enum Error {
     #[error("The lockfile is a v1 lockfile; please first migrate to Yarn Berry then migrate again to Yarn ZPM")]
     LockfileV1Error,
 
-    #[error("[YN0056] Cache entry required but missing for {0:?}.")]
+    #[error("Cache entry required but missing for {0:?}.")]
     ImmutableCache(Locator),
 
-    #[error("[YN0056] {} appears to be unused and would be marked for deletion, but the cache is immutable", .0.to_print_string())]
+    #[error("{} appears to be unused and would be marked for deletion, but the cache is immutable", .0.to_print_string())]
     ImmutableCacheCleanup(Path),
 
-    #[error("[YN0091] Cache path does not exist ({}).", .0.to_print_string())]
+    #[error("Cache path does not exist ({}).", .0.to_print_string())]
     MissingCacheFolder(Path),
 
-    #[error("[YN0080] Request to '{0}' has been blocked because of your configuration settings.")]
+    #[error("Request to '{0}' has been blocked because of your configuration settings.")]
     NetworkDisabledError(reqwest::Url),
 
-    #[error("[YN0081] Unsafe http requests must be explicitly whitelisted in your configuration ({}).", .0.host_str().expect("\"http:\" URL should have a host"))]
+    #[error("The registry metadata for {} isn't available in the offline mirror", .0.to_print_string())]
+    MissingOfflineMirrorMetadata(Ident),
+
+    #[error("The offline mirror is missing {} package(s): {}; run an install with network access and enableOfflineMirror set to populate it", .0.len(), .0.join(", "))]
+    IncompleteOfflineMirror(Vec<String>),
+
+    #[error("Unsafe http requests must be explicitly whitelisted in your configuration ({}).", .0.host_str().expect("\"http:\" URL should have a host"))]
     UnsafeHttpError(reqwest::Url),
 
     #[error("Algolia registry error")]
@@ -216,6 +239,9 @@ pub enum Error {
     #[error("Glob walk error")]
     GlobWalkError(#[from] Arc<wax::walk::WalkError>),
 
+    #[error("File watcher error: {0}")]
+    WatchError(#[from] Arc<notify::Error>),
+
     #[error("UTF-8 error")]
     Utf8Error(#[from] Arc<std::str::Utf8Error>),
 
@@ -252,6 +278,9 @@ pub enum Error {
     #[error("An error occured while parsing the lockfile: {0}")]
     LockfileParseError(zpm_parsers::Error),
 
+    #[error("The lockfile contains nested or unbalanced conflict markers; please resolve the conflict manually")]
+    UnbalancedConflictMarkers,
+
     #[error("Can't perform this operation without a git root")]
     NoGitRoot,
 
@@ -267,6 +296,12 @@ pub enum Error {
     #[error("Failed to read pnpm node_modules directory")]
     PnpmNodeModulesReadError,
 
+    #[error("An error occured while parsing the {0} file: {1}")]
+    ForeignLockfileParseError(String, String),
+
+    #[error("Unsupported {0} version ({1})")]
+    UnsupportedForeignLockfileVersion(String, String),
+
     #[error("An error occured while parsing your configuration: {0}")]
     ConfigurationParseError(Arc<dyn std::error::Error + Send + Sync>),
 
@@ -327,6 +362,9 @@ pub enum Error {
     #[error("Constraints configuration file not found")]
     ConstraintsConfigNotFound,
 
+    #[error("Both {} and {} define constraints; merge them into a single file", .0.to_print_string(), .1.to_print_string())]
+    ConflictingConstraintsConfigs(Path, Path),
+
     #[error("Automatic constraints check failed; run {} to obtain details", DataType::Code.colorize("yarn constraints"))]
     AutoConstraintsError,
 
@@ -378,6 +416,9 @@ pub enum Error {
     #[error("Invalid git url ({0})")]
     InvalidGitUrl(String),
 
+    #[error("Failed to parse the script: {0}")]
+    ShellParseError(String),
+
     #[error("Child process failed ({0})")]
     ChildProcessFailed(String),
 
@@ -492,12 +533,305 @@ pub enum Error {
     #[error("Samply doesn't seem to be installed; first install it by running {}", DataType::Code.colorize("curl https://github.com/mstange/samply/releases/download/samply-v0.13.1/samply-installer.sh | sh"))]
     MissingSamply,
 
+    #[error("The {0} hook ({}) rejected the install: {2}", DataType::Code.colorize(.1))]
+    HookFailed(String, String, String),
+
+    #[error("No executable is configured for the {} protocol; add it to the customProtocols setting", DataType::Code.colorize(&format!("{}:", .0)))]
+    CustomProtocolNotFound(String),
+
+    #[error("The {} protocol executable failed: {1}", DataType::Code.colorize(&format!("{}:", .0)))]
+    CustomProtocolFailed(String, String),
+
+    #[error("This command needs to prompt for input, which requires an interactive terminal")]
+    InteractiveTerminalRequired,
+
+    #[error("Reviewing the blocked builds requires an interactive terminal; pass {} to approve them without prompting", DataType::Code.colorize("--all"))]
+    ApproveBuildsRequiresTerminal,
+
+    #[error("Unknown error code: {0}")]
+    UnknownErrorCode(String),
+
+    #[error("Invalid network configuration: {0}")]
+    InvalidNetworkConfiguration(String),
+
+    #[error("Invalid dist-tag ({0}); tags cannot be valid semver ranges")]
+    InvalidDistTag(String),
+
+    #[error("Expected a package name followed by a version, as in {} (got {0})", DataType::Code.colorize("name@1.2.3"))]
+    InvalidDistTagTarget(String),
+
+    #[error("The {} dist-tag cannot be removed", DataType::Code.colorize("latest"))]
+    CannotRemoveLatestDistTag,
+
+    #[error("No version specified; pass {} to remove every version of {}", DataType::Code.colorize("--force"), .0.to_print_string())]
+    UnpublishRequiresForce(Ident),
+
+    #[error("Version {} of {} isn't published on the registry", .1.to_print_string(), .0.to_print_string())]
+    VersionNotPublished(Ident, zpm_semver::Version),
+
     // Silent error; no particular message, just exit with an exit code 1
     #[error("")]
     SilentError,
 }
 
 impl Error {
+    /// The stable code under which the error is reported; see `yarn explain`.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Error::SilentError
+                => ErrorCode::Unnamed,
+
+            Error::ReplaceMe
+            | Error::Unsupported
+            | Error::SwitchError(..)
+            | Error::PathError(..)
+            | Error::SyncError(..)
+            | Error::SyncError2(..)
+            | Error::SerializationError(..)
+            | Error::FailedToChangeCwd
+            | Error::FileParsingError(..)
+            | Error::IoError {..}
+            | Error::TimeError(..)
+            | Error::ChronoError(..)
+            | Error::GlobError(..)
+            | Error::GlobWalkError(..)
+            | Error::WatchError(..)
+            | Error::Utf8Error(..)
+            | Error::Utf8Error2(..)
+            | Error::NonUtf8Path
+            | Error::ParseIntError(..)
+            | Error::InvalidSha256(..)
+            | Error::InvalidYamlData(..)
+            | Error::UnknownError(..)
+            | Error::PackageConversionError(..)
+            | Error::FromUtf8Error(..)
+            | Error::TaskTimeout
+            | Error::JoinFailed(..)
+            | Error::FailedToGetSwitchDetectedRoot
+                => ErrorCode::Exception,
+
+            Error::CircularBuildDependency(..)
+            | Error::BuildScriptsFailedToRun
+                => ErrorCode::BuildFailed,
+
+            Error::ChecksumMismatch(..)
+                => ErrorCode::CacheChecksumMismatch,
+
+            Error::WorkspaceNotFound(..)
+            | Error::WorkspacePathNotFound(..)
+            | Error::ActivePackageNotFound
+            | Error::ActivePackageNotWorkspace
+                => ErrorCode::WorkspaceNotFound,
+
+            Error::ImmutableLockfile
+            | Error::ImmutableLockfileAutofix
+            | Error::ImmutablePackageManifest(..)
+                => ErrorCode::FrozenLockfileException,
+
+            Error::ImmutableCache(..)
+            | Error::ImmutableCacheCleanup(..)
+                => ErrorCode::ImmutableCache,
+
+            Error::NetworkDisabledError(..)
+                => ErrorCode::NetworkDisabled,
+
+            Error::UnsafeHttpError(..)
+                => ErrorCode::NetworkUnsafeHttp,
+
+            Error::TagNotFound(..)
+            | Error::PackageNotFound(..)
+            | Error::NoMatchingVariantFound(..)
+            | Error::NoCandidatesFound(..)
+            | Error::VersionNotPublished(..)
+            | Error::CatalogNotFound(..)
+            | Error::CatalogEntryNotFound {..}
+            | Error::InvalidResolution(..)
+            | Error::BadResolution(..)
+            | Error::UnsufficientLooseDescriptor(..)
+                => ErrorCode::ResolutionFailed,
+
+            Error::MissingCacheFolder(..)
+                => ErrorCode::MissingCacheFolder,
+
+            Error::HttpError {..}
+            | Error::AlgoliaRegistryError(..)
+            | Error::DnsResolutionError(..)
+            | Error::RemoteRegistryError(..)
+            | Error::FailedToRetrieveLatestClassicVersion
+                => ErrorCode::NetworkError,
+
+            Error::AuthenticationError(..)
+            | Error::ProvenanceRequiresAuthentication
+                => ErrorCode::AuthenticationFailed,
+
+            Error::MissingOfflineMirrorMetadata(..)
+            | Error::IncompleteOfflineMirror(..)
+                => ErrorCode::OfflineMirrorIncomplete,
+
+            Error::HomeDirectoryNotFound
+            | Error::ConfigurationError(..)
+            | Error::ConfigurationHydrateError(..)
+            | Error::ConfigurationParseError(..)
+            | Error::ConfigKeyNotFound(..)
+            | Error::InvalidConfigValue(..)
+            | Error::WorkspaceProfileNotFound(..)
+            | Error::InvalidNetworkConfiguration(..)
+                => ErrorCode::InvalidConfiguration,
+
+            Error::ManifestNotFound(..)
+            | Error::ManifestParseError(..)
+            | Error::MissingPackageManifest
+            | Error::MissingPackageName
+            | Error::LinkedPackageMissingName(..)
+                => ErrorCode::InvalidManifest,
+
+            Error::ProjectNotFound(..)
+                => ErrorCode::ProjectNotFound,
+
+            Error::LockfileV1Error
+            | Error::LockfileReadError(..)
+            | Error::LockfileParseError(..)
+            | Error::UnbalancedConflictMarkers
+            | Error::LegacyLockfileParseError(..)
+            | Error::LockfileGenerationError(..)
+                => ErrorCode::InvalidLockfile,
+
+            Error::PnpmNodeModulesReadError
+            | Error::ForeignLockfileParseError(..)
+            | Error::UnsupportedForeignLockfileVersion(..)
+            | Error::UnsupportedNpmWorkspaces(..)
+                => ErrorCode::LockfileImportFailed,
+
+            Error::InstallStateNotFound
+            | Error::InvalidInstallState
+                => ErrorCode::InstallStateMissing,
+
+            Error::LocatorError(..)
+            | Error::SemverError(..)
+            | Error::UrlError(..)
+            | Error::InvalidIdent(..)
+            | Error::InvalidDescriptor(..)
+            | Error::InvalidRange(..)
+            | Error::InvalidReference(..)
+            | Error::InvalidIdentOrLocator(..)
+            | Error::InvalidGitCommit(..)
+            | Error::InvalidGitBranch(..)
+            | Error::InvalidGitSpecifier
+            | Error::InvalidUrl(..)
+            | Error::InvalidGitUrl(..)
+            | Error::InvalidDistTag(..)
+            | Error::InvalidDistTagTarget(..)
+                => ErrorCode::InvalidPackageSpecifier,
+
+            Error::FormatError(..)
+            | Error::RepositoryCloneFailed(..)
+            | Error::RepositoryCheckoutFailed(..)
+            | Error::InvalidTarFilePath(..)
+                => ErrorCode::FetchFailed,
+
+            Error::ScriptNotFound(..)
+            | Error::GlobalScriptNotFound(..)
+            | Error::AmbiguousScriptName(..)
+            | Error::BinaryNotFound(..)
+            | Error::MissingBinariesDlxContent
+            | Error::AmbiguousDlxContext
+                => ErrorCode::ScriptNotFound,
+
+            Error::SpawnFailed {..}
+            | Error::ShellParseError(..)
+            | Error::ChildProcessFailed(..)
+            | Error::ChildProcessFailedWithLog(..)
+                => ErrorCode::ChildProcessFailed,
+
+            Error::NotAPatchFolder(..)
+            | Error::DiffFailed(..)
+            | Error::EmptyDiff
+            | Error::UnrecognizedPatchPragma(..)
+            | Error::UnsufficientPragmaContext
+            | Error::HunkLinesBeforeHeader
+            | Error::InvalidHunkHeader(..)
+            | Error::InvalidDiffLine(..)
+            | Error::HunkIntegrityCheckFailed
+            | Error::InvalidModeInPatchFile(..)
+            | Error::EmptyPatchFile
+            | Error::MissingRenameTarget
+            | Error::MissingFromPath
+            | Error::MissingToPath
+            | Error::PatchedFileNotFound(..)
+            | Error::UnmatchedHunk(..)
+                => ErrorCode::PatchFailed,
+
+            Error::CannotPublishPrivatePackage
+            | Error::CannotPublishMissingNameOrVersion
+            | Error::InvalidNpmPublishAccess(..)
+            | Error::MissingEnvironmentVariableForProvenancePayload(..)
+            | Error::ProvenanceError(..)
+                => ErrorCode::PublishFailed,
+
+            Error::VersionDeclineNotAllowed
+            | Error::InvalidDeferredVersionBump(..)
+            | Error::NoVersionFoundForActiveWorkspace
+            | Error::NoVersionFoundForWorkspace(..)
+            | Error::RecursiveVersionApplyNotImplemented
+            | Error::VersionBumpLowerThanCurrent(..)
+            | Error::VersionBumpLowerThanDeferred(..)
+            | Error::NoVersionBumpRequiredForProject
+            | Error::VersioningFileNotFound
+            | Error::MultipleVersioningFilesFound
+                => ErrorCode::VersioningFailed,
+
+            Error::NoGitRoot
+            | Error::NoBaseRefs
+            | Error::NoMergeBaseFound(..)
+                => ErrorCode::GitRepositoryRequired,
+
+            Error::ConflictingOptions(..)
+            | Error::IncompatibleOptions(..)
+            | Error::CannotLinkToSelf
+            | Error::InvalidAuditEnvironment(..)
+            | Error::InvalidAuditSeverity(..)
+            | Error::InvalidSbomFormat(..)
+            | Error::InvalidLockfileExportFormat(..)
+            | Error::InvalidDedupeStrategy(..)
+            | Error::InvalidInstallMode(..)
+            | Error::InvalidBenchName(..)
+            | Error::InvalidBenchMode(..)
+            | Error::InvalidRunScriptOptions(..)
+            | Error::InvalidGlob(..)
+            | Error::InvalidWorkspacePattern(..)
+            | Error::InvalidFilePattern(..)
+            | Error::InvalidPackPattern(..)
+            | Error::UnknownErrorCode(..)
+            | Error::CannotRemoveLatestDistTag
+            | Error::UnpublishRequiresForce(..)
+                => ErrorCode::InvalidCommandOptions,
+
+            Error::ConstraintsConfigNotFound
+            | Error::ConflictingConstraintsConfigs(..)
+            | Error::AutoConstraintsError
+                => ErrorCode::ConstraintsFailed,
+
+            Error::MissingLicensePolicy
+            | Error::LicensePolicyViolation(..)
+                => ErrorCode::LicensePolicyViolation,
+
+            Error::MissingRustup
+            | Error::MissingSamply
+                => ErrorCode::MissingTool,
+
+            Error::HookFailed(..)
+                => ErrorCode::HookFailed,
+
+            Error::CustomProtocolNotFound(..)
+            | Error::CustomProtocolFailed(..)
+                => ErrorCode::CustomProtocolFailed,
+
+            Error::InteractiveTerminalRequired
+            | Error::ApproveBuildsRequiresTerminal
+                => ErrorCode::InteractiveTerminalRequired,
+        }
+    }
+
     pub fn ignore<T, F: FnOnce(&Error) -> bool>(self, f: F) -> Result<Option<T>, Error> {
         match f(&self) {
             true => Ok(None),
@@ -527,6 +861,12 @@ impl From<wax::walk::WalkError> for Error {
     }
 }
 
+impl From<notify::Error> for Error {
+    fn from(error: notify::Error) -> Self {
+        Arc::new(error).into()
+    }
+}
+
 
 impl From<std::convert::Infallible> for Error {
     fn from(_: std::convert::Infallible) -> Self {
@@ -0,0 +1,146 @@
+use std::{fmt, str::FromStr};
+
+/**
+ * Each error reported by Yarn is tagged with one of the following codes. The
+ * codes are part of our public interface (they're printed in the reports and
+ * listed by `yarn explain`), so their numbers must never be reassigned; new
+ * codes should always be added at the end of the list.
+ *
+ * The long-form explanations live in `data/error-codes` and are embedded into
+ * the binary so that `yarn explain` keeps working offline.
+ */
+macro_rules! error_codes {
+    ($($variant:ident = $number:literal, $code:literal, $name:literal, $summary:literal;)*) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub enum ErrorCode {
+            $($variant,)*
+        }
+
+        impl ErrorCode {
+            pub const ALL: &[ErrorCode] = &[
+                $(ErrorCode::$variant,)*
+            ];
+
+            pub fn number(&self) -> u16 {
+                match self {
+                    $(ErrorCode::$variant => $number,)*
+                }
+            }
+
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(ErrorCode::$variant => $name,)*
+                }
+            }
+
+            pub fn summary(&self) -> &'static str {
+                match self {
+                    $(ErrorCode::$variant => $summary,)*
+                }
+            }
+
+            pub fn explanation(&self) -> &'static str {
+                match self {
+                    $(ErrorCode::$variant => include_str!(concat!("../data/error-codes/", $code, ".md")),)*
+                }
+            }
+        }
+    };
+}
+
+error_codes! {
+    Unnamed = 0, "YN0000", "UNNAMED", "Generic message without a specific meaning";
+    Exception = 1, "YN0001", "EXCEPTION", "Unexpected internal error";
+    BuildFailed = 9, "YN0009", "BUILD_FAILED", "A package build script failed";
+    CacheChecksumMismatch = 18, "YN0018", "CACHE_CHECKSUM_MISMATCH", "A package archive doesn't match its expected checksum";
+    WorkspaceNotFound = 21, "YN0021", "WORKSPACE_NOT_FOUND", "The requested workspace doesn't exist";
+    FrozenLockfileException = 28, "YN0028", "FROZEN_LOCKFILE_EXCEPTION", "The lockfile or a manifest would be modified by an immutable install";
+    ImmutableCache = 56, "YN0056", "IMMUTABLE_CACHE", "The cache would be modified by an immutable install";
+    NetworkDisabled = 80, "YN0080", "NETWORK_DISABLED", "A network request was blocked by the configuration";
+    NetworkUnsafeHttp = 81, "YN0081", "NETWORK_UNSAFE_HTTP", "An http request was made to a host that isn't whitelisted";
+    ResolutionFailed = 82, "YN0082", "RESOLUTION_FAILED", "A dependency couldn't be resolved to a package";
+    MissingCacheFolder = 91, "YN0091", "MISSING_CACHE_FOLDER", "The local cache folder doesn't exist";
+    NetworkError = 100, "YN0100", "NETWORK_ERROR", "A network request failed";
+    AuthenticationFailed = 101, "YN0101", "AUTHENTICATION_FAILED", "The registry rejected the provided credentials";
+    OfflineMirrorIncomplete = 102, "YN0102", "OFFLINE_MIRROR_INCOMPLETE", "The offline mirror lacks data required by the install";
+    InvalidConfiguration = 103, "YN0103", "INVALID_CONFIGURATION", "A configuration setting is missing or invalid";
+    InvalidManifest = 104, "YN0104", "INVALID_MANIFEST", "A package.json file is missing or invalid";
+    ProjectNotFound = 105, "YN0105", "PROJECT_NOT_FOUND", "The command was run outside of a project";
+    InvalidLockfile = 106, "YN0106", "INVALID_LOCKFILE", "The lockfile couldn't be read or written";
+    LockfileImportFailed = 107, "YN0107", "LOCKFILE_IMPORT_FAILED", "A lockfile from another package manager couldn't be imported";
+    InstallStateMissing = 108, "YN0108", "INSTALL_STATE_MISSING", "The project must be installed first";
+    InvalidPackageSpecifier = 109, "YN0109", "INVALID_PACKAGE_SPECIFIER", "A package name, range, or reference is malformed";
+    FetchFailed = 110, "YN0110", "FETCH_FAILED", "A package couldn't be fetched or extracted";
+    ScriptNotFound = 111, "YN0111", "SCRIPT_NOT_FOUND", "The requested script or binary doesn't exist";
+    ChildProcessFailed = 112, "YN0112", "CHILD_PROCESS_FAILED", "A spawned process failed";
+    PatchFailed = 113, "YN0113", "PATCH_FAILED", "A patch couldn't be generated, parsed, or applied";
+    PublishFailed = 114, "YN0114", "PUBLISH_FAILED", "A package couldn't be published";
+    VersioningFailed = 115, "YN0115", "VERSIONING_FAILED", "A version bump couldn't be recorded or applied";
+    GitRepositoryRequired = 116, "YN0116", "GIT_REPOSITORY_REQUIRED", "The command requires a usable Git repository";
+    InvalidCommandOptions = 117, "YN0117", "INVALID_COMMAND_OPTIONS", "The command line options are invalid";
+    ConstraintsFailed = 118, "YN0118", "CONSTRAINTS_FAILED", "The constraints couldn't be evaluated";
+    LicensePolicyViolation = 119, "YN0119", "LICENSE_POLICY_VIOLATION", "A dependency doesn't satisfy the license policy";
+    MissingTool = 120, "YN0120", "MISSING_TOOL", "An external tool required by the command isn't installed";
+    HookFailed = 121, "YN0121", "HOOK_FAILED", "A hook declared in the configuration rejected the install";
+    CustomProtocolFailed = 122, "YN0122", "CUSTOM_PROTOCOL_FAILED", "A custom protocol executable is missing or failed";
+    InteractiveTerminalRequired = 123, "YN0123", "INTERACTIVE_TERMINAL_REQUIRED", "The command needs to prompt for input but isn't run in a terminal";
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "YN{:04}", self.number())
+    }
+}
+
+/// Accepts both the full code (`YN0028`, case insensitive) and its number (`28`).
+impl FromStr for ErrorCode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = match s.get(..2) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("YN") => &s[2..],
+            _ => s,
+        };
+
+        let number
+            = digits.parse::<u16>()
+                .map_err(|_| ())?;
+
+        ErrorCode::ALL.iter()
+            .find(|code| code.number() == number)
+            .copied()
+            .ok_or(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::ErrorCode;
+
+    #[test]
+    fn should_have_unique_numbers() {
+        let numbers = ErrorCode::ALL.iter()
+            .map(|code| code.number())
+            .collect::<BTreeSet<_>>();
+
+        assert_eq!(numbers.len(), ErrorCode::ALL.len());
+    }
+
+    #[test]
+    fn should_parse_codes() {
+        assert_eq!("YN0028".parse::<ErrorCode>(), Ok(ErrorCode::FrozenLockfileException));
+        assert_eq!("yn0028".parse::<ErrorCode>(), Ok(ErrorCode::FrozenLockfileException));
+        assert_eq!("28".parse::<ErrorCode>(), Ok(ErrorCode::FrozenLockfileException));
+        assert_eq!("YN9999".parse::<ErrorCode>(), Err(()));
+        assert_eq!("foo".parse::<ErrorCode>(), Err(()));
+    }
+
+    #[test]
+    fn should_embed_explanations_matching_their_code() {
+        for code in ErrorCode::ALL {
+            assert!(code.explanation().starts_with(&format!("# {} - {}\n", code, code.name())));
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use serde::Deserialize;
+use serde_json::json;
+use zpm_formats::iter_ext::IterExt;
+use zpm_parsers::JsonDocument;
+use zpm_primitives::{CustomReference, Locator};
+use zpm_utils::{Path, ToFileString};
+
+use crate::{
+    error::Error,
+    install::{FetchResult, InstallContext},
+    manifest::RemoteManifest,
+    npm::NpmEntryExt,
+    resolvers::{Resolution, custom::run_protocol_command},
+};
+
+use super::PackageData;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FetchResponse {
+    archive_path: String,
+}
+
+/// Removes the temporary folder given to the executable once we're done with
+/// it, including when the executable or the unpacking of its archive fails.
+struct TempDirGuard {
+    path: Path,
+}
+
+impl Drop for TempDirGuard {
+    fn drop(&mut self) {
+        let _ = self.path.fs_rm();
+    }
+}
+
+pub async fn fetch_locator<'a>(context: &InstallContext<'a>, locator: &Locator, params: &CustomReference) -> Result<FetchResult, Error> {
+    let project = context.project
+        .expect("The project is required for fetching custom protocol packages");
+
+    let package_cache = context.package_cache
+        .expect("The package cache is required for fetching custom protocol packages");
+
+    let package_subdir
+        = locator.ident.nm_subdir();
+
+    let cached_blob = package_cache.upsert_blob(locator.clone(), ".zip", || async {
+        // The executable may use this folder to store the archive it retrieves
+        let temp_dir = TempDirGuard {
+            path: Path::temp_dir()?,
+        };
+
+        let response: FetchResponse = run_protocol_command(project, &params.protocol, json!({
+            "action": "fetch",
+            "locator": locator.to_file_string(),
+            "ident": locator.ident.to_file_string(),
+            "protocol": params.protocol,
+            "reference": params.payload,
+            "tempDirectory": temp_dir.path.to_file_string(),
+        })).await?;
+
+        let archive_path
+            = project.project_cwd.with_join_str(&response.archive_path);
+
+        let tgz_data
+            = archive_path.fs_read()?;
+
+        drop(temp_dir);
+
+        let tar_data
+            = zpm_formats::tar::unpack_tgz(&tgz_data)?;
+
+        let entries
+            = zpm_formats::tar::entries_from_tar(&tar_data)?
+                .into_iter()
+                .strip_first_segment()
+                .prepare_npm_entries(&package_subdir)
+                .collect::<Vec<_>>();
+
+        Ok(package_cache.bundle_entries(entries)?)
+    }).await?;
+
+    let first_entry
+        = zpm_formats::zip::first_entry_from_zip(&cached_blob.data)?;
+
+    let manifest: RemoteManifest
+        = JsonDocument::hydrate_from_slice(&first_entry.data)?;
+
+    let resolution
+        = Resolution::from_remote_manifest(locator.clone(), manifest);
+
+    let package_directory = cached_blob.info.path
+        .with_join(&package_subdir);
+
+    Ok(FetchResult {
+        resolution: Some(resolution),
+        package_data: PackageData::Zip {
+            archive_path: cached_blob.info.path,
+            checksum: cached_blob.info.checksum,
+            context_directory: package_directory.clone(),
+            package_directory,
+        },
+    })
+}
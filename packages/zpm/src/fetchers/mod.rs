@@ -8,6 +8,7 @@ use crate::{
 };
 
 pub mod builtin;
+pub mod custom;
 pub mod folder;
 pub mod git;
 pub mod link;
@@ -177,6 +178,9 @@ pub async fn fetch_locator<'a>(context: InstallContext<'a>, locator: &Locator, i
         Reference::Url(params)
             => url::fetch_locator(&context, locator, params).await,
 
+        Reference::Custom(params)
+            => custom::fetch_locator(&context, locator, params).await,
+
         Reference::Tarball(params)
             => tarball::fetch_locator(&context, locator, params, dependencies).await,
 
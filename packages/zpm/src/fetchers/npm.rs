@@ -86,6 +86,12 @@ pub async fn fetch_locator<'a>(context: &InstallContext<'a>, locator: &Locator,
                 otp: None,
             }).await?;
 
+        if let Some(integrity) = context.imported_integrities.get(locator) {
+            if !npm::check_integrity(integrity, &bytes) {
+                return Err(Error::ChecksumMismatch(locator.clone()));
+            }
+        }
+
         let tar_data
             = zpm_formats::tar::unpack_tgz(&bytes)?;
 
@@ -30,6 +30,16 @@ impl GitOperation {
             GitOperation::Rebase => "--ours",
         }
     }
+
+    /// Order the two sides of a conflict as `(base, preferred)`, where the
+    /// preferred side is the branch being applied: the bottom one during a
+    /// merge, but the top one during a rebase since git swaps them.
+    pub fn conflict_sides<T>(&self, top: T, bottom: T) -> (T, T) {
+        match self {
+            GitOperation::Merge => (top, bottom),
+            GitOperation::Rebase => (bottom, top),
+        }
+    }
 }
 
 pub async fn detect_git_operation(p: &Path) -> Result<Option<GitOperation>, Error> {
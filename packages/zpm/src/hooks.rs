@@ -0,0 +1,201 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use serde_json::json;
+use zpm_utils::ToFileString;
+
+use crate::{
+    error::Error,
+    fetchers::PackageData,
+    install::Install,
+    linker::LinkResult,
+    project::Project,
+    report::{async_section, current_report},
+    script::{ScriptEnvironment, ScriptResult},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hook {
+    ValidateProject,
+    AfterResolution,
+    AfterFetch,
+    AfterLink,
+}
+
+impl Hook {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Hook::ValidateProject => "validateProject",
+            Hook::AfterResolution => "afterResolution",
+            Hook::AfterFetch => "afterFetch",
+            Hook::AfterLink => "afterLink",
+        }
+    }
+
+    fn commands<'a>(&self, project: &'a Project) -> Vec<&'a str> {
+        let hooks
+            = &project.config.settings.hooks;
+
+        let commands = match self {
+            Hook::ValidateProject => &hooks.validate_project,
+            Hook::AfterResolution => &hooks.after_resolution,
+            Hook::AfterFetch => &hooks.after_fetch,
+            Hook::AfterLink => &hooks.after_link,
+        };
+
+        commands.iter()
+            .map(|setting| setting.value.as_str())
+            .collect()
+    }
+}
+
+/// A message printed by a hook on its standard output. Lines that can't be
+/// parsed as such are reported as-is.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum HookMessage {
+    Info {message: String},
+    Warning {message: String},
+    Error {message: String},
+}
+
+pub fn has_hooks(project: &Project, hook: Hook) -> bool {
+    !hook.commands(project).is_empty()
+}
+
+/**
+ * Hooks are commands declared in the `hooks` setting; they run in the project
+ * folder and receive a JSON payload describing the install on their standard
+ * input. Each line they print on their standard output may be a JSON object
+ * (`{"type": "warning", "message": "..."}`) that will be forwarded to the
+ * report. A hook vetoes the install by exiting with a non-zero code, or by
+ * printing a message of type `error`.
+ */
+pub async fn run_hooks(project: &Project, hook: Hook, payload: serde_json::Value) -> Result<(), Error> {
+    let commands
+        = hook.commands(project);
+
+    if commands.is_empty() {
+        return Ok(());
+    }
+
+    let mut payload
+        = payload;
+
+    payload["hook"] = json!(hook.as_str());
+    payload["projectCwd"] = json!(project.project_cwd.to_file_string());
+
+    let stdin
+        = payload.to_string();
+
+    async_section(&format!("Running the {} hooks", hook.as_str()), async {
+        for command in commands {
+            run_hook(project, hook, command, &stdin).await?;
+        }
+
+        Ok(())
+    }).await
+}
+
+async fn run_hook(project: &Project, hook: Hook, command: &str, stdin: &str) -> Result<(), Error> {
+    let result
+        = ScriptEnvironment::new()?
+            .with_project(project)
+            .with_cwd(project.project_cwd.clone())
+            .with_env_variable("YARN_HOOK", hook.as_str())
+            .with_stdin(Some(stdin.to_string()))
+            .run_script(command, Vec::<String>::new())
+            .await?;
+
+    let (output, success) = match result {
+        ScriptResult::Success(output) => (output, true),
+        ScriptResult::Failure(output, _, _) => (output, false),
+    };
+
+    let stdout
+        = String::from_utf8_lossy(&output.stdout);
+
+    let mut errors
+        = vec![];
+
+    for line in stdout.lines().filter(|line| !line.trim().is_empty()) {
+        let message = serde_json::from_str::<HookMessage>(line)
+            .unwrap_or_else(|_| HookMessage::Info {message: line.to_string()});
+
+        match message {
+            HookMessage::Info {message} => {
+                current_report().await.as_ref().map(|report| report.info(message));
+            },
+
+            HookMessage::Warning {message} => {
+                current_report().await.as_ref().map(|report| report.warn(message));
+            },
+
+            HookMessage::Error {message} => {
+                errors.push(message);
+            },
+        }
+    }
+
+    if success && errors.is_empty() {
+        return Ok(());
+    }
+
+    if errors.is_empty() {
+        let stderr
+            = String::from_utf8_lossy(&output.stderr);
+
+        errors.push(match stderr.trim().is_empty() {
+            true => format!("Exited with {}", output.status),
+            false => stderr.trim().to_string(),
+        });
+    }
+
+    Err(Error::HookFailed(hook.as_str().to_string(), command.to_string(), errors.join("\n")))
+}
+
+pub fn validate_project_payload(project: &Project) -> serde_json::Value {
+    let workspaces = project.workspaces.iter()
+        .map(|workspace| json!({
+            "name": workspace.name.to_file_string(),
+            "cwd": workspace.path.to_file_string(),
+            "relativeCwd": workspace.rel_path.to_file_string(),
+        }))
+        .collect::<Vec<_>>();
+
+    json!({
+        "workspaces": workspaces,
+    })
+}
+
+pub fn after_resolution_payload(install: &Install) -> serde_json::Value {
+    let resolutions = install.lockfile.resolutions.iter()
+        .map(|(descriptor, locator)| (descriptor.to_file_string(), locator.to_file_string()))
+        .collect::<BTreeMap<_, _>>();
+
+    json!({
+        "lockfileChanged": install.lockfile_changed,
+        "resolutions": resolutions,
+    })
+}
+
+pub fn after_fetch_payload(install: &Install) -> serde_json::Value {
+    let packages = install.package_data.iter()
+        .filter(|(_, package_data)| !matches!(package_data, PackageData::Abstract))
+        .map(|(locator, package_data)| (locator.to_file_string(), package_data.package_directory().to_file_string()))
+        .collect::<BTreeMap<_, _>>();
+
+    json!({
+        "packages": packages,
+    })
+}
+
+pub fn after_link_payload(link_result: &LinkResult) -> serde_json::Value {
+    let locations = link_result.packages_by_location.iter()
+        .map(|(location, locator)| (location.to_file_string(), locator.to_file_string()))
+        .collect::<BTreeMap<_, _>>();
+
+    json!({
+        "packageLocations": locations,
+    })
+}
@@ -5,14 +5,15 @@ use dashmap::DashMap;
 use hickory_resolver::{config::LookupIpStrategy, TokioResolver};
 use http::HeaderMap;
 use itertools::Itertools;
-use reqwest::{dns::{self, Addrs}, header::{HeaderName, HeaderValue}, Body, Client, Method, RequestBuilder, Response, Url};
+use reqwest::{dns::{self, Addrs}, header::{HeaderName, HeaderValue, AUTHORIZATION}, Body, Certificate, Client, ClientBuilder, Identity, Method, Proxy, RequestBuilder, Response, Url};
 use tokio::sync::OnceCell;
 use wax::Program;
 use zpm_config::{Configuration, NetworkSettings, Setting};
-use zpm_utils::Glob;
+use zpm_utils::{Glob, Path, ToFileString};
 
 use crate::{
     error::Error,
+    http_npm,
     report::current_report,
 };
 
@@ -25,6 +26,9 @@ pub struct HttpConfig {
     pub unsafe_http_whitelist: Vec<Setting<Glob>>,
     pub slow_network_timeout: u64,
 
+    pub enable_metadata_cache: bool,
+    pub metadata_cache_path: Path,
+
     enable_network: bool,
 
     network_settings: Vec<(Glob, NetworkSettings)>,
@@ -113,11 +117,183 @@ fn new_resolver() -> TokioResolver {
     builder.build()
 }
 
+/// The settings that affect how connections are established. They can be
+/// overriden for specific hosts through `networkSettings`, in which case a
+/// dedicated client is created for those hosts. Since each client has its own
+/// connection limit, this is also what keeps a slow host from starving the
+/// requests made to the other ones.
+#[derive(Debug, Clone)]
+struct TransportSettings {
+    http_proxy: Option<String>,
+    https_proxy: Option<String>,
+    no_proxy: Vec<Glob>,
+    bypass_proxy: bool,
+    ca_file_path: Option<Path>,
+    https_cert_file_path: Option<Path>,
+    https_key_file_path: Option<Path>,
+    network_concurrency: usize,
+}
+
+impl TransportSettings {
+    fn from_config(config: &Configuration) -> Self {
+        let settings
+            = &config.settings;
+
+        Self {
+            http_proxy: settings.http_proxy.value.as_ref().map(|proxy| proxy.value.clone()),
+            https_proxy: settings.https_proxy.value.as_ref().map(|proxy| proxy.value.clone()),
+            no_proxy: settings.no_proxy.iter().map(|glob| glob.value.clone()).collect(),
+            bypass_proxy: false,
+            ca_file_path: settings.ca_file_path.value.clone(),
+            https_cert_file_path: settings.https_cert_file_path.value.clone(),
+            https_key_file_path: settings.https_key_file_path.value.clone(),
+            network_concurrency: settings.network_concurrency.value,
+        }
+    }
+
+    /// Returns the settings to use for the hosts matching a `networkSettings`
+    /// entry, or `None` if the entry doesn't change any of them.
+    fn with_overrides(&self, settings: &NetworkSettings) -> Option<Self> {
+        let has_overrides
+            = settings.http_proxy.value.is_some()
+            || settings.https_proxy.value.is_some()
+            || settings.no_proxy.value.is_some()
+            || settings.ca_file_path.value.is_some()
+            || settings.https_cert_file_path.value.is_some()
+            || settings.https_key_file_path.value.is_some()
+            || settings.network_concurrency.value.is_some();
+
+        if !has_overrides {
+            return None;
+        }
+
+        Some(Self {
+            http_proxy: settings.http_proxy.value.as_ref().map(|proxy| proxy.value.clone()).or_else(|| self.http_proxy.clone()),
+            https_proxy: settings.https_proxy.value.as_ref().map(|proxy| proxy.value.clone()).or_else(|| self.https_proxy.clone()),
+            no_proxy: self.no_proxy.clone(),
+            bypass_proxy: settings.no_proxy.value.unwrap_or(self.bypass_proxy),
+            ca_file_path: settings.ca_file_path.value.clone().or_else(|| self.ca_file_path.clone()),
+            https_cert_file_path: settings.https_cert_file_path.value.clone().or_else(|| self.https_cert_file_path.clone()),
+            https_key_file_path: settings.https_key_file_path.value.clone().or_else(|| self.https_key_file_path.clone()),
+            network_concurrency: settings.network_concurrency.value.unwrap_or(self.network_concurrency),
+        })
+    }
+
+    fn apply(&self, mut builder: ClientBuilder) -> Result<ClientBuilder, Error> {
+        if let Some(ca_file_path) = &self.ca_file_path {
+            let certificates
+                = Certificate::from_pem_bundle(&ca_file_path.fs_read()?)
+                    .map_err(|_| Error::InvalidNetworkConfiguration(format!("Couldn't parse the certificates from caFilePath ({})", ca_file_path.to_file_string())))?;
+
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        match (&self.https_cert_file_path, &self.https_key_file_path) {
+            (Some(cert_file_path), Some(key_file_path)) => {
+                let mut pem
+                    = cert_file_path.fs_read()?;
+
+                pem.push(b'\n');
+                pem.extend(key_file_path.fs_read()?);
+
+                let identity
+                    = Identity::from_pem(&pem)
+                        .map_err(|_| Error::InvalidNetworkConfiguration(format!("Couldn't parse the client certificate from httpsCertFilePath ({}) and httpsKeyFilePath ({})", cert_file_path.to_file_string(), key_file_path.to_file_string())))?;
+
+                builder = builder.identity(identity);
+            },
+
+            (None, None) => {
+            },
+
+            _ => {
+                return Err(Error::InvalidNetworkConfiguration("httpsCertFilePath and httpsKeyFilePath must be set together".to_string()));
+            },
+        }
+
+        if self.bypass_proxy {
+            return Ok(builder.no_proxy());
+        }
+
+        if self.http_proxy.is_none() && self.https_proxy.is_none() {
+            return Ok(builder);
+        }
+
+        // The error messages don't include the URLs since they may contain credentials
+        let http_proxy = self.http_proxy.as_deref()
+            .map(|proxy| Url::parse(proxy).map_err(|_| Error::InvalidNetworkConfiguration("Invalid httpProxy URL".to_string())))
+            .transpose()?;
+
+        let https_proxy = self.https_proxy.as_deref()
+            .map(|proxy| Url::parse(proxy).map_err(|_| Error::InvalidNetworkConfiguration("Invalid httpsProxy URL".to_string())))
+            .transpose()?;
+
+        let no_proxy
+            = self.no_proxy.clone();
+
+        Ok(builder.proxy(Proxy::custom(move |url| {
+            let is_excluded = url.host_str()
+                .is_some_and(|host| no_proxy.iter().any(|glob| glob.is_match(host)));
+
+            if is_excluded {
+                return None;
+            }
+
+            match url.scheme() {
+                "http" => http_proxy.clone(),
+                "https" => https_proxy.clone(),
+                _ => None,
+            }
+        })))
+    }
+}
+
+/// A registry along with the mirrors to query, in order, when a request to
+/// it still fails after all its retries.
+#[derive(Debug)]
+struct RegistryMirrors {
+    registry: String,
+    fallbacks: Vec<RegistryFallback>,
+}
+
+#[derive(Debug)]
+struct RegistryFallback {
+    registry: String,
+    authorization: Option<HeaderValue>,
+}
+
+impl RegistryMirrors {
+    /// Returns the part of the URL that follows the registry, if the URL
+    /// targets this registry.
+    fn strip_registry<'a>(&self, url: &'a str) -> Option<&'a str> {
+        url.strip_prefix(self.registry.as_str())
+            .filter(|rest| rest.is_empty() || rest.starts_with('/') || rest.starts_with('?'))
+    }
+}
+
+fn is_retriable_failure(response: &Result<Response, reqwest::Error>) -> bool {
+    match response {
+        Ok(response) => response.status().is_server_error() || matches!(response.status().as_u16(), 408 | 413 | 429),
+        Err(_) => true,
+    }
+}
+
 pub struct HttpClient {
     pub config: HttpConfig,
 
     client: Client,
 
+    /// Clients dedicated to the hosts whose `networkSettings` entry changes
+    /// how connections are established (proxy, certificates, ...); sorted
+    /// from the most specific pattern to the least specific.
+    host_clients: Vec<(Glob, Client)>,
+
+    /// The mirrors configured through `npmRegistryFallbacks`; sorted from the
+    /// longest registry URL to the shortest.
+    registry_mirrors: Vec<RegistryMirrors>,
+
     /// Cache for GET requests to avoid duplicate network calls for the same URL.
     /// Uses OnceCell for each URL to handle concurrent requests to the same URL.
     get_cache: DashMap<String, Arc<OnceCell<Result<Bytes, Error>>>>,
@@ -128,6 +304,8 @@ impl std::fmt::Debug for HttpClient {
         f.debug_struct("HttpClient")
             .field("config", &self.config)
             .field("client", &self.client)
+            .field("host_clients", &self.host_clients)
+            .field("registry_mirrors", &self.registry_mirrors)
             .field("get_cache", &format!("<{} entries>", self.get_cache.len()))
             .finish()
     }
@@ -145,7 +323,7 @@ pub struct HttpRequest<'a> {
 impl<'a> HttpRequest<'a> {
     pub fn new(client: &'a HttpClient, url: Url, method: Method) -> Self {
         let builder
-            = client.client.request(method.clone(), url.clone());
+            = client.client_for(&url).request(method.clone(), url.clone());
 
         Self {
             builder,
@@ -166,17 +344,47 @@ impl<'a> HttpRequest<'a> {
         self
     }
 
+    /// Sends the request, retrying it on transient failures. If it still
+    /// fails and targets a registry listed in `npmRegistryFallbacks`, the
+    /// request is then replayed against each of its mirrors in order. The
+    /// tarballs obtained this way go through the same checksum validation as
+    /// any other, so a mirror can't substitute a different package.
     pub async fn send(self) -> Result<Response, reqwest::Error> {
+        let mut response
+            = self.send_with_retry(&self.builder, &self.url).await;
+
+        if self.enable_retry && is_retriable_failure(&response) {
+            for (fallback_url, fallback_builder) in self.fallback_requests() {
+                current_report().await.as_ref().map(|report| {
+                    report.warn(format!("Request to {} failed; trying the {} mirror instead", self.url.host_str().unwrap_or_default(), fallback_url.host_str().unwrap_or_default()));
+                });
+
+                response = self.send_with_retry(&fallback_builder, &fallback_url).await;
+
+                if !is_retriable_failure(&response) {
+                    break;
+                }
+            }
+        }
+
+        if self.enable_status_check {
+            response?.error_for_status()
+        } else {
+            response
+        }
+    }
+
+    async fn send_with_retry(&self, builder: &RequestBuilder, url: &Url) -> Result<Response, reqwest::Error> {
         let mut retry_count
             = 0;
 
         let hostname
-            = self.url.host_str()
+            = url.host_str()
                 .map(|s| s.to_string());
 
         loop {
             let mut fetch_future = Box::pin(async {
-                self.builder.try_clone()
+                builder.try_clone()
                     .expect("builder should be clonable")
                     .send()
                     .await
@@ -207,33 +415,51 @@ impl<'a> HttpRequest<'a> {
                 }
             };
 
-            if self.enable_retry && retry_count < self.client.config.http_retry {
-                let is_failure = match &response {
-                    Ok(response) => response.status().is_server_error() || matches!(response.status().as_u16(), 408 | 413 | 429),
-                    Err(_) => true,
-                };
-
-                if is_failure {
-                    retry_count += 1;
+            if self.enable_retry && retry_count < self.client.config.http_retry && is_retriable_failure(&response) {
+                retry_count += 1;
 
-                    let sleep_duration
-                        = 2_u64.saturating_pow(retry_count as u32);
-                    let bounded_sleep_duration
-                        = std::cmp::min(sleep_duration, 10);
+                let sleep_duration
+                    = 2_u64.saturating_pow(retry_count as u32);
+                let bounded_sleep_duration
+                    = std::cmp::min(sleep_duration, 10);
 
-                    tokio::time::sleep(Duration::from_secs(bounded_sleep_duration)).await;
-                    continue;
-                }
+                tokio::time::sleep(Duration::from_secs(bounded_sleep_duration)).await;
+                continue;
             }
 
-            return if self.enable_status_check {
-                response?.error_for_status()
-            } else {
-                response
-            };
+            return response;
         }
     }
 
+    /// Builds the requests to send to the mirrors of the registry targeted by
+    /// this request. The authorization header is only kept when the mirror
+    /// lives on the same host; otherwise the mirror gets the credentials
+    /// configured for it in `npmRegistries`, if any.
+    fn fallback_requests(&self) -> Vec<(Url, RequestBuilder)> {
+        let Some(request) = self.builder.try_clone().and_then(|builder| builder.build().ok()) else {
+            return vec![];
+        };
+
+        self.client.fallback_urls(&self.url).into_iter()
+            .map(|(url, fallback)| {
+                let mut headers
+                    = request.headers().clone();
+
+                if let Some(authorization) = &fallback.authorization {
+                    headers.insert(AUTHORIZATION, authorization.clone());
+                } else if url.host_str() != self.url.host_str() {
+                    headers.remove(AUTHORIZATION);
+                }
+
+                let builder = self.client.client_for(&url)
+                    .request(request.method().clone(), url.clone())
+                    .headers(headers);
+
+                (url, builder)
+            })
+            .collect()
+    }
+
     pub fn headers(&self) -> HeaderMap {
         // TODO: This is filthy
         self.builder.try_clone().unwrap().build().unwrap().headers().clone()
@@ -279,9 +505,53 @@ impl<'a> HttpRequest<'a> {
 
 impl HttpClient {
     pub fn new(config: &Configuration) -> Result<Arc<Self>, Error> {
-        let client = reqwest::Client::builder()
+        let transport_settings
+            = TransportSettings::from_config(config);
+
+        let network_settings: Vec<(Glob, NetworkSettings)> = config.settings.network_settings.clone()
+            .into_iter()
+            // Sort the config by key length to match on the most specific pattern.
+            .sorted_by_cached_key(|(glob, _)| -(glob.raw().len() as isize))
+            .collect();
+
+        let client
+            = Self::build_client(config, &transport_settings)?;
+
+        let host_clients = network_settings.iter()
+            .filter_map(|(glob, settings)| transport_settings.with_overrides(settings).map(|overrides| (glob, overrides)))
+            .map(|(glob, overrides)| Ok((glob.clone(), Self::build_client(config, &overrides)?)))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let registry_mirrors
+            = Self::build_registry_mirrors(config);
+
+        let config = HttpConfig {
+            enforce_unsafe_http: config.settings.enforce_unsafe_http.value,
+            http_retry: config.settings.http_retry.value,
+            unsafe_http_whitelist: config.settings.unsafe_http_whitelist.clone(),
+            slow_network_timeout: config.settings.slow_network_timeout.value,
+
+            enable_metadata_cache: config.settings.enable_metadata_cache.value,
+            metadata_cache_path: config.settings.global_folder.value.with_join_str("metadata"),
+
+            enable_network: config.settings.enable_network.value,
+
+            network_settings,
+        };
+
+        Ok(Arc::new(Self {
+            client,
+            host_clients,
+            registry_mirrors,
+            config,
+            get_cache: DashMap::new(),
+        }))
+    }
+
+    fn build_client(config: &Configuration, transport_settings: &TransportSettings) -> Result<Client, Error> {
+        let builder = reqwest::Client::builder()
             // Connection pooling settings
-            .pool_max_idle_per_host(config.settings.network_concurrency.value)
+            .pool_max_idle_per_host(transport_settings.network_concurrency)
             .pool_idle_timeout(Duration::from_secs(30))
 
             // Timeout settings
@@ -297,43 +567,116 @@ impl HttpClient {
             // Enable connection keep-alive
             .tcp_keepalive(Duration::from_secs(60))
 
-            .connector_layer(tower::limit::concurrency::ConcurrencyLimitLayer::new(config.settings.network_concurrency.value))
+            .connector_layer(tower::limit::concurrency::ConcurrencyLimitLayer::new(transport_settings.network_concurrency))
 
             .use_rustls_tls()
-            .dns_resolver(Arc::new(HickoryDnsResolver::default()))
+            .dns_resolver(Arc::new(HickoryDnsResolver::default()));
+
+        transport_settings.apply(builder)?
             .build()
-            .map_err(|err| Error::DnsResolutionError(Arc::new(err)))?;
+            .map_err(|err| Error::DnsResolutionError(Arc::new(err)))
+    }
 
-        let config = HttpConfig {
-            enforce_unsafe_http: config.settings.enforce_unsafe_http.value,
-            http_retry: config.settings.http_retry.value,
-            unsafe_http_whitelist: config.settings.unsafe_http_whitelist.clone(),
-            slow_network_timeout: config.settings.slow_network_timeout.value,
+    /// The fallbacks of a scope apply to the registry of this scope; when
+    /// several scopes share the same registry, their fallbacks are tried in
+    /// the order of the scopes, followed by the global ones.
+    fn build_registry_mirrors(config: &Configuration) -> Vec<RegistryMirrors> {
+        let default_registry
+            = config.settings.npm_registry_server.value.as_str();
 
-            enable_network: config.settings.enable_network.value,
+        let scope_fallbacks = config.settings.npm_scopes.values()
+            .map(|scope| (scope.npm_registry_server.value.as_deref().unwrap_or(default_registry), &scope.npm_registry_fallbacks));
+
+        let global_fallbacks
+            = std::iter::once((default_registry, &config.settings.npm_registry_fallbacks));
+
+        let mut registry_mirrors: Vec<RegistryMirrors>
+            = Vec::new();
+
+        for (registry, fallbacks) in scope_fallbacks.chain(global_fallbacks) {
+            let registry
+                = registry.trim_end_matches('/');
+
+            let index = match registry_mirrors.iter().position(|mirrors| mirrors.registry == registry) {
+                Some(index) => index,
+                None => {
+                    registry_mirrors.push(RegistryMirrors {registry: registry.to_string(), fallbacks: vec![]});
+                    registry_mirrors.len() - 1
+                },
+            };
+
+            for fallback in fallbacks {
+                let fallback_registry
+                    = fallback.value.trim_end_matches('/');
+
+                let is_known
+                    = fallback_registry == registry
+                    || registry_mirrors[index].fallbacks.iter().any(|fallback| fallback.registry == fallback_registry);
+
+                if is_known {
+                    continue;
+                }
+
+                let authorization
+                    = http_npm::get_registry_authorization(config, fallback_registry)
+                        .and_then(|authorization| HeaderValue::from_str(&authorization).ok())
+                        .map(|mut authorization| {
+                            authorization.set_sensitive(true);
+                            authorization
+                        });
+
+                registry_mirrors[index].fallbacks.push(RegistryFallback {
+                    registry: fallback_registry.to_string(),
+                    authorization,
+                });
+            }
+        }
+
+        registry_mirrors.retain(|mirrors| !mirrors.fallbacks.is_empty());
+        registry_mirrors.sort_by_cached_key(|mirrors| std::cmp::Reverse(mirrors.registry.len()));
 
-            network_settings: config.settings.network_settings.clone()
-                .into_iter()
-                // Sort the config by key length to match on the most specific pattern.
-                .sorted_by_cached_key(|(glob, _)| -(glob.raw().len() as isize))
-                .collect(),
+        registry_mirrors
+    }
+
+    fn fallback_urls(&self, url: &Url) -> Vec<(Url, &RegistryFallback)> {
+        let url_str
+            = url.as_str();
+
+        let Some((mirrors, rest)) = self.registry_mirrors.iter().find_map(|mirrors| mirrors.strip_registry(url_str).map(|rest| (mirrors, rest))) else {
+            return vec![];
         };
 
-        Ok(Arc::new(Self {
-            client,
-            config,
-            get_cache: DashMap::new(),
-        }))
+        mirrors.fallbacks.iter()
+            .filter_map(|fallback| Url::parse(&format!("{}{}", fallback.registry, rest)).ok().map(|url| (url, fallback)))
+            .filter_map(|(url, fallback)| self.check_url(url).ok().map(|url| (url, fallback)))
+            .collect()
+    }
+
+    fn client_for(&self, url: &Url) -> &Client {
+        let Some(host_str) = url.host_str() else {
+            return &self.client;
+        };
+
+        self.host_clients.iter()
+            .find(|(glob, _)| glob.is_match(host_str))
+            .map_or(&self.client, |(_, client)| client)
     }
 
     pub fn request(&self, url: impl AsRef<str>, method: Method) -> Result<HttpRequest<'_>, Error> {
         let url
             = url.as_ref();
 
-        let mut url
+        let url
             = Url::parse(url.as_ref())
                 .map_err(|_| Error::InvalidUrl(url.to_owned()))?;
 
+        let url
+            = self.check_url(url)?;
+
+        Ok(HttpRequest::new(self, url, method))
+    }
+
+    fn check_url(&self, mut url: Url) -> Result<Url, Error> {
         if !self.config.is_network_enabled(&url) {
             return Err(Error::NetworkDisabledError(url));
         }
@@ -353,7 +696,7 @@ impl HttpClient {
             let _ = url.set_scheme("http");
         }
 
-        Ok(HttpRequest::new(self, url, method))
+        Ok(url)
     }
 
     pub fn get(&self, url: impl AsRef<str>) -> Result<HttpRequest<'_>, Error> {
@@ -367,12 +710,7 @@ impl HttpClient {
         let url_str
             = url.as_ref().to_string();
 
-        let cell = self.get_cache
-            .entry(url_str.clone())
-            .or_insert_with(|| Arc::new(OnceCell::new()))
-            .clone();
-
-        let result = cell.get_or_init(|| async {
+        self.cached(url_str.clone(), || async {
             let request
                 = self.get(&url_str)?;
 
@@ -383,9 +721,22 @@ impl HttpClient {
                 = result.bytes().await?;
 
             Ok(bytes)
-        }).await;
+        }).await
+    }
 
-        result.clone()
+    /// Runs the given function only once per key for the lifetime of the
+    /// client, sharing its result with all the callers using the same key.
+    pub async fn cached<F, Fut>(&self, key: String, f: F) -> Result<Bytes, Error>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Bytes, Error>>,
+    {
+        let cell = self.get_cache
+            .entry(key)
+            .or_insert_with(|| Arc::new(OnceCell::new()))
+            .clone();
+
+        cell.get_or_init(f).await.clone()
     }
 
     pub fn post(&self, url: impl AsRef<str>) -> Result<HttpRequest<'_>, Error> {
@@ -395,4 +746,8 @@ impl HttpClient {
     pub fn put(&self, url: impl AsRef<str>) -> Result<HttpRequest<'_>, Error> {
         self.request(url, Method::PUT)
     }
+
+    pub fn delete(&self, url: impl AsRef<str>) -> Result<HttpRequest<'_>, Error> {
+        self.request(url, Method::DELETE)
+    }
 }
@@ -3,11 +3,11 @@ use std::sync::{Arc, LazyLock};
 use bytes::Bytes;
 use regex::{Captures, Regex};
 use reqwest::Response;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use zpm_config::Configuration;
 use zpm_parsers::JsonDocument;
 use zpm_primitives::Ident;
-use zpm_utils::DataType;
+use zpm_utils::{DataType, Hash64, IoResultExt};
 
 use crate::{
     error::Error,
@@ -113,6 +113,33 @@ pub fn get_registry<'a>(config: &'a Configuration, scope: Option<&str>, publish:
     Ok(registry.strip_suffix('/').unwrap_or(registry))
 }
 
+/// Returns the credentials configured in `npmRegistries` for the given
+/// registry, ignoring the scope and global settings. Used for the mirrors
+/// listed in `npmRegistryFallbacks`, which must never receive the
+/// credentials of the registry they back up.
+pub fn get_registry_authorization(config: &Configuration, registry: &str) -> Option<String> {
+    let ident: Option<&Ident>
+        = None;
+
+    let registry
+        = registry.strip_suffix('/').unwrap_or(registry);
+
+    if let Some(auth_token) = scope_registry_setting!(config, registry, ident, npm_auth_token) {
+        return Some(format!("Bearer {}", auth_token.value));
+    }
+
+    scope_registry_setting!(config, registry, ident, npm_auth_ident)
+        .map(|auth_ident| format_auth_ident(auth_ident))
+}
+
+fn format_auth_ident(auth_ident: &str) -> String {
+    if auth_ident.contains(':') {
+        format!("Basic {}", base64::Engine::encode(&base64::engine::general_purpose::STANDARD, auth_ident.as_bytes()))
+    } else {
+        format!("Basic {}", auth_ident)
+    }
+}
+
 pub struct GetAuthorizationOptions<'a> {
     pub configuration: &'a Configuration,
     pub http_client: &'a HttpClient,
@@ -297,11 +324,7 @@ pub async fn get_authorization(options: &GetAuthorizationOptions<'_>) -> Result<
             .or_else(|| options.configuration.settings.npm_auth_ident.value.as_ref());
 
     if let Some(auth_ident) = auth_ident {
-        if auth_ident.contains(':') {
-            return Ok(Some(format!("Basic {}", base64::Engine::encode(&base64::engine::general_purpose::STANDARD, auth_ident.as_bytes()))));
-        } else {
-            return Ok(Some(format!("Basic {}", auth_ident)));
-        }
+        return Ok(Some(format_auth_ident(auth_ident)));
     }
 
     if options.allow_oidc {
@@ -347,6 +370,127 @@ pub async fn get(params: &NpmHttpParams<'_>) -> Result<Bytes, Error> {
     Ok(bytes)
 }
 
+/// The validators sent back by the registry along with a document, stored on
+/// the first line of the metadata cache entries.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MetadataCacheHeaders {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+/// Same as `get`, but meant for the registry metadata: the responses are kept
+/// inside the global folder (unless `enableMetadataCache` is disabled) and
+/// revalidated through `If-None-Match` / `If-Modified-Since`, so that the
+/// registry only sends the document again when it changed. When the network
+/// is disabled the stored document is returned as-is, however old it is.
+pub async fn get_metadata(params: &NpmHttpParams<'_>) -> Result<Bytes, Error> {
+    if !params.http_client.config.enable_metadata_cache {
+        return get(params).await;
+    }
+
+    let url
+        = format!("{}{}", params.registry, params.path);
+
+    // The credentials are part of the key since the registry may return
+    // different documents depending on who's asking
+    let key
+        = Hash64::from_data(format!("{}\n{}", url, params.authorization.unwrap_or_default())).short();
+
+    let entry_path
+        = params.http_client.config.metadata_cache_path.with_join_str(format!("{}.dat", key));
+
+    params.http_client.cached(format!("metadata:{}", key), || async {
+        let cached_entry = entry_path
+            .fs_read()
+            .ok_missing()?
+            .and_then(|data| parse_metadata_cache_entry(&data));
+
+        let request = match params.http_client.get(&url) {
+            Err(Error::NetworkDisabledError(url)) => {
+                return cached_entry
+                    .map(|(_, body)| body)
+                    .ok_or(Error::NetworkDisabledError(url));
+            },
+
+            request => request?,
+        };
+
+        let headers = cached_entry.as_ref()
+            .map(|(headers, _)| headers);
+
+        let response = request
+            .header("authorization", params.authorization)
+            .header("if-none-match", headers.and_then(|headers| headers.etag.as_deref()))
+            .header("if-modified-since", headers.and_then(|headers| headers.last_modified.as_deref()))
+            .enable_status_check(false)
+            .send().await?;
+
+        if params.authorization.is_some() {
+            handle_invalid_authentication_error(params, &response).await?;
+        }
+
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            if let Some((_, body)) = cached_entry {
+                return Ok(body);
+            }
+        }
+
+        let response
+            = response.error_for_status()?;
+
+        let header_value = |name: &str| response.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+
+        let headers = MetadataCacheHeaders {
+            etag: header_value("etag"),
+            last_modified: header_value("last-modified"),
+        };
+
+        let body
+            = response.bytes().await?;
+
+        if headers.etag.is_some() || headers.last_modified.is_some() {
+            write_metadata_cache_entry(&entry_path, &headers, &body)?;
+        }
+
+        Ok(body)
+    }).await
+}
+
+fn parse_metadata_cache_entry(data: &[u8]) -> Option<(MetadataCacheHeaders, Bytes)> {
+    let separator_index
+        = data.iter().position(|&byte| byte == b'\n')?;
+
+    let headers: MetadataCacheHeaders
+        = JsonDocument::hydrate_from_slice(&data[..separator_index]).ok()?;
+
+    Some((headers, Bytes::copy_from_slice(&data[separator_index + 1..])))
+}
+
+/// The entry is first written to a temporary file then moved into place, so
+/// that concurrent processes never observe partially written entries.
+fn write_metadata_cache_entry(entry_path: &zpm_utils::Path, headers: &MetadataCacheHeaders, body: &[u8]) -> Result<(), Error> {
+    let mut data
+        = serde_json::to_vec(headers)
+            .expect("The metadata cache headers should be serializable");
+
+    data.push(b'\n');
+    data.extend_from_slice(body);
+
+    let temp_path = entry_path
+        .with_ext(&format!(".{}.tmp", std::process::id()));
+
+    temp_path
+        .fs_create_parent()?
+        .fs_write(&data)?
+        .fs_move(entry_path)?;
+
+    Ok(())
+}
+
 pub async fn post(params: &NpmHttpParams<'_>, body: String) -> Result<Response, Error> {
     let url
         = format!("{}{}", params.registry, params.path);
@@ -406,6 +550,41 @@ pub async fn put(params: &NpmHttpParams<'_>, body: String) -> Result<Response, E
 
     handle_invalid_authentication_error(params, &response).await?;
 
+    error_for_registry_status(response).await
+}
+
+pub async fn delete(params: &NpmHttpParams<'_>) -> Result<Response, Error> {
+    let url
+        = format!("{}{}", params.registry, params.path);
+
+    let mut request
+        = params.http_client.delete(url)?
+            .enable_status_check(false)
+            .header("authorization", params.authorization);
+
+    let mut response
+        = request
+            .try_clone()
+            .expect("Failed to clone request")
+            .send()
+            .await?;
+
+    if is_otp_error(&response) {
+        let otp
+            = ask_for_otp(params, &response).await?;
+
+        request = inject_otp_headers(request, otp);
+        response = request.send().await?;
+    }
+
+    handle_invalid_authentication_error(params, &response).await?;
+
+    error_for_registry_status(response).await
+}
+
+/// Turns error statuses into errors, including the message the registry
+/// may have provided in the response body.
+async fn error_for_registry_status(response: Response) -> Result<Response, Error> {
     if let Err(error) = response.error_for_status_ref() {
         let body
             = response.text().await?;
@@ -530,7 +709,7 @@ async fn ask_for_otp(params: &NpmHttpParams<'_>, response: &Response) -> Result<
     let otp = current_report().await.as_ref()
         .map(|report| report.prompt(PromptType::Input("One-time password".to_string())))
         .unwrap()
-        .await;
+        .await?;
 
     Ok(otp)
 }
@@ -1,4 +1,4 @@
-use std::{collections::{BTreeMap, BTreeSet}, hash::Hash, marker::PhantomData, sync::LazyLock};
+use std::{collections::{BTreeMap, BTreeSet, HashMap}, hash::Hash, marker::PhantomData, sync::LazyLock};
 
 use chrono::{DateTime, Utc};
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
@@ -16,7 +16,8 @@ use crate::{
     content_flags::ContentFlags,
     error::Error,
     fetchers::{PackageData, SyncFetchAttempt, fetch_locator, patch::has_builtin_patch, try_fetch_locator_sync},
-    graph::{GraphCache, GraphIn, GraphOut, GraphTasks},
+    graph::{GraphCache, GraphIn, GraphOut, GraphTaskResults, GraphTasks},
+    hooks::{self, Hook},
     linker,
     lockfile::{Lockfile, LockfileEntry, LockfileMetadata},
     primitives_exts::{InnerDependencyKind, RangeExt},
@@ -25,6 +26,37 @@ use crate::{
     resolvers::{Resolution, SyncResolutionAttempt, catalog::lookup_catalog_entry, resolve_descriptor, resolve_locator, try_resolve_descriptor_sync, validate_resolution}, tree_resolver::{ResolutionTree, TreeResolver},
 };
 
+/// List the packages whose resolution or fetch failed only because the
+/// network was disabled, ie the ones missing from the offline mirror.
+fn offline_mirror_misses(failed: &[(InstallOp, Error)]) -> Vec<String> {
+    let misses = failed.iter()
+        .filter(|(_, error)| matches!(error, Error::NetworkDisabledError(_) | Error::MissingOfflineMirrorMetadata(_)))
+        .filter_map(|(op, _)| match op {
+            InstallOp::Phantom(_) => None,
+            InstallOp::Resolve {descriptor} | InstallOp::Validate {descriptor, ..} => Some(descriptor.to_print_string()),
+            InstallOp::Refresh {locator} | InstallOp::Fetch {locator, ..} => Some(locator.to_print_string()),
+        })
+        .collect::<BTreeSet<_>>();
+
+    misses.into_iter().collect()
+}
+
+/// Turn the results of an install graph into an error when some of its
+/// operations failed. The network errors are only summarized as offline
+/// mirror misses when the install actually relies on the mirror.
+fn into_install_results<'a>(graph_run: GraphTaskResults<InstallOp<'a>, InstallOpResult, Error>, uses_offline_mirror: bool) -> Result<HashMap<InstallOp<'a>, InstallOpResult>, Error> {
+    if let Some(failed) = graph_run.get_failed().filter(|_| uses_offline_mirror) {
+        let missing_from_offline_mirror
+            = offline_mirror_misses(failed);
+
+        if !missing_from_offline_mirror.is_empty() {
+            return Err(Error::IncompleteOfflineMirror(missing_from_offline_mirror));
+        }
+    }
+
+    graph_run.ok_or(Error::SilentError)
+}
+
 #[derive(Clone)]
 pub struct InstallContext<'a> {
     pub package_cache: Option<&'a CompositeCache>,
@@ -37,6 +69,14 @@ pub struct InstallContext<'a> {
     pub refresh_lockfile: bool,
     pub install_time: DateTime<Utc>,
     pub mode: Option<InstallMode>,
+
+    /// Whether resolved packages are left unfetched, so that they can be
+    /// fetched in a separate pass once the resolution is complete
+    pub defer_fetches: bool,
+
+    /// The integrity hashes imported from another package manager's lockfile,
+    /// checked against the tarballs when they get downloaded
+    pub imported_integrities: BTreeMap<Locator, String>,
 }
 
 impl<'a> Default for InstallContext<'a> {
@@ -52,6 +92,8 @@ impl<'a> Default for InstallContext<'a> {
             refresh_lockfile: false,
             install_time: Utc::now(),
             mode: None,
+            defer_fetches: false,
+            imported_integrities: BTreeMap::new(),
         }
     }
 }
@@ -254,10 +296,15 @@ impl<'a> GraphOut<InstallContext<'a>, InstallOp<'a>> for InstallOpResult {
                 let systems
                     = ctx.systems.unwrap();
 
-                let mut follow_ups = vec![InstallOp::Fetch {
-                    locator: resolution.locator.clone(),
-                    is_mock_request: !resolution.requirements.validate_any(systems),
-                }];
+                let mut follow_ups
+                    = vec![];
+
+                if !ctx.defer_fetches {
+                    follow_ups.push(InstallOp::Fetch {
+                        locator: resolution.locator.clone(),
+                        is_mock_request: !resolution.requirements.validate_any(systems),
+                    });
+                }
 
                 let transitive_dependencies = resolution.dependencies
                     .values()
@@ -511,6 +558,19 @@ impl<'a> GraphCache<InstallContext<'a>, InstallOp<'a>, InstallOpResult, Error> f
                     locator: locator.clone(),
                 })));
             }
+
+            // Packages imported from other lockfiles that couldn't be pinned to a descriptor are
+            // preferred over whatever the registry would return for the ranges they satisfy
+            if let Range::RegistrySemver(RegistrySemverRange {ident: None, range}) = &descriptor.range {
+                let imported_locator = self.lockfile.imported_packages.get(&descriptor.ident)
+                    .and_then(|versions| versions.iter().rev().find(|(version, _)| range.check(version)));
+
+                if let Some((_, locator)) = imported_locator {
+                    return Ok(Some(InstallOpResult::Pinned(PinnedResult {
+                        locator: locator.clone(),
+                    })));
+                }
+            }
         }
 
         Ok(None)
@@ -569,6 +629,8 @@ impl Install {
             let link_result
                 = async_section("Linking the project", link_future).await?;
 
+            hooks::run_hooks(project, Hook::AfterLink, hooks::after_link_payload(&link_result)).await?;
+
             for (location, locator) in &link_result.packages_by_location {
                 self.install_state.locations_by_package.insert(locator.clone(), location.clone());
             }
@@ -582,6 +644,16 @@ impl Install {
                 project.write_lockfile(&self.lockfile)?;
             }
 
+            if !self.skip_build {
+                let install_state = project.install_state.as_ref()
+                    .expect("The install state has just been attached");
+
+                let blocked_builds
+                    = build::find_blocked_builds(project, &self.lockfile, install_state);
+
+                build::report_blocked_builds(&blocked_builds).await;
+            }
+
             if !self.skip_build && !link_result.build_requests.entries.is_empty() {
                 let build_future
                     = build::BuildManager::new(link_result.build_requests).run(project);
@@ -681,6 +753,22 @@ impl<'a> InstallManager<'a> {
         let cache
             = InstallCache::new(self.initial_lockfile.clone());
 
+        let project
+            = self.context.project;
+
+        let uses_offline_mirror
+            = project.is_some_and(|project| project.uses_offline_mirror());
+
+        // The afterResolution hooks must run before any package gets fetched,
+        // so we then only fetch the packages required to resolve the others
+        let has_after_resolution_hooks
+            = project.is_some_and(|project| hooks::has_hooks(project, Hook::AfterResolution));
+
+        self.context.defer_fetches = has_after_resolution_hooks;
+
+        self.context.imported_integrities
+            = self.initial_lockfile.imported_integrities.clone();
+
         let mut graph
             = GraphTasks::new(self.context.clone(), cache);
 
@@ -693,10 +781,10 @@ impl<'a> InstallManager<'a> {
         let graph_run
             = async_section("Installing packages", graph.run()).await;
 
-        let installed_entries = graph_run
-            .ok_or(Error::SilentError)?;
+        let mut deferred_fetches
+            = vec![];
 
-        for entry in installed_entries {
+        for entry in into_install_results(graph_run, uses_offline_mirror)? {
             match entry {
                 (InstallOp::Resolve {..}, InstallOpResult::Validated) => {
                 },
@@ -706,10 +794,12 @@ impl<'a> InstallManager<'a> {
                 },
 
                 (InstallOp::Refresh {..}, InstallOpResult::Resolved(ResolutionResult {resolution, original_resolution, package_data})) => {
+                    deferred_fetches.push(self.deferred_fetch(&resolution));
                     self.record_resolution(resolution, original_resolution, package_data)?;
                 },
 
                 (InstallOp::Resolve {descriptor, ..}, InstallOpResult::Resolved(ResolutionResult {resolution, original_resolution, package_data})) => {
+                    deferred_fetches.push(self.deferred_fetch(&resolution));
                     self.record_descriptor(descriptor, resolution.locator.clone());
                     self.record_resolution(resolution, original_resolution, package_data)?;
                 },
@@ -722,6 +812,37 @@ impl<'a> InstallManager<'a> {
             }
         }
 
+        if let Some(project) = project.filter(|_| has_after_resolution_hooks) {
+            self.result.lockfile.resolutions = self.result.install_state.descriptor_to_locator.clone();
+            self.result.lockfile_changed = self.result.lockfile.resolutions != self.initial_lockfile.resolutions;
+
+            hooks::run_hooks(project, Hook::AfterResolution, hooks::after_resolution_payload(&self.result)).await?;
+
+            let mut fetch_graph
+                = GraphTasks::new(self.context.clone(), InstallCache::new(self.initial_lockfile.clone()));
+
+            for op in deferred_fetches {
+                if let InstallOp::Fetch {locator, ..} = &op {
+                    if !self.result.package_data.contains_key(locator) {
+                        fetch_graph.register(op);
+                    }
+                }
+            }
+
+            let fetch_run
+                = async_section("Fetching packages", fetch_graph.run()).await;
+
+            for entry in into_install_results(fetch_run, uses_offline_mirror)? {
+                match entry {
+                    (InstallOp::Fetch {locator, ..}, InstallOpResult::Fetched(FetchResult {package_data, ..})) => {
+                        self.record_fetch(locator, package_data)?;
+                    },
+
+                    _ => panic!("Unsupported install result ({:?})", entry),
+                }
+            }
+        }
+
         let missing_checksums = self.result.lockfile.entries.values()
             .filter(|entry| {
                 let previous_entry
@@ -812,6 +933,13 @@ impl<'a> InstallManager<'a> {
             .run();
 
         self.result.lockfile.resolutions = self.result.install_state.descriptor_to_locator.clone();
+
+        // The approvals are kept as long as the approved packages remain in the project
+        self.result.lockfile.approved_builds = self.initial_lockfile.approved_builds.iter()
+            .filter(|(locator, _)| self.result.lockfile.entries.contains_key(*locator))
+            .map(|(locator, hash)| (locator.clone(), hash.clone()))
+            .collect();
+
         self.result.lockfile_changed = self.result.lockfile != self.initial_lockfile;
 
         self.result.skip_build = self.context.mode == Some(InstallMode::SkipBuild);
@@ -820,6 +948,10 @@ impl<'a> InstallManager<'a> {
             cache.clean().await?;
         }
 
+        if let Some(project) = project {
+            hooks::run_hooks(project, Hook::AfterFetch, hooks::after_fetch_payload(&self.result)).await?;
+        }
+
         Ok(self.result)
     }
 
@@ -853,6 +985,18 @@ impl<'a> InstallManager<'a> {
         self.result.install_state.descriptor_to_locator.insert(descriptor, locator);
     }
 
+    /// The fetch operation that the resolution would have triggered if the
+    /// fetches hadn't been deferred
+    fn deferred_fetch(&self, resolution: &Resolution) -> InstallOp<'a> {
+        let systems
+            = self.context.systems.unwrap();
+
+        InstallOp::Fetch {
+            locator: resolution.locator.clone(),
+            is_mock_request: !resolution.requirements.validate_any(systems),
+        }
+    }
+
     fn record_fetch(&mut self, locator: Locator, package_data: PackageData) -> Result<(), Error> {
         let content_flags
             = self.previous_state
@@ -10,16 +10,19 @@ pub mod descriptor_loose;
 pub mod diff_finder;
 pub mod manifest_finder;
 pub mod error;
+pub mod error_codes;
 pub mod fetchers;
 pub mod github;
 pub mod git_utils;
 pub mod git;
 pub mod graph;
+pub mod hooks;
 pub mod http_npm;
 pub mod http;
 pub mod install;
 pub mod linker;
 pub mod lockfile;
+pub mod lockfile_export;
 pub mod manifest;
 pub mod misc;
 pub mod npm;
@@ -30,9 +33,13 @@ pub mod primitives_exts;
 pub mod project;
 pub mod provenance;
 pub mod resolvers;
+pub mod sandbox;
+pub mod sbom;
 pub mod report;
 pub mod script;
 pub mod scratchpad;
+pub mod shell;
+pub mod task_cache;
 pub mod tree_resolver;
 pub mod versioning;
 pub mod workspace_glob;
@@ -1,9 +1,10 @@
-use std::{collections::{BTreeMap, BTreeSet}, fs::Permissions, os::unix::fs::PermissionsExt, vec};
+use std::{collections::{BTreeMap, BTreeSet}, fs::Permissions, os::unix::fs::PermissionsExt, sync::{atomic::{AtomicBool, Ordering}, Arc}, vec};
 
 use zpm_formats::iter_ext::IterExt;
 use zpm_parsers::JsonDocument;
 use zpm_primitives::{Descriptor, FilterDescriptor, Locator};
-use zpm_utils::{Path, PathError, System};
+use zpm_sync::FileMaterializer;
+use zpm_utils::{IoResultExt, Path, PathError, Sha256, System};
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 
@@ -24,6 +25,9 @@ pub struct PackageMeta {
 
     #[serde(default, skip_serializing_if = "zpm_utils::is_default")]
     pub unplugged: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "zpm_utils::is_default")]
+    pub allow_network: Option<bool>,
 }
 
 #[serde_as]
@@ -82,7 +86,191 @@ pub fn fs_remove_nm(nm_path: Path) -> Result<(), Error> {
     }
 }
 
-pub fn fs_extract_archive(destination: &Path, package_data: &PackageData) -> Result<bool, Error> {
+/// Content-addressable store shared by all the projects using the same global
+/// folder. Each file is written once under the hash of its content, then
+/// materialized into the projects through reflinks or hardlinks when the
+/// filesystem allows it, falling back to regular copies otherwise.
+///
+/// Hardlinked files share their content with the store, so the linkers must
+/// not materialize packages that get built through it. Entries whose content
+/// got modified regardless are detected and rewritten on the next install.
+#[derive(Clone)]
+pub struct ContentStore {
+    store_path: Path,
+    reflink_supported: Arc<AtomicBool>,
+    hardlink_supported: Arc<AtomicBool>,
+}
+
+impl ContentStore {
+    pub fn from_project(project: &Project) -> Option<Self> {
+        if !project.config.settings.enable_content_store.value {
+            return None;
+        }
+
+        Some(Self {
+            store_path: project.content_store_path(),
+            reflink_supported: Arc::new(AtomicBool::new(true)),
+            hardlink_supported: Arc::new(AtomicBool::new(true)),
+        })
+    }
+
+    pub fn to_file_materializer(&self) -> FileMaterializer {
+        let content_store
+            = self.clone();
+
+        Arc::new(move |path: &Path, data: &[u8], is_exec: bool| {
+            Ok(content_store.materialize(path, data, is_exec)?)
+        })
+    }
+
+    fn entry_path(&self, data: &[u8], is_exec: bool) -> Path {
+        let hash
+            = Sha256::new(data).to_hex();
+
+        // The executable bit is stored on the inode, so hardlinked files
+        // with different modes can't share the same entry
+        let file_name = match is_exec {
+            true => format!("{}-exec", &hash[2..]),
+            false => hash[2..].to_string(),
+        };
+
+        self.store_path
+            .with_join_str(&hash[0..2])
+            .with_join_str(&file_name)
+    }
+
+    fn ensure_entry(&self, data: &[u8], is_exec: bool) -> Result<Path, PathError> {
+        let entry_path
+            = self.entry_path(data, is_exec);
+
+        // Hardlinked entries can be modified from any project they've been
+        // materialized in, so we can't assume an existing entry still holds
+        // the content its name refers to
+        let existing_data = entry_path
+            .fs_read()
+            .ok_missing()?;
+
+        if existing_data.as_deref() == Some(data) {
+            return Ok(entry_path);
+        }
+
+        // Multiple installs may populate the store at the same time, so we
+        // write into a temporary file that we then move into place
+        let temp_path = entry_path
+            .with_ext(&format!(".{:016x}.tmp", rand::random::<u64>()));
+
+        temp_path
+            .fs_create_parent()?
+            .fs_write(data)?
+            .fs_set_permissions(Permissions::from_mode(file_mode(is_exec)))?
+            .fs_rename(&entry_path)?;
+
+        Ok(entry_path)
+    }
+
+    pub fn materialize(&self, destination: &Path, data: &[u8], is_exec: bool) -> Result<(), PathError> {
+        let entry_path
+            = self.ensure_entry(data, is_exec)?;
+
+        // Writing through an existing file could modify a store entry if the
+        // file was previously linked, so we always start from a clean slate
+        destination
+            .fs_rm_file()
+            .ok_missing()?;
+
+        if self.reflink_supported.load(Ordering::Relaxed) {
+            match fs_reflink(&entry_path, destination) {
+                Ok(()) => {
+                    destination.fs_set_permissions(Permissions::from_mode(file_mode(is_exec)))?;
+                    return Ok(());
+                },
+
+                Err(_) => {
+                    self.reflink_supported.store(false, Ordering::Relaxed);
+                },
+            }
+        }
+
+        if self.hardlink_supported.load(Ordering::Relaxed) {
+            match std::fs::hard_link(entry_path.to_path_buf(), destination.to_path_buf()) {
+                Ok(()) => {
+                    return Ok(());
+                },
+
+                Err(_) => {
+                    self.hardlink_supported.store(false, Ordering::Relaxed);
+                },
+            }
+        }
+
+        entry_path.fs_copy_file(destination)?;
+
+        Ok(())
+    }
+}
+
+fn file_mode(is_exec: bool) -> u32 {
+    match is_exec {
+        true => 0o755,
+        false => 0o644,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn fs_reflink(source: &Path, destination: &Path) -> std::io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let source_file
+        = std::fs::File::open(source.to_path_buf())?;
+
+    let destination_file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(destination.to_path_buf())?;
+
+    let result = unsafe {
+        libc::ioctl(destination_file.as_raw_fd(), libc::FICLONE, source_file.as_raw_fd())
+    };
+
+    if result == -1 {
+        let error
+            = std::io::Error::last_os_error();
+
+        drop(destination_file);
+        std::fs::remove_file(destination.to_path_buf())?;
+
+        return Err(error);
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn fs_reflink(source: &Path, destination: &Path) -> std::io::Result<()> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let source
+        = CString::new(source.to_path_buf().as_os_str().as_bytes())?;
+    let destination
+        = CString::new(destination.to_path_buf().as_os_str().as_bytes())?;
+
+    let result = unsafe {
+        libc::clonefile(source.as_ptr(), destination.as_ptr(), 0)
+    };
+
+    if result == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn fs_reflink(_source: &Path, _destination: &Path) -> std::io::Result<()> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+pub fn fs_extract_archive(destination: &Path, package_data: &PackageData, content_store: Option<&ContentStore>) -> Result<bool, Error> {
     let ready_path = destination
         .with_join_str(".ready");
 
@@ -106,9 +294,19 @@ pub fn fs_extract_archive(destination: &Path, package_data: &PackageData) -> Res
                 .with_join(&entry.name);
 
             target_path
-                .fs_create_parent()?
-                .fs_write(&entry.data)?
-                .fs_set_permissions(Permissions::from_mode(entry.mode as u32))?;
+                .fs_create_parent()?;
+
+            match content_store {
+                Some(content_store) => {
+                    content_store.materialize(&target_path, &entry.data, entry.mode & 0o111 != 0)?;
+                },
+
+                None => {
+                    target_path
+                        .fs_write(&entry.data)?
+                        .fs_set_permissions(Permissions::from_mode(entry.mode as u32))?;
+                },
+            }
         }
 
         ready_path
@@ -166,6 +364,7 @@ pub fn populate_build_entry_dependencies(package_build_entries: &BTreeMap<Locato
 pub struct PackageBuildInfo {
     pub must_extract: bool,
     pub build_commands: Option<Vec<build::Command>>,
+    pub allow_network: bool,
 }
 
 pub fn get_package_internal_info(project: &Project, install: &Install, dependencies_meta: &Vec<(FilterDescriptor, PackageMeta)>, locator: &Locator, resolution: &Resolution, physical_package_data: &PackageData) -> PackageBuildInfo {
@@ -190,9 +389,13 @@ pub fn get_package_internal_info(project: &Project, install: &Install, dependenc
     // We don't take into account `is_compatible` here, as it may change
     // depending on the system and we don't want the paths encoded in the
     // .pnp.cjs file to change depending on the system.
+    // Unless explicitly configured, third-party packages are only built once
+    // their build scripts have been approved.
     let should_build_if_compatible
         = package_flags.build_commands.len() > 0
-            && (locator.reference.is_workspace_reference() || package_meta.built.unwrap_or(project.config.settings.enable_scripts.value));
+            && (locator.reference.is_workspace_reference() || package_meta.built.unwrap_or_else(|| {
+                project.config.settings.enable_scripts.value && build::is_build_approved(project, &install.lockfile, locator, &package_flags.build_commands)
+            }));
 
     // Optional dependencies baked by zip archives are always extracted,
     // as we have no way to know whether they would be extracted if we
@@ -220,8 +423,13 @@ pub fn get_package_internal_info(project: &Project, install: &Install, dependenc
     let build_commands
         = must_build.then_some(package_flags.build_commands.clone());
 
+    // Only relevant when the build runs inside the sandbox
+    let allow_network
+        = package_meta.allow_network.unwrap_or(false);
+
     PackageBuildInfo {
         must_extract,
         build_commands,
+        allow_network,
     }
 }
@@ -5,7 +5,7 @@ use zpm_sync::{SyncItem, SyncTemplate, SyncTree};
 use zpm_utils::{FromFileString, Path, ToHumanString};
 
 use crate::{
-    build::BuildRequests, error::Error, fetchers::PackageData, install::Install, linker::{LinkResult, nm::hoist::{Hoister, WorkTree}}, project::Project
+    build::BuildRequests, error::Error, fetchers::PackageData, install::Install, linker::{helpers::ContentStore, LinkResult, nm::hoist::{Hoister, WorkTree}}, project::Project
 };
 
 pub mod hoist;
@@ -112,6 +112,10 @@ pub async fn link_project_nm(project: &Project, install: &Install) -> Result<Lin
 
     hoister.hoist();
 
+    let file_materializer
+        = ContentStore::from_project(project)
+            .map(|content_store| content_store.to_file_materializer());
+
     let mut project_queue
         = vec![0usize];
 
@@ -136,6 +140,7 @@ pub async fn link_project_nm(project: &Project, install: &Install) -> Result<Lin
             = SyncTree::new();
 
         workspace_nm_tree.dry_run = false;
+        workspace_nm_tree.file_materializer = file_materializer.clone();
 
         let workspace_binaries
             = collect_workspace_binaries(install, &work_tree.nodes[workspace_node_idx]);
@@ -347,9 +347,12 @@ pub async fn link_project_pnp<'a>(project: &'a Project, install: &'a Install) ->
                     package_location_abs.clone(),
                 );
 
+                // Unplugged packages are typically built in place, so we don't
+                // want them to share their files with the content store
                 is_freshly_unplugged = linker::helpers::fs_extract_archive(
                     &package_location_abs,
                     physical_package_data,
+                    None,
                 )?;
             }
 
@@ -423,6 +426,7 @@ pub async fn link_project_pnp<'a>(project: &'a Project, install: &'a Install) ->
                 commands: build_commands,
                 allowed_to_fail: install.install_state.resolution_tree.optional_builds.contains(locator),
                 force_rebuild: is_freshly_unplugged,
+                allow_network: package_build_info.allow_network,
             });
         }
     }
@@ -71,12 +71,32 @@ pub async fn link_project_pnpm<'a>(project: &'a Project, install: &'a Install) -
     let dependencies_meta
         = linker::helpers::TopLevelConfiguration::from_project(project);
 
+    let content_store
+        = linker::helpers::ContentStore::from_project(project);
+
     // First pass: copy all packages to store
     for (locator, resolution) in &tree.locator_resolutions {
         let physical_package_data = install.package_data
             .get(&locator.physical_locator())
             .unwrap_or_else(|| panic!("Failed to find physical package data for {}", locator.physical_locator().to_print_string()));
 
+        // Handle build requirements (similar to PnP logic)
+        let package_build_info = linker::helpers::get_package_internal_info(
+            project,
+            install,
+            &dependencies_meta,
+            locator,
+            resolution,
+            physical_package_data,
+        );
+
+        // Packages that get built run their scripts in place, so they can't
+        // share their files with the content store
+        let package_content_store = match package_build_info.build_commands {
+            Some(_) => None,
+            None => content_store.as_ref(),
+        };
+
         let package_base_path = store_path
             .with_join_str(&locator.slug());
 
@@ -92,6 +112,7 @@ pub async fn link_project_pnpm<'a>(project: &'a Project, install: &'a Install) -
                 linker::helpers::fs_extract_archive(
                     &package_store_path,
                     physical_package_data,
+                    package_content_store,
                 )?;
 
                 package_store_path
@@ -117,16 +138,6 @@ pub async fn link_project_pnpm<'a>(project: &'a Project, install: &'a Install) -
             continue;
         }
 
-        // Handle build requirements (similar to PnP logic)
-        let package_build_info = linker::helpers::get_package_internal_info(
-            project,
-            install,
-            &dependencies_meta,
-            locator,
-            resolution,
-            physical_package_data,
-        );
-
         if let Some(build_commands) = package_build_info.build_commands {
             package_build_entries.insert(
                 locator.clone(),
@@ -139,6 +150,7 @@ pub async fn link_project_pnpm<'a>(project: &'a Project, install: &'a Install) -
                 commands: build_commands,
                 allowed_to_fail: install.install_state.resolution_tree.optional_builds.contains(locator),
                 force_rebuild: false, // TODO: track this properly for pnpm
+                allow_network: package_build_info.allow_network,
             });
         }
     }
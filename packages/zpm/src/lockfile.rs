@@ -1,4 +1,4 @@
-use std::{collections::BTreeMap, fmt::{self, Debug, Display}, hash::Hash, marker::PhantomData, sync::Arc};
+use std::{collections::{BTreeMap, BTreeSet}, fmt::{self, Debug, Display}, hash::Hash, marker::PhantomData, sync::Arc};
 
 use rkyv::Archive;
 use itertools::Itertools;
@@ -12,6 +12,10 @@ use crate::{
     error::Error, http_npm, npm, primitives_exts::RangeExt, resolvers::Resolution
 };
 
+#[cfg(test)]
+#[path = "./lockfile.test.rs"]
+mod lockfile_tests;
+
 const LOCKFILE_VERSION: u64 = 9;
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Archive, rkyv::Serialize, rkyv::Deserialize)]
@@ -31,6 +35,21 @@ pub struct Lockfile {
     pub metadata: LockfileMetadata,
     pub resolutions: BTreeMap<Descriptor, Locator>,
     pub entries: BTreeMap<Locator, LockfileEntry>,
+
+    /// The packages whose build scripts got approved through `yarn
+    /// approve-builds`, along with the hash of the approved scripts.
+    pub approved_builds: BTreeMap<Locator, Hash64>,
+
+    /// The integrity hashes found in the lockfiles imported from other package
+    /// managers. They cover the original tarballs, so they're only used to
+    /// validate the first download of each package and aren't persisted.
+    pub imported_integrities: BTreeMap<Locator, String>,
+
+    /// The packages listed in the lockfiles imported from other package
+    /// managers that can't be pinned to a descriptor, since pnpm doesn't store
+    /// the ranges of transitive dependencies. They're used as candidates when
+    /// resolving matching semver ranges; they aren't persisted either.
+    pub imported_packages: BTreeMap<Ident, BTreeMap<zpm_semver::Version, Locator>>,
 }
 
 impl Lockfile {
@@ -39,8 +58,96 @@ impl Lockfile {
             metadata: LockfileMetadata::new(),
             resolutions: BTreeMap::new(),
             entries: BTreeMap::new(),
+            approved_builds: BTreeMap::new(),
+            imported_integrities: BTreeMap::new(),
+            imported_packages: BTreeMap::new(),
         }
     }
+
+    /// Merge the content of another lockfile into this one. When both lockfiles
+    /// resolve the same descriptor, the resolution from `other` wins; entries
+    /// that aren't referenced anymore are discarded. Resolutions that end up
+    /// being unused by the project are pruned by the next install.
+    pub fn merge(mut self, other: Lockfile) -> Lockfile {
+        self.metadata.version
+            = self.metadata.version.max(other.metadata.version);
+
+        self.resolutions.extend(other.resolutions);
+        self.entries.extend(other.entries);
+        self.approved_builds.extend(other.approved_builds);
+
+        let referenced_locators
+            = self.resolutions.values().cloned().collect::<BTreeSet<_>>();
+
+        self.entries.retain(|locator, _| referenced_locators.contains(locator));
+        self.approved_builds.retain(|locator, _| self.entries.contains_key(locator));
+
+        self
+    }
+}
+
+/// Split a file containing git conflict markers into the two versions being
+/// merged: the one above the `=======` separator, and the one below. The
+/// common ancestor section (`|||||||`, emitted by the `diff3` conflict style)
+/// is ignored. Nested or unbalanced markers are reported as an error, since
+/// we can't tell which lines belong to which side.
+pub fn split_conflict_markers(src: &str) -> Result<(String, String), Error> {
+    enum State {
+        Common,
+        Top,
+        Base,
+        Bottom,
+    }
+
+    let mut top
+        = String::new();
+    let mut bottom
+        = String::new();
+
+    let mut state
+        = State::Common;
+
+    for line in src.split_inclusive('\n') {
+        state = match (state, line) {
+            (State::Common, line) if line.starts_with("<<<<<<<") => State::Top,
+            (State::Top, line) if line.starts_with("|||||||") => State::Base,
+            (State::Top | State::Base, line) if line.starts_with("=======") => State::Bottom,
+            (State::Bottom, line) if line.starts_with(">>>>>>>") => State::Common,
+
+            (State::Common, line) if line.starts_with("=======") || line.starts_with(">>>>>>>") || line.starts_with("|||||||") => {
+                return Err(Error::UnbalancedConflictMarkers);
+            },
+
+            (State::Top | State::Base | State::Bottom, line) if line.starts_with("<<<<<<<") => {
+                return Err(Error::UnbalancedConflictMarkers);
+            },
+
+            (State::Common, line) => {
+                top.push_str(line);
+                bottom.push_str(line);
+                State::Common
+            },
+
+            (State::Top, line) => {
+                top.push_str(line);
+                State::Top
+            },
+
+            (State::Base, _) => {
+                State::Base
+            },
+
+            (State::Bottom, line) => {
+                bottom.push_str(line);
+                State::Bottom
+            },
+        };
+    }
+
+    match state {
+        State::Common => Ok((top, bottom)),
+        _ => Err(Error::UnbalancedConflictMarkers),
+    }
 }
 
 impl<'de> Deserialize<'de> for Lockfile {
@@ -50,6 +157,7 @@ impl<'de> Deserialize<'de> for Lockfile {
         let mut lockfile = Lockfile::new();
 
         lockfile.metadata = payload.metadata;
+        lockfile.approved_builds = payload.approved_builds;
 
         for (key, entry) in payload.entries {
             for descriptor in key.0 {
@@ -94,6 +202,7 @@ impl Serialize for Lockfile {
         let payload = LockfilePayload {
             metadata: self.metadata.clone(),
             entries,
+            approved_builds: self.approved_builds.clone(),
         };
 
         payload.serialize(serializer)
@@ -233,6 +342,9 @@ struct LockfilePayload {
 
     #[serde(default)]
     entries: BTreeMap<MultiKey<Descriptor>, LockfileEntry>,
+
+    #[serde(default, rename = "approvedBuilds", skip_serializing_if = "BTreeMap::is_empty")]
+    approved_builds: BTreeMap<Locator, Hash64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -328,22 +440,8 @@ struct PnpmListDependency {
 /// 3. For each package, read its package.json to get the original dependency ranges
 /// 4. Build descriptor -> locator mappings
 pub fn from_pnpm_node_modules(project_cwd: &Path) -> Result<Lockfile, Error> {
-    let user_cwd
-        = Path::home_dir()?;
-
-    let configuration_context = ConfigurationContext {
-        env: std::env::vars().collect(),
-        user_cwd: user_cwd.clone(),
-        project_cwd: Some(project_cwd.clone()),
-        package_cwd: None,
-    };
-
-    let mut last_modified_at
-        = LastModifiedAt::new();
-
     let config
-        = Configuration::load(&configuration_context, &mut last_modified_at)
-            .map_err(|e| Error::ConfigurationParseError(Arc::new(e)))?;
+        = load_import_configuration(project_cwd)?;
 
     let pnpm_dir
         = project_cwd
@@ -435,21 +533,8 @@ pub fn from_pnpm_node_modules(project_cwd: &Path) -> Result<Lockfile, Error> {
                 continue;
             };
 
-            let registry_base
-                = http_npm::get_registry(&config, ident.scope(), false)?;
-
-            // Store the tarball URL only if it's non-conventional (can't be computed from registry + path)
-            let url = if npm::is_conventional_tarball_url(&registry_base, &ident, &version, resolved_field.clone()) {
-                None
-            } else {
-                Some(UrlEncoded::new(resolved_field.clone()))
-            };
-
-            let locator = Locator::new(ident.clone(), RegistryReference {
-                ident: ident,
-                version,
-                url,
-            }.into());
+            let locator
+                = registry_locator(&config, &ident, &version, Some(resolved_field))?;
 
             lockfile.entries.insert(locator.clone(), LockfileEntry {
                 checksum: None,
@@ -462,3 +547,352 @@ pub fn from_pnpm_node_modules(project_cwd: &Path) -> Result<Lockfile, Error> {
 
     Ok(lockfile)
 }
+
+/// The importers need to know the registry configuration to decide whether the
+/// tarball urls they find are conventional or not, but run before the project
+/// is fully loaded.
+fn load_import_configuration(project_cwd: &Path) -> Result<Configuration, Error> {
+    let user_cwd
+        = Path::home_dir()?;
+
+    let configuration_context = ConfigurationContext {
+        env: std::env::vars().collect(),
+        user_cwd: user_cwd.clone(),
+        project_cwd: Some(project_cwd.clone()),
+        package_cwd: None,
+    };
+
+    let mut last_modified_at
+        = LastModifiedAt::new();
+
+    Configuration::load(&configuration_context, &mut last_modified_at)
+        .map_err(|e| Error::ConfigurationParseError(Arc::new(e)))
+}
+
+fn registry_locator(config: &Configuration, ident: &Ident, version: &zpm_semver::Version, tarball_url: Option<&String>) -> Result<Locator, Error> {
+    let registry_base
+        = http_npm::get_registry(config, ident.scope(), false)?;
+
+    // Store the tarball URL only if it's non-conventional (can't be computed from registry + path)
+    let url = match tarball_url {
+        Some(tarball_url) if !npm::is_conventional_tarball_url(&registry_base, ident, version, tarball_url.clone())
+            => Some(UrlEncoded::new(tarball_url.clone())),
+
+        _ => None,
+    };
+
+    Ok(Locator::new(ident.clone(), RegistryReference {
+        ident: ident.clone(),
+        version: version.clone(),
+        url,
+    }.into()))
+}
+
+/// Register a resolution imported from another package manager. The version of
+/// imported lockfiles is set to 1, which causes the install to only reuse the
+/// pinned locators and to fetch the package metadata again. The integrity hashes
+/// from the original lockfiles cover the tarballs rather than our converted
+/// archives, so they're used to validate the downloads rather than stored as
+/// checksums.
+fn insert_imported_resolution(lockfile: &mut Lockfile, descriptor: Descriptor, locator: Locator, version: zpm_semver::Version, integrity: Option<&String>) {
+    lockfile.entries.entry(locator.clone())
+        .or_insert_with(|| LockfileEntry {
+            checksum: None,
+            resolution: Resolution::new_empty(locator.clone(), version),
+        });
+
+    if let Some(integrity) = integrity {
+        lockfile.imported_integrities.insert(locator.clone(), integrity.clone());
+    }
+
+    lockfile.resolutions.insert(descriptor, locator);
+}
+
+/// Parse a range from a foreign lockfile or manifest; we only support importing
+/// raw semver ranges for now.
+fn parse_imported_descriptor(name: &str, range: &str) -> Option<Descriptor> {
+    let ident
+        = Ident::from_file_string(name).ok()?;
+
+    let range
+        = zpm_semver::Range::from_file_string(range).ok()?;
+
+    Some(Descriptor::new(ident, Range::RegistrySemver(RegistrySemverRange {
+        ident: None,
+        range,
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NpmLockfilePackage {
+    #[serde(default)]
+    version: Option<String>,
+
+    #[serde(default)]
+    resolved: Option<String>,
+
+    #[serde(default)]
+    integrity: Option<String>,
+
+    #[serde(default)]
+    link: bool,
+
+    #[serde(default)]
+    dependencies: BTreeMap<String, String>,
+
+    #[serde(default)]
+    dev_dependencies: BTreeMap<String, String>,
+
+    #[serde(default)]
+    optional_dependencies: BTreeMap<String, String>,
+
+    #[serde(default)]
+    peer_dependencies: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NpmLockfilePayload {
+    lockfile_version: u64,
+
+    #[serde(default)]
+    packages: BTreeMap<String, NpmLockfilePackage>,
+}
+
+/// Find the package that Node would load when requiring `name` from the
+/// package installed at `from`, walking up the `node_modules` hierarchy.
+fn find_npm_lockfile_package<'a>(packages: &'a BTreeMap<String, NpmLockfilePackage>, from: &str, name: &str) -> Option<&'a NpmLockfilePackage> {
+    let mut base
+        = from;
+
+    loop {
+        let candidate = match base.is_empty() {
+            true => format!("node_modules/{}", name),
+            false => format!("{}/node_modules/{}", base, name),
+        };
+
+        if let Some(package) = packages.get(&candidate) {
+            return Some(package);
+        }
+
+        if base.is_empty() {
+            return None;
+        }
+
+        base = base.rfind("/node_modules/")
+            .map_or("", |index| &base[..index]);
+    }
+}
+
+/// Builds a lockfile from a `package-lock.json` file (lockfileVersion 2 and 3).
+///
+/// Those lockfiles list all the packages from the `node_modules` tree, keyed by
+/// their path. For each package (workspaces included), we resolve each of its
+/// dependency ranges the same way Node would, and pin the descriptor to the
+/// version installed at that location.
+pub fn from_npm_lockfile(project_cwd: &Path, data: &str) -> Result<Lockfile, Error> {
+    let payload: NpmLockfilePayload
+        = JsonDocument::hydrate_from_str(data)
+            .map_err(|e| Error::ForeignLockfileParseError("package-lock.json".to_string(), e.to_string()))?;
+
+    if payload.lockfile_version < 2 {
+        return Err(Error::UnsupportedForeignLockfileVersion("package-lock.json".to_string(), payload.lockfile_version.to_string()));
+    }
+
+    let config
+        = load_import_configuration(project_cwd)?;
+
+    let mut lockfile
+        = Lockfile::new();
+
+    lockfile.metadata.version = 1;
+
+    for (package_path, package) in &payload.packages {
+        let dependencies = package.dependencies.iter()
+            .chain(package.dev_dependencies.iter())
+            .chain(package.optional_dependencies.iter())
+            .chain(package.peer_dependencies.iter());
+
+        for (name, range) in dependencies {
+            let Some(descriptor) = parse_imported_descriptor(name, range) else {
+                continue;
+            };
+
+            let Some(resolved_package) = find_npm_lockfile_package(&payload.packages, package_path, name) else {
+                continue;
+            };
+
+            // Links point to workspaces or local folders, which aren't resolved through the registry
+            if resolved_package.link {
+                continue;
+            }
+
+            let Some(version) = resolved_package.version.as_deref().and_then(|version| zpm_semver::Version::from_file_string(version).ok()) else {
+                continue;
+            };
+
+            let locator
+                = registry_locator(&config, &descriptor.ident, &version, resolved_package.resolved.as_ref())?;
+
+            insert_imported_resolution(&mut lockfile, descriptor, locator, version, resolved_package.integrity.as_ref());
+        }
+    }
+
+    Ok(lockfile)
+}
+
+#[derive(Debug, Deserialize)]
+struct PnpmLockfileDependency {
+    specifier: String,
+    version: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PnpmLockfileImporter {
+    #[serde(default)]
+    dependencies: BTreeMap<String, PnpmLockfileDependency>,
+
+    #[serde(default)]
+    dev_dependencies: BTreeMap<String, PnpmLockfileDependency>,
+
+    #[serde(default)]
+    optional_dependencies: BTreeMap<String, PnpmLockfileDependency>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PnpmLockfileResolution {
+    #[serde(default)]
+    integrity: Option<String>,
+
+    #[serde(default)]
+    tarball: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PnpmLockfilePackage {
+    #[serde(default)]
+    resolution: PnpmLockfileResolution,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PnpmLockfilePayload {
+    lockfile_version: serde_yaml::Value,
+
+    #[serde(default)]
+    importers: BTreeMap<String, PnpmLockfileImporter>,
+
+    #[serde(default)]
+    packages: BTreeMap<String, PnpmLockfilePackage>,
+}
+
+/// Builds a lockfile from a `pnpm-lock.yaml` file (lockfileVersion 6 and 9).
+///
+/// The pnpm lockfiles only store the ranges declared by the workspaces (the
+/// "importers"); the dependencies of the packages themselves are only listed
+/// with their resolved versions. As a result only the dependencies of the
+/// workspaces can be pinned to their descriptors; the other packages are
+/// registered as candidates for the transitive ranges resolved by the install.
+pub fn from_pnpm_lockfile(project_cwd: &Path, data: &str) -> Result<Lockfile, Error> {
+    let payload: PnpmLockfilePayload = serde_yaml::from_str(data)
+        .map_err(|e| Error::ForeignLockfileParseError("pnpm-lock.yaml".to_string(), e.to_string()))?;
+
+    let lockfile_version = match &payload.lockfile_version {
+        serde_yaml::Value::String(version) => version.clone(),
+        serde_yaml::Value::Number(version) => version.to_string(),
+        _ => String::new(),
+    };
+
+    if !lockfile_version.starts_with("6.") && !lockfile_version.starts_with("9.") {
+        return Err(Error::UnsupportedForeignLockfileVersion("pnpm-lock.yaml".to_string(), lockfile_version));
+    }
+
+    let config
+        = load_import_configuration(project_cwd)?;
+
+    let mut lockfile
+        = Lockfile::new();
+
+    lockfile.metadata.version = 1;
+
+    for (key, package) in &payload.packages {
+        let Some((ident, version)) = parse_pnpm_package_key(key) else {
+            continue;
+        };
+
+        let locator
+            = registry_locator(&config, &ident, &version, package.resolution.tarball.as_ref())?;
+
+        if let Some(integrity) = &package.resolution.integrity {
+            lockfile.imported_integrities.insert(locator.clone(), integrity.clone());
+        }
+
+        lockfile.imported_packages.entry(ident)
+            .or_default()
+            .insert(version, locator);
+    }
+
+    for importer in payload.importers.values() {
+        let dependencies = importer.dependencies.iter()
+            .chain(importer.dev_dependencies.iter())
+            .chain(importer.optional_dependencies.iter());
+
+        for (name, dependency) in dependencies {
+            let Some(descriptor) = parse_imported_descriptor(name, &dependency.specifier) else {
+                continue;
+            };
+
+            // Versions may be suffixed by the resolved peer dependencies (`1.0.0(react@18.0.0)`)
+            let raw_version = dependency.version.split('(')
+                .next()
+                .unwrap_or_default();
+
+            // Links, aliases, and other protocols are resolved again
+            let Ok(version) = zpm_semver::Version::from_file_string(raw_version) else {
+                continue;
+            };
+
+            // The package keys are prefixed with a slash in the v6 format, but not in the v9 one
+            let package = [format!("/{}@{}", name, dependency.version), format!("{}@{}", name, dependency.version), format!("{}@{}", name, raw_version)].iter()
+                .find_map(|key| payload.packages.get(key));
+
+            let tarball_url
+                = package.and_then(|package| package.resolution.tarball.as_ref());
+
+            let integrity
+                = package.and_then(|package| package.resolution.integrity.as_ref());
+
+            let locator
+                = registry_locator(&config, &descriptor.ident, &version, tarball_url)?;
+
+            insert_imported_resolution(&mut lockfile, descriptor, locator, version, integrity);
+        }
+    }
+
+    Ok(lockfile)
+}
+
+/// Extract the name and version from a pnpm package key. The keys are prefixed
+/// with a slash in the v6 format, and may be suffixed by the resolved peer
+/// dependencies (`react-dom@18.0.0(react@18.0.0)`).
+fn parse_pnpm_package_key(key: &str) -> Option<(Ident, zpm_semver::Version)> {
+    let key = key.strip_prefix('/')
+        .unwrap_or(key);
+
+    let key = key.split('(')
+        .next()
+        .unwrap_or_default();
+
+    let (name, version)
+        = npm::split_package_selector(key);
+
+    let ident
+        = Ident::from_file_string(name).ok()?;
+
+    let version
+        = zpm_semver::Version::from_file_string(version?).ok()?;
+
+    Some((ident, version))
+}
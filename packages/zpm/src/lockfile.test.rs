@@ -0,0 +1,231 @@
+use crate::{error::Error, git::GitOperation};
+
+use super::*;
+
+const SIMPLE_CONFLICT: &str = "{
+<<<<<<< HEAD
+  \"a\": 1,
+=======
+  \"a\": 2,
+>>>>>>> feature
+  \"b\": 3
+}
+";
+
+const DIFF3_CONFLICT: &str = "{
+<<<<<<< HEAD
+  \"a\": 1,
+||||||| merged common ancestors
+  \"a\": 0,
+=======
+  \"a\": 2,
+>>>>>>> feature
+  \"b\": 3
+}
+";
+
+const NESTED_CONFLICT: &str = "{
+<<<<<<< HEAD
+<<<<<<< HEAD
+  \"a\": 1,
+=======
+  \"a\": 2,
+>>>>>>> feature
+=======
+  \"a\": 3,
+>>>>>>> other
+}
+";
+
+const UNTERMINATED_CONFLICT: &str = "{
+<<<<<<< HEAD
+  \"a\": 1,
+=======
+  \"a\": 2,
+}
+";
+
+const STRAY_SEPARATOR: &str = "{
+  \"a\": 1,
+=======
+  \"a\": 2,
+}
+";
+
+#[test]
+fn split_simple_conflict() {
+    let (top, bottom) = split_conflict_markers(SIMPLE_CONFLICT).unwrap();
+
+    assert_eq!(top, "{\n  \"a\": 1,\n  \"b\": 3\n}\n");
+    assert_eq!(bottom, "{\n  \"a\": 2,\n  \"b\": 3\n}\n");
+}
+
+#[test]
+fn split_diff3_conflict() {
+    let (top, bottom) = split_conflict_markers(DIFF3_CONFLICT).unwrap();
+
+    assert_eq!(top, "{\n  \"a\": 1,\n  \"b\": 3\n}\n");
+    assert_eq!(bottom, "{\n  \"a\": 2,\n  \"b\": 3\n}\n");
+}
+
+#[test]
+fn split_without_conflict() {
+    let (top, bottom) = split_conflict_markers("{}\n").unwrap();
+
+    assert_eq!(top, "{}\n");
+    assert_eq!(bottom, "{}\n");
+}
+
+#[test]
+fn reject_nested_conflict() {
+    assert!(matches!(split_conflict_markers(NESTED_CONFLICT), Err(Error::UnbalancedConflictMarkers)));
+}
+
+#[test]
+fn reject_unterminated_conflict() {
+    assert!(matches!(split_conflict_markers(UNTERMINATED_CONFLICT), Err(Error::UnbalancedConflictMarkers)));
+}
+
+#[test]
+fn reject_stray_separator() {
+    assert!(matches!(split_conflict_markers(STRAY_SEPARATOR), Err(Error::UnbalancedConflictMarkers)));
+}
+
+fn make_lockfile(resolutions: &[(&str, &str)], approved_builds: &[&str]) -> Lockfile {
+    let mut lockfile
+        = Lockfile::new();
+
+    for (descriptor, locator) in resolutions {
+        let descriptor
+            = Descriptor::from_file_string(descriptor).unwrap();
+        let locator
+            = Locator::from_file_string(locator).unwrap();
+
+        lockfile.resolutions.insert(descriptor, locator.clone());
+        lockfile.entries.insert(locator.clone(), LockfileEntry {
+            checksum: None,
+            resolution: Resolution::new_empty(locator, zpm_semver::Version::default()),
+        });
+    }
+
+    for locator in approved_builds {
+        lockfile.approved_builds.insert(Locator::from_file_string(locator).unwrap(), Hash64::from_data(locator));
+    }
+
+    lockfile
+}
+
+fn resolution_of(lockfile: &Lockfile, descriptor: &str) -> Option<String> {
+    lockfile.resolutions.get(&Descriptor::from_file_string(descriptor).unwrap())
+        .map(|locator| locator.to_file_string())
+}
+
+fn entry_locators(lockfile: &Lockfile) -> Vec<String> {
+    lockfile.entries.keys()
+        .map(|locator| locator.to_file_string())
+        .collect()
+}
+
+#[test]
+fn merge_prefers_the_other_resolution() {
+    let ours
+        = make_lockfile(&[("no-deps@npm:*", "no-deps@npm:1.0.0")], &[]);
+    let theirs
+        = make_lockfile(&[("no-deps@npm:*", "no-deps@npm:2.0.0")], &[]);
+
+    let merged
+        = ours.merge(theirs);
+
+    assert_eq!(resolution_of(&merged, "no-deps@npm:*"), Some("no-deps@npm:2.0.0".to_string()));
+    assert_eq!(entry_locators(&merged), vec!["no-deps@npm:2.0.0"]);
+}
+
+#[test]
+fn merge_keeps_the_resolutions_from_both_sides() {
+    let ours
+        = make_lockfile(&[("no-deps@npm:^1.0.0", "no-deps@npm:1.0.0")], &[]);
+    let theirs
+        = make_lockfile(&[("one-fixed-dep@npm:^1.0.0", "one-fixed-dep@npm:1.0.0")], &[]);
+
+    let merged
+        = ours.merge(theirs);
+
+    assert_eq!(resolution_of(&merged, "no-deps@npm:^1.0.0"), Some("no-deps@npm:1.0.0".to_string()));
+    assert_eq!(resolution_of(&merged, "one-fixed-dep@npm:^1.0.0"), Some("one-fixed-dep@npm:1.0.0".to_string()));
+    assert_eq!(entry_locators(&merged), vec!["no-deps@npm:1.0.0", "one-fixed-dep@npm:1.0.0"]);
+}
+
+#[test]
+fn merge_prunes_unreferenced_entries() {
+    let ours
+        = make_lockfile(&[("no-deps@npm:*", "no-deps@npm:1.0.0"), ("no-deps@npm:^1.0.0", "no-deps@npm:1.0.0")], &[]);
+    let theirs
+        = make_lockfile(&[("no-deps@npm:*", "no-deps@npm:2.0.0")], &[]);
+
+    let merged
+        = ours.merge(theirs);
+
+    // Still referenced by `no-deps@npm:^1.0.0`
+    assert_eq!(entry_locators(&merged), vec!["no-deps@npm:1.0.0", "no-deps@npm:2.0.0"]);
+
+    let ours
+        = make_lockfile(&[("no-deps@npm:*", "no-deps@npm:1.0.0")], &[]);
+    let theirs
+        = make_lockfile(&[("no-deps@npm:*", "no-deps@npm:2.0.0")], &[]);
+
+    let merged
+        = ours.merge(theirs);
+
+    assert_eq!(entry_locators(&merged), vec!["no-deps@npm:2.0.0"]);
+}
+
+#[test]
+fn merge_prunes_unreferenced_approved_builds() {
+    let ours
+        = make_lockfile(&[("no-deps@npm:*", "no-deps@npm:1.0.0"), ("has-bin-entries@npm:*", "has-bin-entries@npm:1.0.0")], &["no-deps@npm:1.0.0", "has-bin-entries@npm:1.0.0"]);
+    let theirs
+        = make_lockfile(&[("no-deps@npm:*", "no-deps@npm:2.0.0")], &["no-deps@npm:2.0.0"]);
+
+    let merged
+        = ours.merge(theirs);
+
+    let approved_builds = merged.approved_builds.keys()
+        .map(|locator| locator.to_file_string())
+        .collect::<Vec<_>>();
+
+    assert_eq!(approved_builds, vec!["has-bin-entries@npm:1.0.0", "no-deps@npm:2.0.0"]);
+}
+
+#[test]
+fn merge_keeps_the_highest_lockfile_version() {
+    let mut ours
+        = make_lockfile(&[], &[]);
+    let mut theirs
+        = make_lockfile(&[], &[]);
+
+    ours.metadata.version = 8;
+    theirs.metadata.version = 9;
+
+    assert_eq!(ours.clone().merge(theirs.clone()).metadata.version, 9);
+    assert_eq!(theirs.merge(ours).metadata.version, 9);
+}
+
+#[test]
+fn prefer_the_incoming_side_of_conflicts() {
+    let (top, bottom)
+        = split_conflict_markers(SIMPLE_CONFLICT).unwrap();
+
+    // During a merge, the top side is our branch and the bottom side the one being merged
+    let (base, preferred)
+        = GitOperation::Merge.conflict_sides(top.as_str(), bottom.as_str());
+
+    assert_eq!(base, top);
+    assert_eq!(preferred, bottom);
+
+    // During a rebase, git swaps them: the top side is the upstream, the bottom side our commit
+    let (base, preferred)
+        = GitOperation::Rebase.conflict_sides(top.as_str(), bottom.as_str());
+
+    assert_eq!(base, bottom);
+    assert_eq!(preferred, top);
+}
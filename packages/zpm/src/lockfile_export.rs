@@ -0,0 +1,526 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use serde::Serialize;
+use zpm_parsers::{JsonDocument, YamlDocument};
+use zpm_primitives::{Ident, Locator, Range};
+use zpm_utils::ToFileString;
+
+use crate::{
+    error::Error,
+    install::InstallState,
+    project::{Project, Workspace},
+    resolvers::Resolution,
+    sbom::{download_url, package_name_and_version},
+};
+
+fn workspace_install_path(workspace: &Workspace) -> String {
+    match workspace.rel_path.as_str() {
+        "." => String::new(),
+        rel_path => rel_path.to_string(),
+    }
+}
+
+fn resolve_dependency<'a>(install_state: &'a InstallState, resolution: &Resolution, ident: &Ident) -> Option<&'a Locator> {
+    let descriptor
+        = resolution.dependencies.get(ident)?;
+
+    install_state.resolution_tree.descriptor_to_locator.get(descriptor)
+}
+
+/// The range as other package managers expect it, without the protocols Yarn
+/// adds when normalizing descriptors; only actual aliases keep the `npm:` prefix
+fn export_range(ident: &Ident, range: &Range) -> String {
+    let physical_range
+        = range.physical_range();
+
+    let is_alias = match physical_range {
+        Range::RegistrySemver(params) => params.ident.as_ref().is_some_and(|alias| alias != ident),
+        Range::RegistryTag(params) => params.ident.as_ref().is_some_and(|alias| alias != ident),
+        _ => false,
+    };
+
+    match is_alias {
+        true => physical_range.to_file_string(),
+        false => physical_range.to_anonymous_range().to_file_string(),
+    }
+}
+
+/// The path at which a package gets installed when nested under another one
+fn nested_install_path(install_path: &str, name: &str) -> String {
+    match install_path.is_empty() {
+        true => format!("node_modules/{}", name),
+        false => format!("{}/node_modules/{}", install_path, name),
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NpmLockfilePackage {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resolved: Option<String>,
+
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    link: bool,
+
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    dependencies: BTreeMap<String, String>,
+
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    dev_dependencies: BTreeMap<String, String>,
+
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    optional_dependencies: BTreeMap<String, String>,
+
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    peer_dependencies: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NpmLockfile {
+    name: String,
+    lockfile_version: u64,
+    requires: bool,
+    packages: BTreeMap<String, NpmLockfilePackage>,
+}
+
+/// Convert the install state into a `package-lock.json` file (lockfileVersion 3).
+///
+/// Since npm lockfiles describe a `node_modules` layout rather than a set of
+/// resolutions, we generate one by hoisting each package as high as possible
+/// without breaking the Node resolution of the packages already placed. The
+/// integrity hashes are omitted, as we only store checksums of the converted
+/// archives.
+pub fn to_npm_lockfile(project: &Project) -> Result<String, Error> {
+    let install_state
+        = project.install_state.as_ref()
+            .ok_or(Error::InstallStateNotFound)?;
+
+    let mut packages
+        = BTreeMap::new();
+
+    // Maps each install path to the (possibly virtual) locator installed there
+    let mut placements: BTreeMap<String, Locator>
+        = BTreeMap::new();
+
+    let mut queue
+        = VecDeque::new();
+
+    // The root workspace is processed first so that its dependencies are the ones
+    // getting hoisted to the top-level `node_modules` folder
+    for workspace in &project.workspaces {
+        let install_path
+            = workspace_install_path(workspace);
+
+        if !install_path.is_empty() {
+            packages.insert(format!("node_modules/{}", workspace.name.to_file_string()), NpmLockfilePackage {
+                resolved: Some(install_path.clone()),
+                link: true,
+                ..Default::default()
+            });
+        }
+
+        let manifest_ranges = |dependencies: &BTreeMap<Ident, zpm_primitives::Descriptor>| dependencies.iter()
+            .map(|(ident, descriptor)| (ident.to_file_string(), export_range(ident, &descriptor.range)))
+            .collect::<BTreeMap<_, _>>();
+
+        packages.insert(install_path.clone(), NpmLockfilePackage {
+            name: Some(workspace.name.to_file_string()),
+            version: workspace.manifest.remote.version.as_ref().map(|version| version.to_file_string()),
+            dependencies: manifest_ranges(&workspace.manifest.remote.dependencies),
+            dev_dependencies: manifest_ranges(&workspace.manifest.dev_dependencies),
+            optional_dependencies: manifest_ranges(&workspace.manifest.remote.optional_dependencies),
+            ..Default::default()
+        });
+
+        queue.push_back((install_path, workspace.locator()));
+    }
+
+    while let Some((install_path, locator)) = queue.pop_front() {
+        let resolution
+            = &install_state.resolution_tree.locator_resolutions[&locator];
+
+        for ident in resolution.dependencies.keys() {
+            // Peer dependencies are provided by the parent package
+            if resolution.peer_dependencies.contains_key(ident) {
+                continue;
+            }
+
+            let Some(dependency) = resolve_dependency(install_state, resolution, ident) else {
+                continue;
+            };
+
+            // Workspaces are linked from the top-level `node_modules` folder
+            if project.try_workspace_by_locator(&dependency.physical_locator())?.is_some() {
+                continue;
+            }
+
+            let name
+                = ident.to_file_string();
+
+            let mut base
+                = install_path.as_str();
+
+            let existing = loop {
+                let candidate
+                    = nested_install_path(base, &name);
+
+                if let Some(existing) = placements.get(&candidate) {
+                    break Some(existing);
+                }
+
+                if base.is_empty() {
+                    break None;
+                }
+
+                base = base.rfind("/node_modules/")
+                    .map_or("", |index| &base[..index]);
+            };
+
+            // When another version is visible from the dependent, we nest the
+            // dependency right under it so that it shadows the conflicting one
+            let target_path = match existing {
+                Some(existing) if existing.physical_locator() == dependency.physical_locator() => continue,
+                Some(_) => nested_install_path(&install_path, &name),
+                None => nested_install_path("", &name),
+            };
+
+            placements.insert(target_path.clone(), dependency.clone());
+            queue.push_back((target_path, dependency.clone()));
+        }
+    }
+
+    for (install_path, locator) in placements {
+        let resolution
+            = &install_state.resolution_tree.locator_resolutions[&locator];
+
+        let physical_locator
+            = locator.physical_locator();
+
+        let (package_ident, version)
+            = package_name_and_version(&physical_locator, &resolution.version);
+
+        let mut package = NpmLockfilePackage {
+            name: (package_ident != physical_locator.ident).then(|| package_ident.to_file_string()),
+            version: Some(version.to_file_string()),
+            resolved: download_url(project, &physical_locator)?,
+            ..Default::default()
+        };
+
+        for (ident, descriptor) in &resolution.dependencies {
+            if resolution.peer_dependencies.contains_key(ident) {
+                continue;
+            }
+
+            let range
+                = export_range(ident, &descriptor.range);
+
+            if resolution.optional_dependencies.contains(ident) {
+                package.optional_dependencies.insert(ident.to_file_string(), range);
+            } else {
+                package.dependencies.insert(ident.to_file_string(), range);
+            }
+        }
+
+        for (ident, range) in &resolution.peer_dependencies {
+            package.peer_dependencies.insert(ident.to_file_string(), range.to_file_string());
+        }
+
+        packages.insert(install_path, package);
+    }
+
+    let lockfile = NpmLockfile {
+        name: project.root_workspace().name.to_file_string(),
+        lockfile_version: 3,
+        requires: true,
+        packages,
+    };
+
+    Ok(format!("{}\n", JsonDocument::to_string_pretty(&lockfile)?))
+}
+
+#[derive(Debug, Serialize)]
+struct PnpmLockfileDependency {
+    specifier: String,
+    version: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PnpmLockfileImporter {
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    dependencies: BTreeMap<String, PnpmLockfileDependency>,
+
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    dev_dependencies: BTreeMap<String, PnpmLockfileDependency>,
+
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    optional_dependencies: BTreeMap<String, PnpmLockfileDependency>,
+}
+
+#[derive(Debug, Serialize)]
+struct PnpmLockfileResolution {
+    tarball: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PnpmLockfilePackage {
+    resolution: PnpmLockfileResolution,
+
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    peer_dependencies: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PnpmLockfileSnapshot {
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    dependencies: BTreeMap<String, String>,
+
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    optional_dependencies: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PnpmLockfileSettings {
+    auto_install_peers: bool,
+    exclude_links_from_lockfile: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PnpmLockfile {
+    lockfile_version: String,
+    settings: PnpmLockfileSettings,
+    importers: BTreeMap<String, PnpmLockfileImporter>,
+    packages: BTreeMap<String, PnpmLockfilePackage>,
+    snapshots: BTreeMap<String, PnpmLockfileSnapshot>,
+}
+
+/// Generates the strings used by pnpm to reference packages from the `packages`
+/// and `snapshots` sections of its lockfile.
+struct PnpmReferences<'a> {
+    project: &'a Project,
+    install_state: &'a InstallState,
+}
+
+impl<'a> PnpmReferences<'a> {
+    /// The package name and version, as used in the keys of the `packages` section
+    fn package_key(&self, locator: &Locator) -> String {
+        let physical_locator
+            = locator.physical_locator();
+
+        let resolution
+            = &self.install_state.resolution_tree.locator_resolutions[locator];
+
+        let (package_ident, version)
+            = package_name_and_version(&physical_locator, &resolution.version);
+
+        format!("{}@{}", package_ident.to_file_string(), version.to_file_string())
+    }
+
+    /// The version, prefixed by the package name for aliased packages
+    fn base_version_reference(&self, locator: &Locator) -> String {
+        let physical_locator
+            = locator.physical_locator();
+
+        let resolution
+            = &self.install_state.resolution_tree.locator_resolutions[locator];
+
+        let (package_ident, version)
+            = package_name_and_version(&physical_locator, &resolution.version);
+
+        match package_ident == physical_locator.ident {
+            true => version.to_file_string(),
+            false => format!("{}@{}", package_ident.to_file_string(), version.to_file_string()),
+        }
+    }
+
+    /// The version, followed by the resolved peer dependencies of virtual packages (`1.0.0(react@18.0.0)`)
+    fn version_reference(&self, locator: &Locator) -> String {
+        let mut reference
+            = self.base_version_reference(locator);
+
+        if locator.reference.is_virtual_reference() {
+            let resolution
+                = &self.install_state.resolution_tree.locator_resolutions[locator];
+
+            for peer_ident in resolution.peer_dependencies.keys() {
+                let Some(peer_locator) = resolve_dependency(self.install_state, resolution, peer_ident) else {
+                    continue;
+                };
+
+                reference.push_str(&format!("({}@{})", peer_ident.to_file_string(), self.base_version_reference(peer_locator)));
+            }
+        }
+
+        reference
+    }
+
+    /// The reference to a dependency as seen from the given workspace
+    fn dependency_reference(&self, from: &Workspace, locator: &Locator) -> Result<String, Error> {
+        if let Some(workspace) = self.project.try_workspace_by_locator(&locator.physical_locator())? {
+            return Ok(format!("link:{}", workspace.path.relative_to(&from.path).to_file_string()));
+        }
+
+        Ok(self.version_reference(locator))
+    }
+
+    /// The key of the `snapshots` section
+    fn snapshot_key(&self, locator: &Locator) -> String {
+        let version_reference
+            = self.version_reference(locator);
+
+        let package_key
+            = self.package_key(locator);
+
+        // Aliased packages already contain the package name in their version reference
+        match version_reference.find('(') {
+            Some(index) => format!("{}{}", package_key, &version_reference[index..]),
+            None => package_key,
+        }
+    }
+}
+
+/// Convert the install state into a `pnpm-lock.yaml` file (lockfileVersion 9).
+///
+/// Virtual packages are exported as distinct snapshots, suffixed by their
+/// resolved peer dependencies the same way pnpm does. As with the npm export,
+/// integrity hashes aren't available and tarball urls are used instead.
+pub fn to_pnpm_lockfile(project: &Project) -> Result<String, Error> {
+    let install_state
+        = project.install_state.as_ref()
+            .ok_or(Error::InstallStateNotFound)?;
+
+    let references = PnpmReferences {
+        project,
+        install_state,
+    };
+
+    let mut importers
+        = BTreeMap::new();
+
+    let mut queue
+        = vec![];
+
+    for workspace in &project.workspaces {
+        let workspace_resolution
+            = &install_state.resolution_tree.locator_resolutions[&workspace.locator()];
+
+        let mut importer
+            = PnpmLockfileImporter::default();
+
+        let dependency_sets = [
+            (&mut importer.dependencies, &workspace.manifest.remote.dependencies),
+            (&mut importer.dev_dependencies, &workspace.manifest.dev_dependencies),
+            (&mut importer.optional_dependencies, &workspace.manifest.remote.optional_dependencies),
+        ];
+
+        for (importer_dependencies, manifest_dependencies) in dependency_sets {
+            for (ident, descriptor) in manifest_dependencies {
+                let Some(locator) = resolve_dependency(install_state, workspace_resolution, ident) else {
+                    continue;
+                };
+
+                importer_dependencies.insert(ident.to_file_string(), PnpmLockfileDependency {
+                    specifier: export_range(ident, &descriptor.range),
+                    version: references.dependency_reference(workspace, locator)?,
+                });
+
+                queue.push(locator.clone());
+            }
+        }
+
+        let importer_key = match workspace_install_path(workspace) {
+            install_path if install_path.is_empty() => ".".to_string(),
+            install_path => install_path,
+        };
+
+        importers.insert(importer_key, importer);
+    }
+
+    let mut packages
+        = BTreeMap::new();
+    let mut snapshots
+        = BTreeMap::new();
+
+    let mut seen
+        = BTreeSet::new();
+
+    while let Some(locator) = queue.pop() {
+        if !seen.insert(locator.clone()) {
+            continue;
+        }
+
+        let physical_locator
+            = locator.physical_locator();
+
+        if project.try_workspace_by_locator(&physical_locator)?.is_some() {
+            continue;
+        }
+
+        let resolution
+            = &install_state.resolution_tree.locator_resolutions[&locator];
+
+        let package_key
+            = references.package_key(&locator);
+
+        if !packages.contains_key(&package_key) {
+            let tarball
+                = download_url(project, &physical_locator)?
+                    .unwrap_or_else(|| physical_locator.reference.to_file_string());
+
+            packages.insert(package_key, PnpmLockfilePackage {
+                resolution: PnpmLockfileResolution {
+                    tarball,
+                },
+                peer_dependencies: resolution.peer_dependencies.iter()
+                    .map(|(ident, range)| (ident.to_file_string(), range.to_file_string()))
+                    .collect(),
+            });
+        }
+
+        let mut snapshot
+            = PnpmLockfileSnapshot::default();
+
+        for ident in resolution.dependencies.keys() {
+            let Some(dependency) = resolve_dependency(install_state, resolution, ident) else {
+                continue;
+            };
+
+            let reference
+                = references.version_reference(dependency);
+
+            if resolution.optional_dependencies.contains(ident) {
+                snapshot.optional_dependencies.insert(ident.to_file_string(), reference);
+            } else {
+                snapshot.dependencies.insert(ident.to_file_string(), reference);
+            }
+
+            queue.push(dependency.clone());
+        }
+
+        snapshots.insert(references.snapshot_key(&locator), snapshot);
+    }
+
+    let lockfile = PnpmLockfile {
+        lockfile_version: "9.0".to_string(),
+        settings: PnpmLockfileSettings {
+            auto_install_peers: true,
+            exclude_links_from_lockfile: false,
+        },
+        importers,
+        packages,
+        snapshots,
+    };
+
+    Ok(YamlDocument::to_string(&lockfile)?)
+}
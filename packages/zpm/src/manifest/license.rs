@@ -0,0 +1,165 @@
+use serde::Deserialize;
+use serde_with::{serde_as, DefaultOnError};
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum LicenseField {
+    String(String),
+
+    // Legacy format, still found in some old packages:
+    // {
+    //   "license": {
+    //     "type": "MIT",
+    //     "url": "https://opensource.org/licenses/MIT"
+    //   }
+    // }
+    Object {
+        #[serde(rename = "type")]
+        type_: String,
+    },
+}
+
+impl LicenseField {
+    pub fn as_str(&self) -> &str {
+        match self {
+            LicenseField::String(license) => license,
+            LicenseField::Object {type_} => type_,
+        }
+    }
+}
+
+/**
+ * Only the license-related fields of a package manifest; we don't store them in
+ * the regular `Manifest` struct since they're only needed by a handful of commands.
+ */
+#[serde_as]
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LicenseManifest {
+    #[serde(default)]
+    #[serde_as(deserialize_as = "DefaultOnError")]
+    pub license: Option<LicenseField>,
+
+    #[serde(default)]
+    #[serde_as(deserialize_as = "DefaultOnError")]
+    pub licenses: Option<Vec<LicenseField>>,
+}
+
+impl LicenseManifest {
+    /// Return the license expression declared by the package, combining the
+    /// entries of the legacy `licenses` array if needed.
+    pub fn license_expression(&self) -> Option<String> {
+        if let Some(license) = &self.license {
+            let license
+                = license.as_str().trim();
+
+            return (!license.is_empty()).then(|| license.to_string());
+        }
+
+        let licenses = self.licenses.iter()
+            .flatten()
+            .map(|license| license.as_str().trim())
+            .filter(|license| !license.is_empty())
+            .collect::<Vec<_>>();
+
+        match licenses.len() {
+            0 => None,
+            1 => Some(licenses[0].to_string()),
+            _ => Some(format!("({})", licenses.join(" OR "))),
+        }
+    }
+}
+
+/// Check whether the given SPDX expression is satisfied by the allowed
+/// licenses. `OR` expressions require any of their operands to be allowed,
+/// `AND` expressions require all of them. License exceptions (`WITH`) are
+/// ignored, and malformed expressions are never considered satisfied.
+pub fn is_license_allowed(expression: &str, allowed: &[&str]) -> bool {
+    let normalized
+        = expression.replace('(', " ( ").replace(')', " ) ");
+
+    let tokens
+        = normalized.split_whitespace().collect::<Vec<_>>();
+
+    let mut position
+        = 0;
+
+    let result
+        = evaluate_or(&tokens, &mut position, allowed);
+
+    result == Some(true) && position == tokens.len()
+}
+
+fn evaluate_or(tokens: &[&str], position: &mut usize, allowed: &[&str]) -> Option<bool> {
+    let mut result
+        = evaluate_and(tokens, position, allowed)?;
+
+    while tokens.get(*position).is_some_and(|token| token.eq_ignore_ascii_case("OR")) {
+        *position += 1;
+        result |= evaluate_and(tokens, position, allowed)?;
+    }
+
+    Some(result)
+}
+
+fn evaluate_and(tokens: &[&str], position: &mut usize, allowed: &[&str]) -> Option<bool> {
+    let mut result
+        = evaluate_term(tokens, position, allowed)?;
+
+    while tokens.get(*position).is_some_and(|token| token.eq_ignore_ascii_case("AND")) {
+        *position += 1;
+        result &= evaluate_term(tokens, position, allowed)?;
+    }
+
+    Some(result)
+}
+
+fn evaluate_term(tokens: &[&str], position: &mut usize, allowed: &[&str]) -> Option<bool> {
+    let token
+        = *tokens.get(*position)?;
+
+    *position += 1;
+
+    if token == "(" {
+        let result
+            = evaluate_or(tokens, position, allowed)?;
+
+        if tokens.get(*position) != Some(&")") {
+            return None;
+        }
+
+        *position += 1;
+        return Some(result);
+    }
+
+    if token == ")" || token.eq_ignore_ascii_case("AND") || token.eq_ignore_ascii_case("OR") || token.eq_ignore_ascii_case("WITH") {
+        return None;
+    }
+
+    if tokens.get(*position).is_some_and(|token| token.eq_ignore_ascii_case("WITH")) {
+        tokens.get(*position + 1)?;
+        *position += 2;
+    }
+
+    Some(allowed.iter().any(|license| license.eq_ignore_ascii_case(token)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_license_allowed;
+
+    #[test]
+    fn should_check_license_expressions() {
+        let allowed
+            = ["MIT", "Apache-2.0"];
+
+        assert!(is_license_allowed("MIT", &allowed));
+        assert!(is_license_allowed("(MIT OR GPL-3.0)", &allowed));
+        assert!(is_license_allowed("MIT AND Apache-2.0", &allowed));
+        assert!(is_license_allowed("Apache-2.0 WITH LLVM-exception", &allowed));
+
+        assert!(!is_license_allowed("GPL-3.0", &allowed));
+        assert!(!is_license_allowed("MIT AND GPL-3.0", &allowed));
+        assert!(!is_license_allowed("(MIT OR", &allowed));
+        assert!(!is_license_allowed("", &allowed));
+    }
+}
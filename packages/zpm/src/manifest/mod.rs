@@ -18,6 +18,7 @@ pub mod browser;
 pub mod exports;
 pub mod helpers;
 pub mod imports;
+pub mod license;
 pub mod resolutions;
 
 #[derive(Clone, Debug, Deserialize, Serialize, Archive, rkyv::Serialize, rkyv::Deserialize)]
@@ -37,6 +38,25 @@ pub struct PeerDependenciesMeta {
     pub optional: bool,
 }
 
+/// Declares the files a script reads and writes, allowing its results to be
+/// cached and restored when its inputs didn't change.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq, Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptMeta {
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub inputs: Vec<String>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub outputs: Vec<String>,
+
+    /// The environment variables whose values affect the script output
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub env: Vec<String>,
+}
+
 #[serde_as]
 #[derive(Clone, Debug, Default, Deserialize, Serialize, Archive, rkyv::Serialize, rkyv::Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -198,6 +218,10 @@ pub struct Manifest {
     #[serde(skip_serializing_if = "BTreeMap::is_empty")]
     pub scripts: BTreeMap<String, String>,
 
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub scripts_meta: BTreeMap<String, ScriptMeta>,
+
     #[serde(default)]
     #[serde(skip_serializing_if = "ResolutionsField::is_empty")]
     pub resolutions: ResolutionsField,
@@ -5,7 +5,7 @@ use regex::Regex;
 use zpm_formats::{iter_ext::IterExt, Entry};
 use zpm_primitives::Ident;
 use zpm_semver::Version;
-use zpm_utils::{Path, ToFileString};
+use zpm_utils::{Path, Sha1, Sha256, Sha512, ToFileString, UrlEncoded};
 
 pub trait NpmEntryExt<'a> {
     fn prepare_npm_entries(self, subdir: &Path) -> impl Iterator<Item = Entry<'a>>;
@@ -67,6 +67,49 @@ pub fn is_conventional_tarball_url(registry: &str, ident: &Ident, version: &zpm_
     false
 }
 
+/// Checks whether the data matches a Subresource Integrity string, as found in
+/// the lockfiles of other package managers. Hashes computed with algorithms we
+/// don't support are ignored; the data is valid if any of the others matches.
+pub fn check_integrity(integrity: &str, data: &[u8]) -> bool {
+    let mut has_supported_hash
+        = false;
+
+    for hash in integrity.split_whitespace() {
+        let Some((algorithm, digest)) = hash.split_once('-') else {
+            continue;
+        };
+
+        // The digests may be followed by options, which we don't use
+        let digest = digest.split('?')
+            .next()
+            .unwrap_or_default();
+
+        let expected_digest = match algorithm {
+            "sha1" => Sha1::new(data).to_base64(),
+            "sha256" => Sha256::new(data).to_base64(),
+            "sha512" => Sha512::new(data).to_base64(),
+            _ => continue,
+        };
+
+        if expected_digest == digest {
+            return true;
+        }
+
+        has_supported_hash = true;
+    }
+
+    !has_supported_hash
+}
+
+/// Splits a `name@selector` string into its two parts; the leading `@` of
+/// scoped packages isn't considered a separator.
+pub fn split_package_selector(value: &str) -> (&str, Option<&str>) {
+    match value.char_indices().skip(1).find(|(_, c)| *c == '@') {
+        Some((index, _)) => (&value[..index], Some(&value[index + 1..])),
+        None => (value, None),
+    }
+}
+
 pub fn registry_url_for_all_versions(ident: &Ident) -> String {
     let mut url = String::new();
 
@@ -95,6 +138,26 @@ pub fn registry_url_for_one_version(ident: &Ident, version: &Version) -> String
     url
 }
 
+pub fn registry_url_for_dist_tags(ident: &Ident) -> String {
+    let mut url
+        = String::from("/-/package");
+
+    url.push_str(&registry_url_for_all_versions(ident));
+    url.push_str("/dist-tags");
+
+    url
+}
+
+pub fn registry_url_for_dist_tag(ident: &Ident, tag: &str) -> String {
+    let mut url
+        = registry_url_for_dist_tags(ident);
+
+    url.push('/');
+    url.push_str(&UrlEncoded::new(tag).to_file_string());
+
+    url
+}
+
 pub fn registry_url_for_package_data(ident: &Ident, version: &Version) -> String {
     let mut url
         = registry_url_for_all_versions(ident);
@@ -84,6 +84,7 @@ impl RangeExt for Range {
                 }
             },
 
+            Range::Custom(_) |
             Range::Git(_) |
             Range::Url(_) => {
                 RangeDetails {
@@ -1,12 +1,12 @@
 use std::{collections::{BTreeMap, BTreeSet, HashSet}, io::ErrorKind, sync::Arc, time::UNIX_EPOCH};
 
 use globset::{GlobBuilder, GlobSetBuilder};
-use zpm_config::{Configuration, ConfigurationContext};
+use zpm_config::{Configuration, ConfigurationContext, Source};
 use zpm_macro_enum::zpm_enum;
 use zpm_parsers::JsonDocument;
 use zpm_primitives::{Descriptor, Ident, Locator, Range, Reference, WorkspaceIdentReference, WorkspaceMagicRange, WorkspacePathReference};
 use zpm_utils::{Glob, LastModifiedAt, Path, ToFileString, ToHumanString};
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize};
 use zpm_formats::zip::ZipSupport;
 
 use crate::{
@@ -14,13 +14,14 @@ use crate::{
     diff_finder::CacheEntry,
     error::Error,
     git::{GitOperation, detect_git_operation},
+    hooks::{self, Hook},
     http::HttpClient,
     install::{InstallContext, InstallManager, InstallResult, InstallState},
-    lockfile::{Lockfile, from_legacy_berry_lockfile, from_pnpm_node_modules},
+    lockfile::{Lockfile, from_legacy_berry_lockfile, from_npm_lockfile, from_pnpm_lockfile, from_pnpm_node_modules, split_conflict_markers},
     manifest::{Manifest, helpers::read_manifest_with_size},
     manifest_finder::CachedManifestFinder,
     report::{StreamReport, StreamReportConfig, with_report_result},
-    script::{Binary, ScriptEnvironment},
+    script::Binary,
 };
 
 pub const LOCKFILE_NAME: &str = "yarn.lock";
@@ -71,6 +72,9 @@ pub struct Project {
     pub last_modified_at: LastModifiedAt,
     pub install_state: Option<InstallState>,
     pub http_client: std::sync::Arc<HttpClient>,
+
+    /// Whether the network got disabled in favor of the offline mirror (`--offline`)
+    pub offline_mode: bool,
 }
 
 impl Project {
@@ -142,6 +146,13 @@ impl Project {
             config.settings.enable_global_cache.source = config.settings.enable_migration_mode.source;
         }
 
+        // The offline mirror lives inside the local cache, so that it can be
+        // committed alongside the project
+        if config.settings.enable_offline_mirror.value {
+            config.settings.enable_global_cache.value = false;
+            config.settings.enable_global_cache.source = config.settings.enable_offline_mirror.source;
+        }
+
         let root_workspace
             = Workspace::from_root_path(&project_cwd)?;
 
@@ -179,6 +190,7 @@ impl Project {
             last_modified_at,
             install_state: None,
             http_client,
+            offline_mode: false,
         })
     }
 
@@ -239,12 +251,22 @@ impl Project {
             .with_join_str("cache")
     }
 
+    pub fn content_store_path(&self) -> Path {
+        self.config.settings.global_folder.value
+            .with_join_str("store")
+    }
+
     pub fn local_cache_path(&self) -> Path {
         self.project_cwd
             .with_join_str(".yarn")
             .with_join_str(&self.config.settings.local_cache_folder_name.value)
     }
 
+    pub fn offline_mirror_metadata_path(&self) -> Path {
+        self.local_cache_path()
+            .with_join_str("metadata")
+    }
+
     pub fn preferred_cache_path(&self) -> Path {
         if self.config.settings.enable_global_cache.value {
             self.global_cache_path()
@@ -267,7 +289,19 @@ impl Project {
             let source_lockfile
                 = Project::lockfile_from(&source_lockfile_path)?;
 
+            // The first migrated install is seeded with the source lockfile (which may
+            // have been imported from npm or pnpm) so that its pinned versions are kept
+            if lockfile.entries.is_empty() {
+                lockfile.metadata = source_lockfile.metadata;
+                lockfile.entries = source_lockfile.entries;
+            }
+
             lockfile.resolutions.extend(source_lockfile.resolutions.into_iter());
+
+            // Also keep what was imported from npm or pnpm, so that their integrity
+            // hashes get checked and their transitive versions stay pinned
+            lockfile.imported_integrities.extend(source_lockfile.imported_integrities.into_iter());
+            lockfile.imported_packages.extend(source_lockfile.imported_packages.into_iter());
         }
 
         Ok(lockfile)
@@ -275,8 +309,22 @@ impl Project {
 
     fn lockfile_from(lockfile_path: &Path) -> Result<Lockfile, Error> {
         if !lockfile_path.fs_exists() {
-            // Check for pnpm node_modules in the same directory
+            // Check for lockfiles from other package managers in the same directory
             if let Some(project_cwd) = lockfile_path.dirname() {
+                let npm_lockfile_path
+                    = project_cwd.with_join_str("package-lock.json");
+
+                if npm_lockfile_path.fs_exists() {
+                    return from_npm_lockfile(&project_cwd, &npm_lockfile_path.fs_read_text()?);
+                }
+
+                let pnpm_lockfile_path
+                    = project_cwd.with_join_str("pnpm-lock.yaml");
+
+                if pnpm_lockfile_path.fs_exists() {
+                    return from_pnpm_lockfile(&project_cwd, &pnpm_lockfile_path.fs_read_text()?);
+                }
+
                 let pnpm_dir
                     = project_cwd.with_join_str("node_modules/.pnpm");
 
@@ -291,16 +339,20 @@ impl Project {
         let src = lockfile_path
             .fs_read_text()?;
 
+        Project::lockfile_from_str(&src)
+    }
+
+    fn lockfile_from_str(src: &str) -> Result<Lockfile, Error> {
         if src.is_empty() {
             return Ok(Lockfile::new());
         }
 
         if src.starts_with('#') {
-            return from_legacy_berry_lockfile(&src);
+            return from_legacy_berry_lockfile(src);
         }
 
         let lockfile: Lockfile
-            = JsonDocument::hydrate_from_str(&src)
+            = JsonDocument::hydrate_from_str(src)
                 .map_err(|e| Error::LockfileParseError(e))?;
 
         Ok(lockfile)
@@ -427,6 +479,34 @@ impl Project {
         ))
     }
 
+    /// Refuse all network access for the rest of the command; packages and
+    /// registry metadata are then only read from the offline mirror.
+    pub fn enable_offline_mode(&mut self) -> Result<(), Error> {
+        self.config.settings.enable_network.value = false;
+        self.config.settings.enable_network.source = Source::Cli;
+
+        for (_, network_settings) in self.config.settings.network_settings.iter_mut() {
+            network_settings.enable_network.value = None;
+        }
+
+        self.config.settings.enable_global_cache.value = false;
+        self.config.settings.enable_global_cache.source = Source::Cli;
+
+        self.http_client
+            = HttpClient::new(&self.config)?;
+
+        self.offline_mode = true;
+
+        Ok(())
+    }
+
+    /// Whether the packages missing from the network should be looked up in
+    /// the offline mirror, either because it's being populated or because
+    /// we're installing from it.
+    pub fn uses_offline_mirror(&self) -> bool {
+        self.offline_mode || self.config.settings.enable_offline_mirror.value
+    }
+
     pub fn root_workspace(&self) -> &Workspace {
         &self.workspaces[0]
     }
@@ -597,6 +677,19 @@ impl Project {
         Ok(package_location)
     }
 
+    /// Read the manifest of an installed package, whether it lives on disk or inside a zip archive from the cache.
+    pub fn package_manifest<T: DeserializeOwned>(&self, locator: &Locator) -> Result<T, Error> {
+        let package_location
+            = self.package_location(locator)?;
+
+        let manifest_text = self.project_cwd
+            .with_join(&package_location)
+            .with_join_str(MANIFEST_NAME)
+            .fs_read_text_with_zip()?;
+
+        Ok(JsonDocument::hydrate_from_str(&manifest_text)?)
+    }
+
     pub fn package_self_binaries(&self, locator: &Locator) -> Result<BTreeMap<String, Binary>, Error> {
         // Link dependencies never have any package.json, so we mustn't even try to read them.
         if matches!(locator.reference, Reference::Link(_)) {
@@ -780,23 +873,34 @@ impl Project {
                         return Err(Error::ImmutableLockfileAutofix);
                     }
 
+                    let (top, bottom)
+                        = split_conflict_markers(&lockfile_content)?;
+
                     let git_operation
                         = detect_git_operation(&self.project_cwd)
                             .await?
                             .unwrap_or(GitOperation::Merge);
 
-                    ScriptEnvironment::new()?
-                        .with_cwd(self.project_cwd.clone())
-                        .run_exec("git", vec!["checkout", git_operation.true_theirs(), lockfile_path.as_str()])
-                        .await?
-                        .ok()
-                        .map_err(|e| Error::LockfileAutofixGitError(e.to_string()))?;
+                    // Both sides are kept so that the resolutions required by either branch
+                    // remain available; when both sides resolve the same descriptor, we prefer
+                    // the branch being merged into ours. The install then prunes everything
+                    // the merged manifests don't require anymore.
+                    let (base, preferred)
+                        = git_operation.conflict_sides(top, bottom);
+
+                    let merged_lockfile
+                        = Project::lockfile_from_str(&base)?
+                            .merge(Project::lockfile_from_str(&preferred)?);
+
+                    self.write_lockfile(&merged_lockfile)?;
 
                     lockfile
                         = self.lockfile();
                 }
             }
 
+            hooks::run_hooks(self, Hook::ValidateProject, hooks::validate_project_payload(self)).await?;
+
             let install_context
                 = InstallContext::default()
                     .with_package_cache(Some(&package_cache))
@@ -1,13 +1,15 @@
-use std::{cell::RefCell, future::Future, io::{self, Write}, sync::{Arc, LazyLock, atomic::AtomicU32, mpsc}, thread::JoinHandle, time::{Duration, SystemTime}};
+use std::{cell::RefCell, future::Future, io::{self, Write}, sync::{Arc, LazyLock, atomic::{AtomicBool, AtomicU32}, mpsc}, thread::JoinHandle, time::{Duration, SystemTime}};
 
 use colored::{Color, Colorize};
-use dialoguer::{Input, Password};
+use dialoguer::{Input, Password, Select};
 use itertools::Itertools;
+use regex::Regex;
+use serde_json::json;
 use tokio::sync::{Mutex, RwLock, RwLockReadGuard};
 use zpm_config::Configuration;
 use zpm_primitives::{Descriptor, Locator};
 use zpm_switch::get_bin_version;
-use zpm_utils::{DataType, Path, ToHumanString, Unit};
+use zpm_utils::{DataType, Path, ToFileString, ToHumanString, Unit};
 
 use crate::error::Error;
 
@@ -15,6 +17,21 @@ const TOP_LEVEL_PREFIX: char = '·';
 
 pub static REPORT: LazyLock<RwLock<Option<StreamReport>>> = LazyLock::new(|| RwLock::new(None));
 
+static JSON_REPORT: AtomicBool = AtomicBool::new(false);
+
+static ANSI_ESCAPE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\x1b\[[0-9;]*m").unwrap());
+static ERROR_CODE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\[(YN\d{4})\] ?").unwrap());
+
+/// Switch all reports to the NDJSON format, where each message is printed as
+/// a single-line JSON object rather than a human-readable line.
+pub fn enable_json_report() {
+    JSON_REPORT.store(true, std::sync::atomic::Ordering::Relaxed);
+}
+
+pub fn is_json_report() -> bool {
+    JSON_REPORT.load(std::sync::atomic::Ordering::Relaxed)
+}
+
 pub async fn set_current_report(report: StreamReport) {
     REPORT.write().await.replace(report);
 }
@@ -109,21 +126,38 @@ pub async fn with_context_result<F, R>(context: ReportContext, f: F) -> Result<R
     }).await
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct StreamReportConfig {
     pub enable_progress_bars: bool,
     pub enable_timers: bool,
     pub include_version: bool,
     pub silent_or_error: bool,
+    pub json: bool,
+}
+
+impl Default for StreamReportConfig {
+    fn default() -> Self {
+        Self {
+            enable_progress_bars: false,
+            enable_timers: false,
+            include_version: false,
+            silent_or_error: false,
+            json: is_json_report(),
+        }
+    }
 }
 
 impl StreamReportConfig {
     pub fn from_config(config: &Configuration) -> Self {
+        let json
+            = is_json_report();
+
         Self {
-            enable_progress_bars: config.settings.enable_progress_bars.value,
+            enable_progress_bars: config.settings.enable_progress_bars.value && !json,
             enable_timers: config.settings.enable_timers.value,
             include_version: false,
             silent_or_error: false,
+            json,
         }
     }
 }
@@ -136,6 +170,14 @@ pub enum Severity {
 }
 
 impl Severity {
+    pub fn json_type(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+
     pub fn color(&self) -> DataType {
         match self {
             Severity::Info => DataType::Info,
@@ -149,6 +191,9 @@ impl Severity {
 pub enum PromptType {
     Input(String),
     Password(String),
+
+    /// Resolves to the index of the selected item (the first item is selected by default)
+    Select(String, Vec<String>),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -185,11 +230,14 @@ struct Reporter {
     buffered_lines: Option<Vec<String>>,
     log_paths: Vec<Path>,
     spinner_idx: Option<usize>,
-    prompt_tx: mpsc::Sender<String>,
+    prompt_tx: mpsc::Sender<Option<String>>,
+
+    section_names: Vec<String>,
+    last_json_progress: Option<(u32, u32, u32)>,
 }
 
 impl Reporter {
-    pub fn new(config: StreamReportConfig, counters: Arc<ReportCounters>, prompt_tx: mpsc::Sender<String>) -> Self {
+    pub fn new(config: StreamReportConfig, counters: Arc<ReportCounters>, prompt_tx: mpsc::Sender<Option<String>>) -> Self {
         let buffered_lines
             = config.silent_or_error.then_some(Vec::new());
 
@@ -204,6 +252,8 @@ impl Reporter {
             log_paths: Vec::new(),
             spinner_idx: None,
             prompt_tx,
+            section_names: Vec::new(),
+            last_json_progress: None,
         }
     }
 
@@ -216,6 +266,11 @@ impl Reporter {
     }
 
     pub fn write_spinner<T: Write>(&mut self, writer: &mut T) {
+        if self.config.json {
+            self.write_json_progress(writer);
+            return;
+        }
+
         if let Some(spinner_idx) = self.spinner_idx {
             if !self.config.silent_or_error && self.config.enable_progress_bars {
                 let prefix
@@ -278,6 +333,11 @@ impl Reporter {
     }
 
     pub fn report<T: Write>(&mut self, writer: &mut T, message: ReportMessage) {
+        if self.config.json {
+            self.report_json(writer, message);
+            return;
+        }
+
         match message {
             ReportMessage::Line(severity, message) => {
                 self.on_line(writer, severity, &message);
@@ -301,6 +361,124 @@ impl Reporter {
         }
     }
 
+    fn report_json<T: Write>(&mut self, writer: &mut T, message: ReportMessage) {
+        match message {
+            ReportMessage::Line(severity, message) => {
+                if self.config.silent_or_error && severity == Severity::Error {
+                    self.stop_buffering(writer);
+                }
+
+                let message
+                    = ANSI_ESCAPE.replace_all(&message, "");
+
+                let code = ERROR_CODE.captures(&message)
+                    .map(|captures| captures[1].to_string());
+
+                let message
+                    = ERROR_CODE.replace(&message, "");
+
+                self.write_json(writer, json!({
+                    "type": severity.json_type(),
+                    "code": code,
+                    "indent": self.indent,
+                    "message": message,
+                }));
+            },
+
+            ReportMessage::LogFile(log_path) => {
+                self.log_paths.push(log_path);
+            },
+
+            ReportMessage::PushSection(name) => {
+                self.level += 1;
+                self.indent += 1;
+                self.spinner_idx = Some(0);
+
+                if self.config.enable_timers {
+                    self.start_time = Some(SystemTime::now());
+                }
+
+                self.write_json(writer, json!({
+                    "type": "sectionStart",
+                    "name": name,
+                }));
+
+                self.section_names.push(name);
+            },
+
+            ReportMessage::PopSection => {
+                if self.level == 0 {
+                    panic!("Cannot pop section when no sections are pushed");
+                }
+
+                self.write_json_progress(writer);
+
+                self.spinner_idx = None;
+                self.last_json_progress = None;
+
+                self.counters.resolution_count.store(0, std::sync::atomic::Ordering::Relaxed);
+                self.counters.fetch_count.store(0, std::sync::atomic::Ordering::Relaxed);
+                self.counters.fetch_size.store(0, std::sync::atomic::Ordering::Relaxed);
+
+                let name
+                    = self.section_names.pop();
+
+                let duration = self.start_time
+                    .and_then(|start_time| start_time.elapsed().ok())
+                    .map(|elapsed| elapsed.as_millis() as u64);
+
+                self.write_json(writer, json!({
+                    "type": "sectionEnd",
+                    "name": name,
+                    "duration": duration,
+                }));
+
+                self.indent -= 1;
+                self.level -= 1;
+            },
+
+            ReportMessage::Prompt(prompt) => {
+                self.on_prompt(writer, prompt);
+            },
+        }
+    }
+
+    fn write_json_progress<T: Write>(&mut self, writer: &mut T) {
+        if self.spinner_idx.is_none() {
+            return;
+        }
+
+        let counters = (
+            self.counters.resolution_count.load(std::sync::atomic::Ordering::Relaxed),
+            self.counters.fetch_count.load(std::sync::atomic::Ordering::Relaxed),
+            self.counters.fetch_size.load(std::sync::atomic::Ordering::Relaxed),
+        );
+
+        if counters == (0, 0, 0) || self.last_json_progress == Some(counters) {
+            return;
+        }
+
+        self.last_json_progress = Some(counters);
+
+        self.write_json(writer, json!({
+            "type": "progress",
+            "resolutionCount": counters.0,
+            "fetchCount": counters.1,
+            "fetchSize": counters.2,
+        }));
+    }
+
+    fn write_json<T: Write>(&mut self, writer: &mut T, event: serde_json::Value) {
+        let line
+            = event.to_string();
+
+        if let Some(buffered_lines) = &mut self.buffered_lines {
+            buffered_lines.push(line);
+        } else {
+            writeln!(writer, "{}", line).unwrap();
+        }
+    }
+
     fn on_start<T: Write>(&mut self, writer: &mut T) {
         if self.config.enable_progress_bars {
             writer.write_all(b"\x1b[?25l").unwrap();
@@ -308,6 +486,21 @@ impl Reporter {
     }
 
     fn on_end<T: Write>(&mut self, writer: &mut T) {
+        if self.config.json {
+            for log_path in std::mem::take(&mut self.log_paths) {
+                let content
+                    = log_path.fs_read_text().ok();
+
+                self.write_json(writer, json!({
+                    "type": "logFile",
+                    "path": log_path.to_file_string(),
+                    "content": content,
+                }));
+            }
+
+            return;
+        }
+
         for log_path in &self.log_paths {
             writeln!(writer, "\n{}\n", log_path.to_print_string()).unwrap();
 
@@ -362,10 +555,12 @@ impl Reporter {
                 let label
                     = self.format_prompt(&prompt);
 
+                // Prompts can't be displayed without a terminal; the caller
+                // is responsible for turning this into a proper error
                 let input = Input::<String>::new()
                     .with_prompt(label)
                     .interact_text()
-                    .unwrap();
+                    .ok();
 
                 self.prompt_tx.send(input).unwrap();
             },
@@ -377,10 +572,24 @@ impl Reporter {
                 let password = Password::new()
                     .with_prompt(label)
                     .interact()
-                    .unwrap();
+                    .ok();
 
                 self.prompt_tx.send(password).unwrap();
             },
+
+            PromptType::Select(prompt, items) => {
+                let label
+                    = self.format_prompt(&prompt);
+
+                let selection = Select::new()
+                    .with_prompt(label)
+                    .items(&items[..])
+                    .default(0)
+                    .interact()
+                    .ok();
+
+                self.prompt_tx.send(selection.map(|selection| selection.to_string())).unwrap();
+            },
         }
     }
 
@@ -461,7 +670,7 @@ pub struct StreamReport {
     handle: JoinHandle<()>,
     break_request_tx: mpsc::Sender<bool>,
     msg_queue_tx: mpsc::Sender<ReportMessage>,
-    prompt_rx: Mutex<mpsc::Receiver<String>>,
+    prompt_rx: Mutex<mpsc::Receiver<Option<String>>>,
 }
 
 impl StreamReport {
@@ -474,7 +683,7 @@ impl StreamReport {
         let (msg_queue_tx, msg_queue_rx)
             = mpsc::channel::<ReportMessage>();
         let (prompt_tx, prompt_rx)
-            = mpsc::channel::<String>();
+            = mpsc::channel::<Option<String>>();
 
         let mut reporter
             = Reporter::new(config, counters.clone(), prompt_tx);
@@ -487,7 +696,14 @@ impl StreamReport {
             }
 
             if reporter.config.include_version {
-                reporter.write_line(&mut io::stdout(), &format!("Yarn {}", get_bin_version()).bold().to_string(), Severity::Info);
+                if reporter.config.json {
+                    reporter.write_json(&mut io::stdout(), json!({
+                        "type": "version",
+                        "version": get_bin_version(),
+                    }));
+                } else {
+                    reporter.write_line(&mut io::stdout(), &format!("Yarn {}", get_bin_version()).bold().to_string(), Severity::Info);
+                }
             }
 
             loop {
@@ -536,7 +752,10 @@ impl StreamReport {
 
     pub fn error(&self, error: Error) {
         if !matches!(error, Error::SilentError) {
-            self.report(ReportMessage::Line(Severity::Error, self.with_content_prefix(error.to_string())));
+            let message
+                = self.with_content_prefix(error.to_string());
+
+            self.report(ReportMessage::Line(Severity::Error, format!("[{}] {}", error.code(), message)));
         }
 
         if let Error::ChildProcessFailedWithLog(_, log_path) = error {
@@ -586,7 +805,7 @@ impl StreamReport {
         }
     }
 
-    pub async fn prompt(&self, prompt: PromptType) -> String {
+    pub async fn prompt(&self, prompt: PromptType) -> Result<String, Error> {
         self.report(ReportMessage::Prompt(prompt));
 
         let prompt_rx
@@ -595,6 +814,7 @@ impl StreamReport {
         prompt_rx
             .recv()
             .unwrap()
+            .ok_or(Error::InteractiveTerminalRequired)
     }
 
     pub fn close(self) {
@@ -0,0 +1,84 @@
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::json;
+use zpm_primitives::{CustomRange, CustomReference, Descriptor, Locator};
+use zpm_utils::ToFileString;
+
+use crate::{
+    error::Error,
+    fetchers,
+    install::{InstallContext, IntoResolutionResult, ResolutionResult},
+    project::Project,
+    script::{ScriptEnvironment, ScriptResult},
+};
+
+#[derive(Deserialize)]
+struct ResolveResponse {
+    reference: String,
+}
+
+/**
+ * Runs the executable registered for the given protocol in the
+ * `customProtocols` setting. The request is sent as a JSON object on its
+ * standard input, and the response is read as a JSON object from its standard
+ * output; anything written on its standard error is reported if it fails.
+ */
+pub async fn run_protocol_command<T: DeserializeOwned>(project: &Project, protocol: &str, request: serde_json::Value) -> Result<T, Error> {
+    let command
+        = project.config.settings.custom_protocols.get(protocol)
+            .map(|setting| setting.value.clone())
+            .ok_or_else(|| Error::CustomProtocolNotFound(protocol.to_string()))?;
+
+    let result
+        = ScriptEnvironment::new()?
+            .with_project(project)
+            .with_cwd(project.project_cwd.clone())
+            .with_stdin(Some(request.to_string()))
+            .run_script(&command, Vec::<String>::new())
+            .await?;
+
+    let output = match result {
+        ScriptResult::Success(output) => output,
+        ScriptResult::Failure(output, _, _) => {
+            let stderr
+                = String::from_utf8_lossy(&output.stderr);
+
+            return Err(Error::CustomProtocolFailed(protocol.to_string(), match stderr.trim().is_empty() {
+                true => format!("Exited with {}", output.status),
+                false => stderr.trim().to_string(),
+            }));
+        },
+    };
+
+    serde_json::from_slice(&output.stdout)
+        .map_err(|err| Error::CustomProtocolFailed(protocol.to_string(), format!("Invalid response ({})", err)))
+}
+
+pub async fn resolve_descriptor(context: &InstallContext<'_>, descriptor: &Descriptor, params: &CustomRange) -> Result<ResolutionResult, Error> {
+    let project = context.project
+        .expect("The project is required for resolving a custom protocol package");
+
+    let response: ResolveResponse = run_protocol_command(project, &params.protocol, json!({
+        "action": "resolve",
+        "descriptor": descriptor.to_file_string(),
+        "ident": descriptor.ident.to_file_string(),
+        "protocol": params.protocol,
+        "selector": params.selector,
+    })).await?;
+
+    let locator = descriptor.resolve_with(CustomReference {
+        protocol: params.protocol.clone(),
+        payload: response.reference,
+    }.into());
+
+    let fetch_result
+        = fetchers::fetch_locator(context.clone(), &locator, false, vec![]).await?;
+
+    fetch_result.into_resolution_result(context)
+}
+
+pub async fn resolve_locator(context: &InstallContext<'_>, locator: &Locator, _params: &CustomReference) -> Result<ResolutionResult, Error> {
+    let fetch_result
+        = fetchers::fetch_locator(context.clone(), locator, false, vec![]).await?;
+
+    fetch_result.into_resolution_result(context)
+}
@@ -11,6 +11,7 @@ use crate::{
 
 pub mod builtin;
 pub mod catalog;
+pub mod custom;
 pub mod folder;
 pub mod git;
 pub mod link;
@@ -201,6 +202,9 @@ pub async fn resolve_descriptor(context: InstallContext<'_>, descriptor: Descrip
         Range::Url(params)
             => url::resolve_descriptor(&context, &descriptor, params).await,
 
+        Range::Custom(params)
+            => custom::resolve_descriptor(&context, &descriptor, params).await,
+
         Range::Patch(params)
             => patch::resolve_descriptor(&context, &descriptor, params, dependencies).await,
 
@@ -251,6 +255,9 @@ pub async fn resolve_locator(context: InstallContext<'_>, locator: Locator, depe
         Reference::Url(params)
             => url::resolve_locator(&context, &locator, params).await,
 
+        Reference::Custom(params)
+            => custom::resolve_locator(&context, &locator, params).await,
+
         Reference::Tarball(params)
             => tarball::resolve_locator(&context, &locator, params, dependencies).await,
 
@@ -6,7 +6,8 @@ use serde::Deserialize;
 use serde_with::{serde_as, MapSkipError};
 use zpm_parsers::{JsonDocument, RawJsonValue};
 use zpm_primitives::{AnonymousSemverRange, Descriptor, Ident, Locator, Reference, RegistryReference, RegistrySemverRange, RegistryTagRange};
-use zpm_utils::UrlEncoded;
+use bytes::Bytes;
+use zpm_utils::{Hash64, IoResultExt, UrlEncoded};
 
 use crate::{
     error::Error,
@@ -14,6 +15,7 @@ use crate::{
     install::{InstallContext, InstallOpResult, IntoResolutionResult, ResolutionResult},
     manifest::RemoteManifest,
     npm,
+    project::Project,
     resolvers::{Resolution, workspace},
 };
 
@@ -82,6 +84,62 @@ fn build_resolution_result(context: &InstallContext, descriptor: &Descriptor, pa
         .into_resolution_result(context)
 }
 
+/// Retrieve the registry metadata stored at the given path. When the offline
+/// mirror is enabled the response is also stored inside the local cache, from
+/// which it'll be read whenever the network isn't available. The metadata
+/// cache from the global folder is then bypassed, so that installs from the
+/// mirror only succeed if the mirror is complete, whatever the machine.
+async fn get_registry_metadata(project: &Project, ident: &Ident, registry_path: &str) -> Result<Bytes, Error> {
+    let registry_base
+        = http_npm::get_registry(&project.config, ident.scope(), false)?;
+
+    let mirror_path = project.offline_mirror_metadata_path()
+        .with_join_str(format!("{}.json", Hash64::from_data(format!("{}{}", registry_base, registry_path)).short()));
+
+    let authorization
+        = http_npm::get_authorization(&http_npm::GetAuthorizationOptions {
+            configuration: &project.config,
+            http_client: &project.http_client,
+            registry: registry_base,
+            ident: Some(ident),
+            auth_mode: http_npm::AuthorizationMode::RespectConfiguration,
+            allow_oidc: false,
+        }).await?;
+
+    let params = http_npm::NpmHttpParams {
+        http_client: &project.http_client,
+        registry: registry_base,
+        path: registry_path,
+        authorization: authorization.as_deref(),
+        otp: None,
+    };
+
+    let response = match project.uses_offline_mirror() {
+        true => http_npm::get(&params).await,
+        false => http_npm::get_metadata(&params).await,
+    };
+
+    let bytes = match response {
+        Err(Error::NetworkDisabledError(_)) if project.uses_offline_mirror() => {
+            return mirror_path
+                .fs_read()
+                .ok_missing()?
+                .map(Bytes::from)
+                .ok_or_else(|| Error::MissingOfflineMirrorMetadata(ident.clone()));
+        },
+
+        response => response?,
+    };
+
+    if project.config.settings.enable_offline_mirror.value {
+        mirror_path
+            .fs_create_parent()?
+            .fs_change(&bytes, false)?;
+    }
+
+    Ok(bytes)
+}
+
 pub async fn resolve_semver_or_workspace_descriptor(context: &InstallContext<'_>, descriptor: &Descriptor, params: &RegistrySemverRange) -> Result<ResolutionResult, Error> {
     let project = context.project
         .expect("The project is required for resolving a workspace package");
@@ -101,11 +159,20 @@ fn is_package_approved(context: &InstallContext<'_>, ident: &Ident, version: &zp
     let project = context.project
         .expect("The project is required for resolving a workspace package");
 
+    is_version_approved(project, &context.install_time, ident, version, release_time)
+}
+
+/**
+ * Returns whether the given version may be selected at the given time; versions
+ * more recent than `npmMinimalAgeGate` are only accepted when they match one of
+ * the `npmPreapprovedPackages` patterns.
+ */
+pub fn is_version_approved(project: &Project, now: &DateTime<Utc>, ident: &Ident, version: &zpm_semver::Version, release_time: Option<&DateTime<Utc>>) -> bool {
     let check_config
         = || project.config.settings.npm_preapproved_packages.iter().any(|setting| setting.value.check(ident, version));
 
     if let Some(minimal_age_gate) = project.config.settings.npm_minimal_age_gate.value {
-        if release_time.map_or(false, |time| context.install_time < *time + minimal_age_gate) {
+        if release_time.map_or(false, |time| *now < *time + minimal_age_gate) {
             return check_config();
         }
     }
@@ -160,29 +227,8 @@ pub async fn resolve_semver_descriptor(context: &InstallContext<'_>, descriptor:
     let package_ident = params.ident.as_ref()
         .unwrap_or(&descriptor.ident);
 
-    let registry_base
-        = http_npm::get_registry(&project.config, package_ident.scope(), false)?;
-    let registry_path
-        = npm::registry_url_for_all_versions(&package_ident);
-
-    let authorization
-        = http_npm::get_authorization(&http_npm::GetAuthorizationOptions {
-            configuration: &project.config,
-            http_client: &project.http_client,
-            registry: &registry_base,
-            ident: Some(package_ident),
-            auth_mode: http_npm::AuthorizationMode::RespectConfiguration,
-            allow_oidc: false,
-        }).await?;
-
     let bytes
-        = http_npm::get(&http_npm::NpmHttpParams {
-            http_client: &project.http_client,
-            registry: &registry_base,
-            path: &registry_path,
-            authorization: authorization.as_deref(),
-            otp: None,
-        }).await?;
+        = get_registry_metadata(project, package_ident, &npm::registry_url_for_all_versions(&package_ident)).await?;
 
     #[serde_as]
     #[derive(Deserialize)]
@@ -229,29 +275,8 @@ pub async fn resolve_tag_descriptor(context: &InstallContext<'_>, descriptor: &D
     let package_ident = params.ident.as_ref()
         .unwrap_or(&descriptor.ident);
 
-    let registry_base
-        = http_npm::get_registry(&project.config, package_ident.scope(), false)?;
-    let registry_path
-        = npm::registry_url_for_all_versions(&package_ident);
-
-    let authorization
-        = http_npm::get_authorization(&http_npm::GetAuthorizationOptions {
-            configuration: &project.config,
-            http_client: &project.http_client,
-            registry: &registry_base,
-            ident: Some(package_ident),
-            auth_mode: http_npm::AuthorizationMode::RespectConfiguration,
-            allow_oidc: false,
-        }).await?;
-
     let bytes
-        = http_npm::get(&http_npm::NpmHttpParams {
-            http_client: &project.http_client,
-            registry: &registry_base,
-            path: &registry_path,
-            authorization: authorization.as_deref(),
-            otp: None,
-        }).await?;
+        = get_registry_metadata(project, package_ident, &npm::registry_url_for_all_versions(&package_ident)).await?;
 
     #[serde_as]
     #[derive(Deserialize)]
@@ -294,29 +319,8 @@ pub async fn resolve_locator(context: &InstallContext<'_>, locator: &Locator, pa
     let project = context.project
         .expect("The project is required for resolving a workspace package");
 
-    let registry_base
-        = http_npm::get_registry(&project.config, params.ident.scope(), false)?;
-    let registry_path
-        = npm::registry_url_for_one_version(&params.ident, &params.version);
-
-    let authorization
-        = http_npm::get_authorization(&http_npm::GetAuthorizationOptions {
-            configuration: &project.config,
-            http_client: &project.http_client,
-            registry: &registry_base,
-            ident: Some(&params.ident),
-            auth_mode: http_npm::AuthorizationMode::RespectConfiguration,
-            allow_oidc: false,
-        }).await?;
-
     let bytes
-        = http_npm::get(&http_npm::NpmHttpParams {
-            http_client: &project.http_client,
-            registry: &registry_base,
-            path: &registry_path,
-            authorization: authorization.as_deref(),
-            otp: None,
-        }).await?;
+        = get_registry_metadata(project, &params.ident, &npm::registry_url_for_one_version(&params.ident, &params.version)).await?;
 
     let mut manifest: RemoteManifestWithScripts
         = JsonDocument::hydrate_from_slice(&bytes[..])?;
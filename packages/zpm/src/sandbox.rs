@@ -0,0 +1,267 @@
+use zpm_utils::Path;
+
+#[cfg(all(test, target_os = "linux"))]
+#[path = "./sandbox.test.rs"]
+mod sandbox_tests;
+
+/// Restrictions applied to the processes spawned by the build scripts of
+/// third-party packages when `enableBuildSandbox` is set. Only Linux is
+/// supported, as the sandbox relies on user, mount, and network namespaces.
+#[derive(Debug, Clone)]
+pub struct Sandbox {
+    /// Folders replaced by an empty temporary folder, typically the home
+    /// folder so that the credentials it contains (`~/.npmrc`, `~/.ssh`, ...)
+    /// can't be read, and that the files it contains can't be tampered with.
+    /// Whatever the process writes there is discarded once it exits.
+    pub hidden_paths: Vec<Path>,
+
+    /// Folders that the process can read but not modify. They remain visible
+    /// even when they are located inside one of the hidden folders.
+    pub read_only_paths: Vec<Path>,
+
+    /// Folders that remain writable, even if they are located inside one of
+    /// the read-only or hidden folders.
+    pub writable_paths: Vec<Path>,
+
+    /// Whether the process keeps access to the network; if not, it runs in
+    /// its own network namespace, which only contains a disabled loopback.
+    pub allow_network: bool,
+}
+
+/// A folder mounted back into the sandbox. The source is a file descriptor
+/// opened before entering the sandbox, so that the folder remains reachable
+/// even once its parent got hidden.
+#[cfg(target_os = "linux")]
+struct BindMount {
+    _source_fd: std::fs::File,
+    source: std::ffi::CString,
+    target: std::ffi::CString,
+    missing_parents: Vec<std::ffi::CString>,
+    read_only: bool,
+}
+
+impl Sandbox {
+    pub fn is_supported() -> bool {
+        cfg!(target_os = "linux")
+    }
+
+    /// Configures the command so that the spawned process enters the sandbox
+    /// right before executing the program. Since the program inherits the
+    /// namespaces, the processes it spawns are restricted as well. The folders
+    /// listed in the command's `PATH` and the folder of the current binary
+    /// stay readable, so that the programs the scripts rely on keep working.
+    #[cfg(target_os = "linux")]
+    pub fn apply(&self, cmd: &mut tokio::process::Command) -> std::io::Result<()> {
+        use std::{ffi::CString, os::{fd::AsRawFd, unix::{ffi::OsStrExt, fs::OpenOptionsExt}}};
+
+        let to_c_string = |path: &Path| {
+            CString::new(path.to_path_buf().as_os_str().as_bytes())
+        };
+
+        let is_hidden = |path: &Path| {
+            self.hidden_paths.iter().any(|hidden_path| hidden_path.contains(path))
+        };
+
+        let command_path = cmd.as_std().get_envs()
+            .find(|(key, _)| *key == "PATH")
+            .and_then(|(_, value)| value)
+            .map(|value| value.to_owned())
+            .or_else(|| std::env::var_os("PATH"))
+            .unwrap_or_default();
+
+        let tool_paths = std::env::split_paths(&command_path)
+            .chain(std::env::current_exe().ok().and_then(|exe| exe.parent().map(|parent| parent.to_path_buf())))
+            .filter_map(|path| Path::try_from(path).ok())
+            .filter(|path| is_hidden(path));
+
+        let exposed_paths = self.read_only_paths.iter().cloned()
+            .chain(tool_paths)
+            .map(|path| (path, true))
+            .chain(self.writable_paths.iter().cloned().map(|path| (path, false)));
+
+        // Everything is prepared before forking, as allocating in the child
+        // process isn't safe
+        let mut bind_mounts
+            = vec![];
+
+        for (path, read_only) in exposed_paths {
+            // There's nothing to expose (nor to protect) if the folder doesn't exist
+            let Ok(source_fd) = std::fs::OpenOptions::new().read(true).custom_flags(libc::O_PATH).open(path.to_path_buf()) else {
+                continue;
+            };
+
+            let source
+                = CString::new(format!("/proc/self/fd/{}", source_fd.as_raw_fd()))?;
+
+            // The folders leading to a path inside a hidden folder don't exist
+            // in the temporary folder replacing it, so we need to create them
+            let mut missing_parents
+                = vec![];
+
+            if let Some(hidden_path) = self.hidden_paths.iter().find(|hidden_path| hidden_path.contains(&path)) {
+                let mut parent
+                    = path.dirname();
+
+                while let Some(current) = parent.filter(|current| current != hidden_path && hidden_path.contains(current)) {
+                    missing_parents.push(to_c_string(&current)?);
+                    parent = current.dirname();
+                }
+
+                missing_parents.reverse();
+                missing_parents.push(to_c_string(&path)?);
+            }
+
+            bind_mounts.push(BindMount {
+                target: to_c_string(&path)?,
+                _source_fd: source_fd,
+                source,
+                missing_parents,
+                read_only,
+            });
+        }
+
+        let hidden_paths = self.hidden_paths.iter()
+            .filter(|path| path.fs_is_dir() && !path.is_root())
+            .map(to_c_string)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (uid, gid) = unsafe {
+            (libc::getuid(), libc::getgid())
+        };
+
+        let uid_map
+            = format!("{} {} 1\n", uid, uid);
+        let gid_map
+            = format!("{} {} 1\n", gid, gid);
+
+        let mut namespaces
+            = libc::CLONE_NEWUSER | libc::CLONE_NEWNS;
+
+        if !self.allow_network {
+            namespaces |= libc::CLONE_NEWNET;
+        }
+
+        let enter_sandbox = move || {
+            check(unsafe { libc::unshare(namespaces) })?;
+
+            // We map our own user and group to themselves, so that the files
+            // created by the build keep the right owner
+            write_proc_file(c"/proc/self/setgroups", b"deny")?;
+            write_proc_file(c"/proc/self/uid_map", uid_map.as_bytes())?;
+            write_proc_file(c"/proc/self/gid_map", gid_map.as_bytes())?;
+
+            // Prevents our mounts from propagating back to the host
+            mount(None, c"/", None, libc::MS_REC | libc::MS_PRIVATE)?;
+
+            for path in &hidden_paths {
+                mount(Some(c"tmpfs"), path, Some(c"tmpfs"), libc::MS_NOSUID | libc::MS_NODEV)?;
+            }
+
+            // The writable folders must be bound before the read-only ones
+            // are remounted; being distinct mounts, they aren't affected by
+            // the remount even when they're located inside them
+            for bind_mount in &bind_mounts {
+                for missing_parent in &bind_mount.missing_parents {
+                    create_dir(missing_parent)?;
+                }
+
+                mount(Some(&bind_mount.source), &bind_mount.target, None, libc::MS_BIND | libc::MS_REC)?;
+            }
+
+            for bind_mount in bind_mounts.iter().filter(|bind_mount| bind_mount.read_only) {
+                remount_read_only(&bind_mount.target)?;
+            }
+
+            Ok(())
+        };
+
+        unsafe {
+            cmd.pre_exec(enter_sandbox);
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn apply(&self, _cmd: &mut tokio::process::Command) -> std::io::Result<()> {
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn check(result: libc::c_int) -> std::io::Result<()> {
+    match result {
+        -1 => Err(std::io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn write_proc_file(path: &std::ffi::CStr, data: &[u8]) -> std::io::Result<()> {
+    let fd = unsafe {
+        libc::open(path.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC)
+    };
+
+    check(fd)?;
+
+    let written = unsafe {
+        libc::write(fd, data.as_ptr().cast(), data.len())
+    };
+
+    let write_error = match written {
+        -1 => Some(std::io::Error::last_os_error()),
+        _ => None,
+    };
+
+    unsafe {
+        libc::close(fd);
+    }
+
+    match write_error {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn mount(source: Option<&std::ffi::CStr>, target: &std::ffi::CStr, fs_type: Option<&std::ffi::CStr>, flags: libc::c_ulong) -> std::io::Result<()> {
+    let source = source
+        .map_or(std::ptr::null(), |source| source.as_ptr());
+
+    let fs_type = fs_type
+        .map_or(std::ptr::null(), |fs_type| fs_type.as_ptr());
+
+    check(unsafe {
+        libc::mount(source, target.as_ptr(), fs_type, flags, std::ptr::null())
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn create_dir(path: &std::ffi::CStr) -> std::io::Result<()> {
+    match check(unsafe { libc::mkdir(path.as_ptr(), 0o755) }) {
+        Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => Ok(()),
+        result => result,
+    }
+}
+
+/// The kernel refuses to remount a bind mount from a user namespace if the
+/// remount would clear one of the flags set on the original mount, so we
+/// need to carry them over.
+#[cfg(target_os = "linux")]
+fn remount_read_only(path: &std::ffi::CStr) -> std::io::Result<()> {
+    let mut stat
+        = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+
+    check(unsafe {
+        libc::statvfs(path.as_ptr(), stat.as_mut_ptr())
+    })?;
+
+    let stat = unsafe {
+        stat.assume_init()
+    };
+
+    let locked_flags
+        = stat.f_flag & (libc::ST_NOSUID | libc::ST_NODEV | libc::ST_NOEXEC | libc::ST_NOATIME | libc::ST_NODIRATIME | libc::ST_RELATIME);
+
+    mount(None, path, None, libc::MS_BIND | libc::MS_REMOUNT | libc::MS_RDONLY | locked_flags)
+}
@@ -0,0 +1,123 @@
+use std::process::ExitStatus;
+
+use super::*;
+
+/// Runs the given shell script inside the sandbox; returns `None` when the
+/// system doesn't let unprivileged users create namespaces (some CI
+/// providers and hardened kernels), in which case the test is skipped.
+async fn run_sandboxed(sandbox: &Sandbox, script: &str) -> Option<ExitStatus> {
+    let mut cmd
+        = tokio::process::Command::new("sh");
+
+    cmd.arg("-c").arg(script);
+
+    sandbox.apply(&mut cmd).unwrap();
+
+    match cmd.status().await {
+        Ok(status) => Some(status),
+        Err(error) if error.kind() == std::io::ErrorKind::PermissionDenied => None,
+        Err(error) => panic!("Failed to spawn the sandboxed process: {error}"),
+    }
+}
+
+#[tokio::test]
+async fn read_only_paths_cant_be_modified() {
+    let project_path
+        = Path::temp_dir().unwrap();
+
+    let package_path = project_path
+        .with_join_str("package");
+
+    package_path.fs_create_dir_all().unwrap();
+
+    let sandbox = Sandbox {
+        hidden_paths: vec![],
+        read_only_paths: vec![project_path.clone()],
+        writable_paths: vec![package_path.clone()],
+        allow_network: true,
+    };
+
+    let Some(status) = run_sandboxed(&sandbox, &format!("echo foo > '{}/file'", project_path.to_path_buf().display())).await else {
+        project_path.fs_rm().unwrap();
+        return;
+    };
+
+    assert!(!status.success());
+    assert!(!project_path.with_join_str("file").fs_exists());
+
+    let status
+        = run_sandboxed(&sandbox, &format!("echo foo > '{}/file'", package_path.to_path_buf().display())).await.unwrap();
+
+    assert!(status.success());
+    assert!(package_path.with_join_str("file").fs_exists());
+
+    project_path.fs_rm().unwrap();
+}
+
+#[tokio::test]
+async fn network_is_unavailable_unless_allowed() {
+    let sandbox = Sandbox {
+        hidden_paths: vec![],
+        read_only_paths: vec![],
+        writable_paths: vec![],
+        allow_network: false,
+    };
+
+    // A fresh network namespace only contains the loopback interface
+    let Some(status) = run_sandboxed(&sandbox, "test \"$(grep -c : /proc/net/dev)\" = 1").await else {
+        return;
+    };
+
+    assert!(status.success());
+}
+
+#[tokio::test]
+async fn hidden_paths_cant_be_read() {
+    let home_path
+        = Path::temp_dir().unwrap();
+
+    let secret_path = home_path
+        .with_join_str(".npmrc");
+
+    secret_path.fs_write("//registry.npmjs.org/:_authToken=secret").unwrap();
+
+    let cache_path = home_path
+        .with_join_str(".yarn/berry/cache");
+
+    cache_path.fs_create_dir_all().unwrap();
+    cache_path.with_join_str("archive.zip").fs_write("archive").unwrap();
+
+    let sandbox = Sandbox {
+        hidden_paths: vec![home_path.clone()],
+        read_only_paths: vec![cache_path.clone()],
+        writable_paths: vec![],
+        allow_network: true,
+    };
+
+    let Some(status) = run_sandboxed(&sandbox, &format!("cat '{}'", secret_path.to_path_buf().display())).await else {
+        home_path.fs_rm().unwrap();
+        return;
+    };
+
+    assert!(!status.success());
+
+    // Writing to the hidden folder only affects the temporary folder replacing it
+    let status
+        = run_sandboxed(&sandbox, &format!("echo tampered > '{}'", secret_path.to_path_buf().display())).await.unwrap();
+
+    assert!(status.success());
+    assert_eq!(secret_path.fs_read_text().unwrap(), "//registry.npmjs.org/:_authToken=secret");
+
+    // The folders explicitly allowed remain readable, but not writable
+    let status
+        = run_sandboxed(&sandbox, &format!("cat '{}/archive.zip'", cache_path.to_path_buf().display())).await.unwrap();
+
+    assert!(status.success());
+
+    let status
+        = run_sandboxed(&sandbox, &format!("echo tampered > '{}/archive.zip'", cache_path.to_path_buf().display())).await.unwrap();
+
+    assert!(!status.success());
+
+    home_path.fs_rm().unwrap();
+}
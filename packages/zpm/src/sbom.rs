@@ -0,0 +1,511 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+use zpm_parsers::JsonDocument;
+use zpm_primitives::{Ident, Locator, Reference};
+use zpm_switch::get_bin_version;
+use zpm_git::GitSource;
+use zpm_utils::{Hash64, ToFileString, UrlEncoded};
+
+use crate::{
+    error::Error,
+    http_npm,
+    manifest::license::LicenseManifest,
+    npm,
+    project::{Project, Workspace},
+};
+
+/// Name of the algorithm used to compute the lockfile checksums, as spelled by both CycloneDX and SPDX
+const CHECKSUM_ALGORITHM: &str = "BLAKE2b-512";
+
+#[derive(Debug, Clone)]
+pub struct SbomPackage {
+    pub locator: Locator,
+    pub ident: Ident,
+    pub version: zpm_semver::Version,
+    pub purl: String,
+    pub download_url: Option<String>,
+    /// The checksum stored in the lockfile; it covers the archive from the
+    /// cache rather than the tarball published on the registry
+    pub checksum: Option<Hash64>,
+    pub license: Option<String>,
+    pub dependencies: BTreeSet<Locator>,
+}
+
+impl SbomPackage {
+    fn spdx_id(&self) -> String {
+        format!("SPDXRef-Package-{}", Hash64::from_string(&self.locator).short())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SbomOptions {
+    pub prune_dev_dependencies: bool,
+}
+
+#[derive(Debug)]
+pub struct Sbom {
+    pub root: Locator,
+    pub packages: BTreeMap<Locator, SbomPackage>,
+}
+
+impl Sbom {
+    /// Walk the resolution tree from the given workspace, collecting all physical packages it depends on. When
+    /// `prune_dev_dependencies` is set, the dev dependencies of the traversed workspaces are ignored, mirroring
+    /// what the install does in that mode.
+    pub fn from_workspace(project: &Project, workspace: &Workspace, options: &SbomOptions) -> Result<Sbom, Error> {
+        let install_state
+            = project.install_state.as_ref()
+                .ok_or(Error::InstallStateNotFound)?;
+
+        let lockfile
+            = project.lockfile()?;
+
+        let root
+            = workspace.locator();
+
+        let mut packages
+            = BTreeMap::new();
+
+        let mut queue
+            = vec![root.clone()];
+
+        let mut seen
+            = BTreeSet::new();
+
+        while let Some(locator) = queue.pop() {
+            if !seen.insert(locator.clone()) {
+                continue;
+            }
+
+            // The install state may predate changes to the project; it needs to be refreshed by an install
+            let resolution
+                = install_state.resolution_tree.locator_resolutions.get(&locator)
+                    .ok_or(Error::InvalidInstallState)?;
+
+            let physical_locator
+                = locator.physical_locator();
+
+            let workspace
+                = project.try_workspace_by_locator(&physical_locator)?;
+
+            let mut dependencies
+                = BTreeSet::new();
+
+            for (ident, descriptor) in &resolution.dependencies {
+                if resolution.peer_dependencies.contains_key(ident) {
+                    continue;
+                }
+
+                if options.prune_dev_dependencies && workspace.is_some_and(|workspace| is_dev_only_dependency(workspace, ident)) {
+                    continue;
+                }
+
+                let Some(dependency) = install_state.resolution_tree.descriptor_to_locator.get(descriptor) else {
+                    continue;
+                };
+
+                dependencies.insert(dependency.physical_locator());
+                queue.push(dependency.clone());
+            }
+
+            if let Some(package) = packages.get_mut(&physical_locator) {
+                package.dependencies.extend(dependencies);
+                continue;
+            }
+
+            let (ident, version)
+                = package_name_and_version(&physical_locator, &resolution.version);
+
+            let license = match workspace {
+                Some(workspace) => {
+                    let manifest: LicenseManifest
+                        = project.package_manifest(&workspace.locator())?;
+
+                    manifest.license_expression()
+                },
+
+                None if install_state.locations_by_package.contains_key(&physical_locator) => {
+                    let manifest: LicenseManifest
+                        = project.package_manifest(&physical_locator)?;
+
+                    manifest.license_expression()
+                },
+
+                // Packages that aren't installed (for example optional dependencies that
+                // don't match the current platform) have no manifest we can read
+                None => None,
+            };
+
+            let checksum
+                = lockfile.entries.get(&physical_locator)
+                    .and_then(|entry| entry.checksum.clone());
+
+            packages.insert(physical_locator.clone(), SbomPackage {
+                locator: physical_locator.clone(),
+                purl: purl(&physical_locator, &ident, &version),
+                download_url: download_url(project, &physical_locator)?,
+                ident,
+                version,
+                checksum,
+                license,
+                dependencies,
+            });
+        }
+
+        Ok(Sbom {
+            root,
+            packages,
+        })
+    }
+
+    pub fn to_cyclonedx(&self) -> Result<String, Error> {
+        #[derive(Serialize)]
+        struct CycloneDxHash<'a> {
+            alg: &'a str,
+            content: String,
+        }
+
+        #[derive(Serialize)]
+        struct CycloneDxLicense<'a> {
+            expression: &'a str,
+        }
+
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct CycloneDxExternalReference<'a> {
+            #[serde(rename = "type")]
+            type_: &'a str,
+            url: &'a str,
+        }
+
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct CycloneDxComponent<'a> {
+            #[serde(rename = "type")]
+            type_: &'a str,
+            #[serde(rename = "bom-ref")]
+            bom_ref: String,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            group: Option<&'a str>,
+            name: &'a str,
+            version: String,
+            purl: &'a str,
+            #[serde(skip_serializing_if = "Vec::is_empty")]
+            hashes: Vec<CycloneDxHash<'a>>,
+            #[serde(skip_serializing_if = "Vec::is_empty")]
+            licenses: Vec<CycloneDxLicense<'a>>,
+            #[serde(skip_serializing_if = "Vec::is_empty")]
+            external_references: Vec<CycloneDxExternalReference<'a>>,
+        }
+
+        #[derive(Serialize)]
+        struct CycloneDxTool<'a> {
+            #[serde(rename = "type")]
+            type_: &'a str,
+            name: &'a str,
+            version: String,
+        }
+
+        #[derive(Serialize)]
+        struct CycloneDxTools<'a> {
+            components: Vec<CycloneDxTool<'a>>,
+        }
+
+        #[derive(Serialize)]
+        struct CycloneDxMetadata<'a> {
+            timestamp: String,
+            tools: CycloneDxTools<'a>,
+            component: CycloneDxComponent<'a>,
+        }
+
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct CycloneDxDependency {
+            #[serde(rename = "ref")]
+            ref_: String,
+            depends_on: Vec<String>,
+        }
+
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct CycloneDxDocument<'a> {
+            bom_format: &'a str,
+            spec_version: &'a str,
+            version: u32,
+            metadata: CycloneDxMetadata<'a>,
+            components: Vec<CycloneDxComponent<'a>>,
+            dependencies: Vec<CycloneDxDependency>,
+        }
+
+        fn to_component<'a>(package: &'a SbomPackage, type_: &'a str) -> CycloneDxComponent<'a> {
+            let (group, name)
+                = package.ident.split();
+
+            CycloneDxComponent {
+                type_,
+                bom_ref: package.locator.to_file_string(),
+                group,
+                name,
+                version: package.version.to_file_string(),
+                purl: &package.purl,
+                hashes: package.checksum.iter().map(|checksum| CycloneDxHash {alg: CHECKSUM_ALGORITHM, content: checksum.to_file_string()}).collect(),
+                licenses: package.license.iter().map(|license| CycloneDxLicense {expression: license}).collect(),
+                external_references: package.download_url.iter().map(|url| CycloneDxExternalReference {type_: "distribution", url}).collect(),
+            }
+        }
+
+        let root_package
+            = &self.packages[&self.root];
+
+        let document = CycloneDxDocument {
+            bom_format: "CycloneDX",
+            spec_version: "1.5",
+            version: 1,
+            metadata: CycloneDxMetadata {
+                timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+                tools: CycloneDxTools {
+                    components: vec![CycloneDxTool {
+                        type_: "application",
+                        name: "yarn",
+                        version: get_bin_version(),
+                    }],
+                },
+                component: to_component(root_package, "application"),
+            },
+            components: self.packages.values()
+                .filter(|package| package.locator != self.root)
+                .map(|package| to_component(package, "library"))
+                .collect(),
+            dependencies: self.packages.values()
+                .map(|package| CycloneDxDependency {
+                    ref_: package.locator.to_file_string(),
+                    depends_on: package.dependencies.iter().map(|dependency| dependency.to_file_string()).collect(),
+                })
+                .collect(),
+        };
+
+        Ok(JsonDocument::to_string_pretty(&document)?)
+    }
+
+    pub fn to_spdx(&self) -> Result<String, Error> {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct SpdxChecksum<'a> {
+            algorithm: &'a str,
+            checksum_value: String,
+        }
+
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct SpdxExternalRef<'a> {
+            reference_category: &'a str,
+            reference_type: &'a str,
+            reference_locator: &'a str,
+        }
+
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct SpdxPackage<'a> {
+            name: &'a str,
+            #[serde(rename = "SPDXID")]
+            spdx_id: String,
+            version_info: String,
+            download_location: &'a str,
+            files_analyzed: bool,
+            license_concluded: &'a str,
+            license_declared: &'a str,
+            copyright_text: &'a str,
+            #[serde(skip_serializing_if = "Vec::is_empty")]
+            checksums: Vec<SpdxChecksum<'a>>,
+            external_refs: Vec<SpdxExternalRef<'a>>,
+        }
+
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct SpdxRelationship {
+            spdx_element_id: String,
+            relationship_type: &'static str,
+            related_spdx_element: String,
+        }
+
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct SpdxCreationInfo {
+            created: String,
+            creators: Vec<String>,
+        }
+
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct SpdxDocument<'a> {
+            spdx_version: &'a str,
+            data_license: &'a str,
+            #[serde(rename = "SPDXID")]
+            spdx_id: &'a str,
+            name: String,
+            document_namespace: String,
+            creation_info: SpdxCreationInfo,
+            packages: Vec<SpdxPackage<'a>>,
+            relationships: Vec<SpdxRelationship>,
+        }
+
+        let root_package
+            = &self.packages[&self.root];
+
+        let created
+            = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+
+        // The namespace must be unique for each document, so we derive it from the
+        // content of the document and its creation time.
+        let namespace_hash
+            = Hash64::from_data(format!("{}\n{}", created, self.packages.keys().map(|locator| locator.to_file_string()).collect::<Vec<_>>().join("\n")));
+
+        let document_name
+            = format!("{}@{}", root_package.ident.to_file_string(), root_package.version.to_file_string());
+
+        let mut relationships = vec![SpdxRelationship {
+            spdx_element_id: "SPDXRef-DOCUMENT".to_string(),
+            relationship_type: "DESCRIBES",
+            related_spdx_element: root_package.spdx_id(),
+        }];
+
+        for package in self.packages.values() {
+            for dependency in &package.dependencies {
+                relationships.push(SpdxRelationship {
+                    spdx_element_id: package.spdx_id(),
+                    relationship_type: "DEPENDS_ON",
+                    related_spdx_element: self.packages[dependency].spdx_id(),
+                });
+            }
+        }
+
+        let document = SpdxDocument {
+            spdx_version: "SPDX-2.3",
+            data_license: "CC0-1.0",
+            spdx_id: "SPDXRef-DOCUMENT",
+            name: document_name.clone(),
+            document_namespace: format!("https://spdx.org/spdxdocs/{}-{}", document_name.replace(['@', '/'], "-").trim_start_matches('-'), namespace_hash.short()),
+            creation_info: SpdxCreationInfo {
+                created,
+                creators: vec![format!("Tool: yarn-{}", get_bin_version())],
+            },
+            packages: self.packages.values()
+                .map(|package| SpdxPackage {
+                    name: package.ident.as_str(),
+                    spdx_id: package.spdx_id(),
+                    version_info: package.version.to_file_string(),
+                    download_location: package.download_url.as_deref().unwrap_or("NOASSERTION"),
+                    files_analyzed: false,
+                    license_concluded: "NOASSERTION",
+                    license_declared: package.license.as_deref().unwrap_or("NOASSERTION"),
+                    copyright_text: "NOASSERTION",
+                    checksums: package.checksum.iter().map(|checksum| SpdxChecksum {algorithm: CHECKSUM_ALGORITHM, checksum_value: checksum.to_file_string()}).collect(),
+                    external_refs: vec![SpdxExternalRef {
+                        reference_category: "PACKAGE-MANAGER",
+                        reference_type: "purl",
+                        reference_locator: &package.purl,
+                    }],
+                })
+                .collect(),
+            relationships,
+        };
+
+        Ok(JsonDocument::to_string_pretty(&document)?)
+    }
+}
+
+fn is_dev_only_dependency(workspace: &Workspace, ident: &Ident) -> bool {
+    workspace.manifest.dev_dependencies.contains_key(ident)
+        && !workspace.manifest.remote.dependencies.contains_key(ident)
+        && !workspace.manifest.remote.optional_dependencies.contains_key(ident)
+}
+
+/// Return the name and version under which the package is published, which may differ from the
+/// ones of the locator when the package is aliased (`foo@npm:bar@1.0.0`) or patched.
+pub fn package_name_and_version(locator: &Locator, version: &zpm_semver::Version) -> (Ident, zpm_semver::Version) {
+    match &locator.reference {
+        Reference::Registry(params)
+            => (params.ident.clone(), params.version.clone()),
+
+        Reference::Patch(_)
+            => locator.reference.inner_locator()
+                .map(|inner_locator| package_name_and_version(inner_locator, version))
+                .unwrap_or_else(|| (locator.ident.clone(), version.clone())),
+
+        _ => (locator.ident.clone(), version.clone()),
+    }
+}
+
+pub fn download_url(project: &Project, locator: &Locator) -> Result<Option<String>, Error> {
+    match &locator.reference {
+        Reference::Shorthand(params) => {
+            let registry
+                = http_npm::get_registry(&project.config, locator.ident.scope(), false)?;
+
+            Ok(Some(format!("{}{}", registry, npm::registry_url_for_package_data(&locator.ident, &params.version))))
+        },
+
+        Reference::Registry(params) => {
+            if let Some(url) = &params.url {
+                return Ok(Some(url.0.clone()));
+            }
+
+            let registry
+                = http_npm::get_registry(&project.config, params.ident.scope(), false)?;
+
+            Ok(Some(format!("{}{}", registry, npm::registry_url_for_package_data(&params.ident, &params.version))))
+        },
+
+        Reference::Patch(_) => {
+            locator.reference.inner_locator()
+                .map_or(Ok(None), |inner_locator| download_url(project, inner_locator))
+        },
+
+        _ => Ok(None),
+    }
+}
+
+/// Generate the package url (https://github.com/package-url/purl-spec) for the given package. Only the
+/// packages coming from a registry are npm packages; git dependencies are identified by their repository,
+/// and the other ones (workspaces, folders, tarballs, ...) are reported as generic packages.
+pub fn purl(locator: &Locator, ident: &Ident, version: &zpm_semver::Version) -> String {
+    // Patched packages are identified by the package they patch
+    if let Some(inner_locator) = locator.reference.inner_locator() {
+        return purl(inner_locator, ident, version);
+    }
+
+    let (scope, name)
+        = ident.split();
+
+    let name = match scope {
+        Some(scope) => format!("{}/{}", scope.replace('@', "%40"), name),
+        None => name.to_string(),
+    };
+
+    let version
+        = version.to_file_string().replace('+', "%2B");
+
+    match &locator.reference {
+        Reference::Registry(_) | Reference::Shorthand(_) => {
+            format!("pkg:npm/{}@{}", name, version)
+        },
+
+        Reference::Git(params) => match &params.git.repo {
+            GitSource::GitHub {owner, repository}
+                => format!("pkg:github/{}/{}@{}", owner.to_lowercase(), repository.to_lowercase(), params.git.commit),
+
+            GitSource::Url(url)
+                => format!("pkg:generic/{}@{}?vcs_url={}", name, version, UrlEncoded::new(format!("git+{}@{}", url, params.git.commit)).to_file_string()),
+        },
+
+        Reference::Url(params) => {
+            format!("pkg:generic/{}@{}?download_url={}", name, version, UrlEncoded::new(params.url.clone()).to_file_string())
+        },
+
+        _ => {
+            format!("pkg:generic/{}@{}", name, version)
+        },
+    }
+}
@@ -1,6 +1,7 @@
-use std::{collections::BTreeMap, ffi::OsStr, fs::Permissions, io::Read, os::unix::{fs::PermissionsExt, process::ExitStatusExt}, process::{ExitStatus, Output}, sync::{Arc, LazyLock}};
+use std::{collections::BTreeMap, ffi::OsStr, fs::Permissions, io::{Read, Write}, os::unix::{fs::PermissionsExt, process::ExitStatusExt}, process::{ExitStatus, Output}, sync::{Arc, LazyLock}};
 
 use serde::{Deserialize, Serialize};
+use zpm_config::ScriptShell;
 use zpm_parsers::JsonDocument;
 use zpm_primitives::Locator;
 use zpm_utils::{shell_escape, to_shell_line, FromFileString, Hash64, Path, ToFileString};
@@ -11,6 +12,8 @@ use tokio::process::Command;
 use crate::{
     error::Error,
     project::Project,
+    sandbox::Sandbox,
+    shell::exec::{run_shell_script, ShellOptions},
 };
 
 static CJS_LOADER_MATCHER: LazyLock<Regex> = LazyLock::new(|| regex::Regex::new(r"\s*--require\s+\S*\.pnp\.c?js\s*").unwrap());
@@ -298,13 +301,46 @@ impl From<ScriptResult> for ExitStatus {
     }
 }
 
+async fn tee_stream<R: tokio::io::AsyncRead + Unpin>(stream: Option<R>, mut forward: impl Write) -> Result<Vec<u8>, std::io::Error> {
+    use tokio::io::AsyncReadExt;
+
+    let mut data
+        = Vec::new();
+
+    let Some(mut stream) = stream else {
+        return Ok(data);
+    };
+
+    let mut buffer
+        = [0u8; 8192];
+
+    loop {
+        let size
+            = stream.read(&mut buffer).await?;
+
+        if size == 0 {
+            break;
+        }
+
+        forward.write_all(&buffer[..size])?;
+        forward.flush()?;
+
+        data.extend_from_slice(&buffer[..size]);
+    }
+
+    Ok(data)
+}
+
 pub struct ScriptEnvironment {
     cwd: Path,
     binaries: ScriptBinaries,
     env: BTreeMap<String, Option<String>>,
     node_args: Vec<String>,
     shell_forwarding: bool,
+    record_output: bool,
+    shell: ScriptShell,
     stdin: Option<String>,
+    sandbox: Option<Sandbox>,
 }
 
 impl ScriptEnvironment {
@@ -315,7 +351,10 @@ impl ScriptEnvironment {
             env: BTreeMap::new(),
             node_args: Vec::new(),
             shell_forwarding: false,
+            record_output: false,
+            shell: ScriptShell::Bash,
             stdin: None,
+            sandbox: None,
         };
 
         if let Ok(val) = std::env::var("YARNSW_DETECTED_ROOT") {
@@ -387,11 +426,27 @@ impl ScriptEnvironment {
         self
     }
 
+    /// Forward the process output to the terminal like `enable_shell_forwarding`,
+    /// but also keep a copy of it in the returned `ScriptResult`.
+    pub fn enable_output_recording(mut self) -> Self {
+        self.shell_forwarding = true;
+        self.record_output = true;
+        self
+    }
+
     pub fn with_stdin(mut self, stdin: Option<String>) -> Self {
         self.stdin = stdin;
         self
     }
 
+    /// Run the processes inside the given sandbox. Scripts are then always
+    /// executed through bash, since the builtin shell runs within our own
+    /// process and thus can't be isolated.
+    pub fn with_sandbox(mut self, sandbox: Sandbox) -> Self {
+        self.sandbox = Some(sandbox);
+        self
+    }
+
     pub fn with_project(mut self, project: &Project) -> Self {
         self.remove_pnp_loader();
 
@@ -407,6 +462,8 @@ impl ScriptEnvironment {
         self.env.insert("INIT_CWD".to_string(), Some(project.project_cwd.with_join(&project.shell_cwd).to_file_string()));
         self.env.insert("CACHE_CWD".to_string(), Some(project.preferred_cache_path().to_file_string()));
 
+        self.shell = project.config.settings.script_shell.value;
+
         self
     }
 
@@ -522,6 +579,23 @@ impl ScriptEnvironment {
         Ok(dir)
     }
 
+    fn path_with_binaries(&self, bin_dir: &Path) -> String {
+        let env_path = self.env.get("PATH")
+            .cloned()
+            .unwrap_or_else(|| std::env::var("PATH").ok())
+            .unwrap_or_default();
+
+        match env_path.is_empty() {
+            true => {
+                bin_dir.to_file_string()
+            },
+
+            false => {
+                format!("{}:{}", bin_dir.to_file_string(), env_path)
+            },
+        }
+    }
+
     pub async fn run_exec<I, S>(&mut self, program: &str, args: I) -> Result<ScriptResult, Error> where I: IntoIterator<Item = S>, S: AsRef<str> {
         let mut cmd
             = Command::new(program);
@@ -547,22 +621,7 @@ impl ScriptEnvironment {
         let bin_dir
             = self.install_binaries()?;
 
-        let env_path = self.env.get("PATH")
-            .cloned()
-            .unwrap_or_else(|| std::env::var("PATH").ok())
-            .unwrap_or_default();
-
-        let next_env_path = match env_path.is_empty() {
-            true => {
-                bin_dir.to_file_string()
-            },
-
-            false => {
-                format!("{}:{}", bin_dir.to_file_string(), env_path)
-            },
-        };
-
-        cmd.env("PATH", next_env_path);
+        cmd.env("PATH", self.path_with_binaries(&bin_dir));
         cmd.env("BERRY_BIN_FOLDER", bin_dir.to_file_string());
 
         cmd.args(&args);
@@ -571,7 +630,12 @@ impl ScriptEnvironment {
             cmd.stdin(std::process::Stdio::piped());
         }
 
-        if !self.shell_forwarding {
+        if let Some(sandbox) = &self.sandbox {
+            sandbox.apply(&mut cmd)
+                .map_err(|e| Error::SpawnFailed { name: program.to_string(), path: self.cwd.clone(), error: Arc::new(Box::new(e)) })?;
+        }
+
+        if !self.shell_forwarding || self.record_output {
             cmd.stdout(std::process::Stdio::piped());
             cmd.stderr(std::process::Stdio::piped());
         }
@@ -580,27 +644,63 @@ impl ScriptEnvironment {
             = cmd.spawn()
                 .map_err(|e| Error::SpawnFailed { name: program.to_string(), path: self.cwd.clone(), error: Arc::new(Box::new(e)) })?;
 
-        if let Some(stdin) = &self.stdin {
-            if let Some(mut child_stdin) = child.stdin.take() {
-                use tokio::io::AsyncWriteExt;
-                child_stdin.write_all(stdin.as_bytes()).await.unwrap();
-            }
-        }
+        // The input is written from a separate task, as the process may not
+        // read all of it until we start draining its output
+        let stdin_task = match (&self.stdin, child.stdin.take()) {
+            (Some(stdin), Some(mut child_stdin)) => {
+                let stdin
+                    = stdin.clone();
 
-        let output = match self.shell_forwarding {
-            false => {
+                Some(tokio::spawn(async move {
+                    use tokio::io::AsyncWriteExt;
+
+                    // The process may exit without reading its standard input
+                    match child_stdin.write_all(stdin.as_bytes()).await {
+                        Err(err) if err.kind() != std::io::ErrorKind::BrokenPipe => Err(err),
+                        _ => Ok(()),
+                    }
+                }))
+            },
+
+            _ => None,
+        };
+
+        let output = match (self.shell_forwarding, self.record_output) {
+            (false, _) => {
                 child.wait_with_output().await.unwrap()
             },
 
-            true => {
+            (true, false) => {
                 Output {
                     status: child.wait().await.unwrap(),
                     stdout: Vec::new(),
                     stderr: Vec::new(),
                 }
             },
+
+            (true, true) => {
+                let child_stdout
+                    = child.stdout.take();
+                let child_stderr
+                    = child.stderr.take();
+
+                let (stdout, stderr) = tokio::try_join!(
+                    tee_stream(child_stdout, std::io::stdout()),
+                    tee_stream(child_stderr, std::io::stderr()),
+                )?;
+
+                Output {
+                    status: child.wait().await.unwrap(),
+                    stdout,
+                    stderr,
+                }
+            },
         };
 
+        if let Some(stdin_task) = stdin_task {
+            stdin_task.await.unwrap()?;
+        }
+
         Ok(ScriptResult::new(output, cmd.as_std()))
     }
 
@@ -630,6 +730,10 @@ impl ScriptEnvironment {
             final_script.push_str(&shell_escape(arg.to_string().as_str()));
         }
 
+        if self.shell == ScriptShell::Builtin && self.sandbox.is_none() {
+            return self.run_builtin_shell(final_script).await;
+        }
+
         let mut bash_args = vec![];
 
         bash_args.push("-c".to_string());
@@ -638,4 +742,40 @@ impl ScriptEnvironment {
 
         self.run_exec("bash", bash_args).await
     }
+
+    async fn run_builtin_shell(&mut self, final_script: String) -> Result<ScriptResult, Error> {
+        let mut env
+            = std::env::vars().collect::<BTreeMap<_, _>>();
+
+        for (key, value) in &self.env {
+            match value {
+                Some(val) => {
+                    env.insert(key.clone(), val.clone());
+                },
+
+                None => {
+                    env.remove(key);
+                },
+            };
+        }
+
+        let bin_dir
+            = self.install_binaries()?;
+
+        env.insert("PATH".to_string(), self.path_with_binaries(&bin_dir));
+        env.insert("BERRY_BIN_FOLDER".to_string(), bin_dir.to_file_string());
+
+        let output = run_shell_script(&final_script, ShellOptions {
+            cwd: self.cwd.clone(),
+            env,
+            stdin: self.stdin.clone(),
+            capture_output: !self.shell_forwarding || self.record_output,
+            forward_output: self.record_output,
+        }).await?;
+
+        match output.status.success() {
+            true => Ok(ScriptResult::Success(output)),
+            false => Ok(ScriptResult::Failure(output, "yarn-shell".to_string(), final_script)),
+        }
+    }
 }
@@ -0,0 +1,920 @@
+use std::{collections::BTreeMap, fs::{File, OpenOptions}, io::{Read, Write}, os::{fd::{AsFd, OwnedFd}, unix::process::ExitStatusExt}, path::PathBuf, process::{ExitStatus, Output, Stdio}};
+
+use futures::{future::{join_all, BoxFuture}, FutureExt};
+use tokio::task::JoinHandle;
+use zpm_utils::{Path, ToFileString};
+
+use crate::{
+    error::Error,
+    shell::parse::{parse_script, AndOr, Command, Connector, Pipeline, Redirection, RedirectionKind, Script, Word, WordPart},
+};
+
+#[cfg(test)]
+#[path = "./exec.test.rs"]
+mod exec_tests;
+
+const SHELL_NAME: &str = "yarn-shell";
+
+pub struct ShellOptions {
+    pub cwd: Path,
+    pub env: BTreeMap<String, String>,
+    pub stdin: Option<String>,
+    pub capture_output: bool,
+    pub forward_output: bool,
+}
+
+/// Run a script through the builtin shell rather than spawning bash. The
+/// script runs in-process, only spawning the external commands it references,
+/// so it behaves the same regardless of the shells available on the system.
+pub async fn run_shell_script(source: &str, options: ShellOptions) -> Result<Output, Error> {
+    let script
+        = parse_script(source)?;
+
+    let mut streams
+        = Streams::default();
+
+    if let Some(stdin) = options.stdin {
+        let (reader, mut writer)
+            = std::io::pipe()?;
+
+        tokio::task::spawn_blocking(move || {
+            let _ = writer.write_all(stdin.as_bytes());
+        });
+
+        streams.stdin = Some(reader.into());
+    }
+
+    let mut captures
+        = None;
+
+    if options.capture_output {
+        let (stdout_writer, stdout_task)
+            = capture_pipe(options.forward_output.then(|| Box::new(std::io::stdout()) as Box<dyn Write + Send>))?;
+        let (stderr_writer, stderr_task)
+            = capture_pipe(options.forward_output.then(|| Box::new(std::io::stderr()) as Box<dyn Write + Send>))?;
+
+        streams.stdout = Some(stdout_writer);
+        streams.stderr = Some(stderr_writer);
+
+        captures = Some((stdout_task, stderr_task));
+    }
+
+    let mut shell = Shell {
+        cwd: options.cwd,
+        env: options.env,
+        variables: BTreeMap::new(),
+        exit_code: 0,
+        exit_requested: false,
+    };
+
+    let exit_code
+        = shell.run_script(&script, &streams).await;
+
+    // Closes our side of the capture pipes, so that the readers get EOF
+    drop(streams);
+
+    let (stdout, stderr) = match captures {
+        Some((stdout_task, stderr_task)) => (stdout_task.await?, stderr_task.await?),
+        None => (vec![], vec![]),
+    };
+
+    Ok(Output {
+        status: ExitStatus::from_raw((exit_code & 0xff) << 8),
+        stdout,
+        stderr,
+    })
+}
+
+/// Create a pipe whose content will be collected; when `forward` is set, the
+/// data is also written into it as soon as it's received.
+fn capture_pipe(mut forward: Option<Box<dyn Write + Send>>) -> std::io::Result<(OwnedFd, JoinHandle<Vec<u8>>)> {
+    let (mut reader, writer)
+        = std::io::pipe()?;
+
+    let task = tokio::task::spawn_blocking(move || {
+        let mut data
+            = vec![];
+
+        let mut buffer
+            = [0u8; 8192];
+
+        while let Ok(size) = reader.read(&mut buffer) {
+            if size == 0 {
+                break;
+            }
+
+            if let Some(forward) = forward.as_mut() {
+                let _ = forward.write_all(&buffer[..size]);
+                let _ = forward.flush();
+            }
+
+            data.extend_from_slice(&buffer[..size]);
+        }
+
+        data
+    });
+
+    Ok((writer.into(), task))
+}
+
+fn exit_code_from_status(status: ExitStatus) -> i32 {
+    status.code()
+        .unwrap_or_else(|| 128 + status.signal().unwrap_or(0))
+}
+
+/// The standard streams of a command; `None` means that the stream is
+/// inherited from the current process.
+#[derive(Default)]
+struct Streams {
+    stdin: Option<OwnedFd>,
+    stdout: Option<OwnedFd>,
+    stderr: Option<OwnedFd>,
+}
+
+impl Streams {
+    fn try_clone(&self) -> std::io::Result<Self> {
+        Ok(Self {
+            stdin: self.stdin.as_ref().map(|fd| fd.try_clone()).transpose()?,
+            stdout: self.stdout.as_ref().map(|fd| fd.try_clone()).transpose()?,
+            stderr: self.stderr.as_ref().map(|fd| fd.try_clone()).transpose()?,
+        })
+    }
+
+    fn get(&self, fd: u32) -> std::io::Result<OwnedFd> {
+        match fd {
+            0 => self.stdin.as_ref().map_or_else(|| std::io::stdin().as_fd().try_clone_to_owned(), |fd| fd.try_clone()),
+            1 => self.stdout.as_ref().map_or_else(|| std::io::stdout().as_fd().try_clone_to_owned(), |fd| fd.try_clone()),
+            2 => self.stderr.as_ref().map_or_else(|| std::io::stderr().as_fd().try_clone_to_owned(), |fd| fd.try_clone()),
+            _ => Err(std::io::Error::other(format!("{}: bad file descriptor", fd))),
+        }
+    }
+
+    fn set(&mut self, fd: u32, value: OwnedFd) -> std::io::Result<()> {
+        match fd {
+            0 => self.stdin = Some(value),
+            1 => self.stdout = Some(value),
+            2 => self.stderr = Some(value),
+            _ => return Err(std::io::Error::other(format!("{}: bad file descriptor", fd))),
+        }
+
+        Ok(())
+    }
+
+    fn stdio(fd: &Option<OwnedFd>) -> std::io::Result<Stdio> {
+        match fd {
+            Some(fd) => Ok(fd.try_clone()?.into()),
+            None => Ok(Stdio::inherit()),
+        }
+    }
+
+    fn write_stdout(&self, data: &[u8]) {
+        let _ = match &self.stdout {
+            Some(fd) => fd.try_clone().and_then(|fd| File::from(fd).write_all(data)),
+            None => std::io::stdout().write_all(data).and_then(|_| std::io::stdout().flush()),
+        };
+    }
+
+    fn write_stderr(&self, data: &[u8]) {
+        let _ = match &self.stderr {
+            Some(fd) => fd.try_clone().and_then(|fd| File::from(fd).write_all(data)),
+            None => std::io::stderr().write_all(data),
+        };
+    }
+
+    /// Print an error message and return the exit code to use for the command
+    fn report(&self, message: impl std::fmt::Display, exit_code: i32) -> i32 {
+        self.write_stderr(format!("{}: {}\n", SHELL_NAME, message).as_bytes());
+        exit_code
+    }
+}
+
+/// A field being built during word expansion; `pattern` mirrors `value` but
+/// with the quoted characters escaped, so that only unquoted wildcards are
+/// interpreted when globbing.
+#[derive(Default)]
+struct Field {
+    value: String,
+    pattern: String,
+    has_glob: bool,
+}
+
+impl Field {
+    fn push_quoted(&mut self, value: &str) {
+        self.value.push_str(value);
+
+        for c in value.chars() {
+            if matches!(c, '*' | '?' | '[' | ']' | '{' | '}' | '\\') {
+                self.pattern.push('\\');
+            }
+
+            self.pattern.push(c);
+        }
+    }
+
+    fn push_unquoted(&mut self, value: &str) {
+        self.value.push_str(value);
+        self.pattern.push_str(value);
+
+        if value.contains(['*', '?', '[']) {
+            self.has_glob = true;
+        }
+    }
+}
+
+#[derive(Default)]
+struct Fields {
+    fields: Vec<Field>,
+    current: Option<Field>,
+}
+
+impl Fields {
+    fn current(&mut self) -> &mut Field {
+        self.current.get_or_insert_with(Field::default)
+    }
+
+    fn flush(&mut self) {
+        if let Some(field) = self.current.take() {
+            self.fields.push(field);
+        }
+    }
+
+    fn push_expansion(&mut self, value: &str, quoted: bool) {
+        if quoted {
+            self.current().push_quoted(value);
+            return;
+        }
+
+        if value.starts_with(char::is_whitespace) {
+            self.flush();
+        }
+
+        for (idx, piece) in value.split_whitespace().enumerate() {
+            if idx > 0 {
+                self.flush();
+            }
+
+            self.current().push_unquoted(piece);
+        }
+
+        if value.ends_with(char::is_whitespace) {
+            self.flush();
+        }
+    }
+}
+
+fn has_glob_chars(segment: &str) -> bool {
+    let mut chars
+        = segment.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            },
+
+            '*' | '?' | '[' => {
+                return true;
+            },
+
+            _ => {},
+        }
+    }
+
+    false
+}
+
+fn unescape(segment: &str) -> String {
+    let mut result
+        = String::new();
+
+    let mut chars
+        = segment.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => result.extend(chars.next()),
+            c => result.push(c),
+        }
+    }
+
+    result
+}
+
+#[derive(Clone)]
+struct Shell {
+    cwd: Path,
+    env: BTreeMap<String, String>,
+    variables: BTreeMap<String, String>,
+    exit_code: i32,
+    exit_requested: bool,
+}
+
+impl Shell {
+    fn resolve_path(&self, path: &str) -> PathBuf {
+        self.cwd.to_path_buf().join(path)
+    }
+
+    fn get_variable(&self, name: &str) -> Option<String> {
+        match name {
+            "?" => Some(self.exit_code.to_string()),
+            "$" => Some(std::process::id().to_string()),
+            "#" => Some("0".to_string()),
+            "0" => Some(SHELL_NAME.to_string()),
+            "@" | "*" => Some(String::new()),
+            _ => self.variables.get(name).or_else(|| self.env.get(name)).cloned(),
+        }
+    }
+
+    fn set_variable(&mut self, name: String, value: String) {
+        match self.env.get_mut(&name) {
+            Some(existing) => *existing = value,
+            None => {
+                self.variables.insert(name, value);
+            },
+        }
+    }
+
+    fn run_script<'a>(&'a mut self, script: &'a Script, streams: &'a Streams) -> BoxFuture<'a, i32> {
+        async move {
+            let mut background_jobs
+                = vec![];
+
+            for statement in &script.statements {
+                if self.exit_requested {
+                    break;
+                }
+
+                if !statement.background {
+                    self.exit_code = self.run_and_or(&statement.and_or, streams).await;
+                    continue;
+                }
+
+                let job_streams = match streams.try_clone() {
+                    Ok(job_streams) => job_streams,
+                    Err(error) => {
+                        self.exit_code = streams.report(error, 1);
+                        continue;
+                    },
+                };
+
+                let mut job_shell
+                    = self.clone();
+                let job_and_or
+                    = statement.and_or.clone();
+
+                background_jobs.push(tokio::spawn(async move {
+                    job_shell.run_and_or(&job_and_or, &job_streams).await
+                }));
+
+                self.exit_code = 0;
+            }
+
+            // Background jobs must complete before the script is considered done, as
+            // they may still be writing into the script output
+            for job in background_jobs {
+                let _ = job.await;
+            }
+
+            self.exit_code
+        }.boxed()
+    }
+
+    async fn run_and_or(&mut self, and_or: &AndOr, streams: &Streams) -> i32 {
+        let mut exit_code
+            = self.run_pipeline(&and_or.first, streams).await;
+
+        for (connector, pipeline) in &and_or.rest {
+            if self.exit_requested {
+                break;
+            }
+
+            let should_run = match connector {
+                Connector::And => exit_code == 0,
+                Connector::Or => exit_code != 0,
+            };
+
+            if should_run {
+                exit_code = self.run_pipeline(pipeline, streams).await;
+            }
+        }
+
+        exit_code
+    }
+
+    async fn run_pipeline(&mut self, pipeline: &Pipeline, streams: &Streams) -> i32 {
+        let exit_code = match pipeline.commands.as_slice() {
+            [command] => self.run_command(command, streams).await,
+            commands => self.run_piped_commands(commands, streams).await,
+        };
+
+        match pipeline.negated {
+            true => (exit_code == 0) as i32,
+            false => exit_code,
+        }
+    }
+
+    async fn run_piped_commands(&mut self, commands: &[Command], streams: &Streams) -> i32 {
+        let mut stage_streams
+            = vec![];
+
+        let mut next_stdin = match streams.stdin.as_ref().map(|fd| fd.try_clone()).transpose() {
+            Ok(stdin) => stdin,
+            Err(error) => return streams.report(error, 1),
+        };
+
+        for idx in 0..commands.len() {
+            let mut command_streams = match streams.try_clone() {
+                Ok(command_streams) => command_streams,
+                Err(error) => return streams.report(error, 1),
+            };
+
+            command_streams.stdin = next_stdin.take();
+
+            if idx + 1 < commands.len() {
+                let (reader, writer) = match std::io::pipe() {
+                    Ok(pipe) => pipe,
+                    Err(error) => return streams.report(error, 1),
+                };
+
+                command_streams.stdout = Some(writer.into());
+                next_stdin = Some(reader.into());
+            }
+
+            stage_streams.push(command_streams);
+        }
+
+        // Each command of the pipeline runs concurrently in its own copy of the
+        // shell state; the streams are dropped as soon as their command exits,
+        // which lets the next command know that its input is complete
+        let mut shells
+            = vec![self.clone(); commands.len()];
+
+        let runs = shells.iter_mut()
+            .zip(commands)
+            .zip(stage_streams)
+            .map(|((shell, command), command_streams)| async move {
+                shell.run_command(command, &command_streams).await
+            });
+
+        let exit_codes
+            = join_all(runs).await;
+
+        exit_codes.last().copied().unwrap_or(0)
+    }
+
+    async fn run_command(&mut self, command: &Command, streams: &Streams) -> i32 {
+        match command {
+            Command::Simple {assignments, args, redirections} => {
+                self.run_simple_command(assignments, args, redirections, streams).await
+            },
+
+            Command::Subshell {script, redirections} => {
+                let streams = match self.apply_redirections(redirections, streams).await {
+                    Ok(streams) => streams,
+                    Err(exit_code) => return exit_code,
+                };
+
+                let mut shell
+                    = self.clone();
+
+                shell.run_script(script, &streams).await
+            },
+
+            Command::Group {script, redirections} => {
+                let streams = match self.apply_redirections(redirections, streams).await {
+                    Ok(streams) => streams,
+                    Err(exit_code) => return exit_code,
+                };
+
+                self.run_script(script, &streams).await
+            },
+        }
+    }
+
+    async fn run_simple_command(&mut self, assignments: &[(String, Word)], args: &[Word], redirections: &[Redirection], streams: &Streams) -> i32 {
+        let mut argv
+            = vec![];
+
+        for arg in args {
+            argv.extend(self.expand_word(arg, streams).await);
+        }
+
+        let mut values
+            = vec![];
+
+        for (name, value) in assignments {
+            values.push((name.clone(), self.expand_word_to_string(value, streams).await));
+        }
+
+        let streams = match self.apply_redirections(redirections, streams).await {
+            Ok(streams) => streams,
+            Err(exit_code) => return exit_code,
+        };
+
+        if argv.is_empty() {
+            for (name, value) in values {
+                self.set_variable(name, value);
+            }
+
+            return 0;
+        }
+
+        if let Some(exit_code) = self.run_builtin(&argv, &streams) {
+            return exit_code;
+        }
+
+        self.run_external(&argv, values, &streams).await
+    }
+
+    async fn apply_redirections(&mut self, redirections: &[Redirection], streams: &Streams) -> Result<Streams, i32> {
+        let mut streams
+            = streams.try_clone()
+                .map_err(|error| streams.report(error, 1))?;
+
+        for redirection in redirections {
+            let target
+                = self.expand_word_to_string(&redirection.target, &streams).await;
+
+            let fd = match redirection.kind {
+                RedirectionKind::Input => {
+                    File::open(self.resolve_path(&target)).map(OwnedFd::from)
+                },
+
+                RedirectionKind::Output => {
+                    File::create(self.resolve_path(&target)).map(OwnedFd::from)
+                },
+
+                RedirectionKind::Append => {
+                    OpenOptions::new().append(true).create(true).open(self.resolve_path(&target)).map(OwnedFd::from)
+                },
+
+                RedirectionKind::Duplicate => {
+                    target.parse::<u32>()
+                        .map_err(|_| std::io::Error::other(format!("{}: ambiguous redirect", target)))
+                        .and_then(|fd| streams.get(fd))
+                },
+            };
+
+            fd.and_then(|fd| streams.set(redirection.fd, fd))
+                .map_err(|error| streams.report(format!("{}: {}", target, error), 1))?;
+        }
+
+        Ok(streams)
+    }
+
+    fn run_builtin(&mut self, argv: &[String], streams: &Streams) -> Option<i32> {
+        let exit_code = match argv[0].as_str() {
+            "true" | ":" => {
+                0
+            },
+
+            "false" => {
+                1
+            },
+
+            "echo" => {
+                let (args, newline) = match argv.get(1).map(String::as_str) {
+                    Some("-n") => (&argv[2..], false),
+                    _ => (&argv[1..], true),
+                };
+
+                let mut output
+                    = args.join(" ");
+
+                if newline {
+                    output.push('\n');
+                }
+
+                streams.write_stdout(output.as_bytes());
+                0
+            },
+
+            "pwd" => {
+                streams.write_stdout(format!("{}\n", self.cwd.to_file_string()).as_bytes());
+                0
+            },
+
+            "cd" => {
+                let target = match argv.get(1) {
+                    Some(target) => target.clone(),
+                    None => self.get_variable("HOME").unwrap_or_else(|| "/".to_string()),
+                };
+
+                let next_cwd
+                    = std::fs::canonicalize(self.resolve_path(&target))
+                        .map_err(|error| error.to_string())
+                        .and_then(|path| Path::try_from(path).map_err(|error| error.to_string()));
+
+                match next_cwd {
+                    Ok(next_cwd) if next_cwd.fs_is_dir() => {
+                        self.env.insert("PWD".to_string(), next_cwd.to_file_string());
+                        self.cwd = next_cwd;
+                        0
+                    },
+
+                    Ok(_) => {
+                        streams.report(format!("cd: {}: Not a directory", target), 1)
+                    },
+
+                    Err(error) => {
+                        streams.report(format!("cd: {}: {}", target, error), 1)
+                    },
+                }
+            },
+
+            "exit" => {
+                self.exit_requested = true;
+
+                match argv.get(1) {
+                    Some(code) => code.parse().unwrap_or(2),
+                    None => self.exit_code,
+                }
+            },
+
+            "export" => {
+                for arg in &argv[1..] {
+                    match arg.split_once('=') {
+                        Some((name, value)) => {
+                            self.variables.remove(name);
+                            self.env.insert(name.to_string(), value.to_string());
+                        },
+
+                        None => {
+                            if let Some(value) = self.variables.remove(arg) {
+                                self.env.insert(arg.clone(), value);
+                            }
+                        },
+                    }
+                }
+
+                0
+            },
+
+            "unset" => {
+                for arg in &argv[1..] {
+                    self.variables.remove(arg);
+                    self.env.remove(arg);
+                }
+
+                0
+            },
+
+            _ => {
+                return None;
+            },
+        };
+
+        Some(exit_code)
+    }
+
+    async fn run_external(&mut self, argv: &[String], assignments: Vec<(String, String)>, streams: &Streams) -> i32 {
+        let program = match argv[0].contains('/') {
+            true => self.resolve_path(&argv[0]).into_os_string(),
+            false => argv[0].clone().into(),
+        };
+
+        let stdio = Streams::stdio(&streams.stdin)
+            .and_then(|stdin| Ok((stdin, Streams::stdio(&streams.stdout)?, Streams::stdio(&streams.stderr)?)));
+
+        let (stdin, stdout, stderr) = match stdio {
+            Ok(stdio) => stdio,
+            Err(error) => return streams.report(error, 1),
+        };
+
+        let mut command
+            = tokio::process::Command::new(program);
+
+        command
+            .args(&argv[1..])
+            .current_dir(self.cwd.to_path_buf())
+            .env_clear()
+            .envs(&self.env)
+            .envs(assignments)
+            .stdin(stdin)
+            .stdout(stdout)
+            .stderr(stderr);
+
+        let child
+            = command.spawn();
+
+        // The command keeps its own copies of the streams; they must be closed
+        // for the readers at the other end of the pipes to ever get EOF
+        drop(command);
+
+        match child {
+            Ok(mut child) => match child.wait().await {
+                Ok(status) => exit_code_from_status(status),
+                Err(error) => streams.report(format!("{}: {}", argv[0], error), 1),
+            },
+
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                streams.report(format!("{}: command not found", argv[0]), 127)
+            },
+
+            Err(error) => {
+                streams.report(format!("{}: {}", argv[0], error), 126)
+            },
+        }
+    }
+
+    async fn run_substitution(&mut self, script: &Script, streams: &Streams) -> String {
+        let (mut reader, writer) = match std::io::pipe() {
+            Ok(pipe) => pipe,
+            Err(error) => {
+                self.exit_code = streams.report(error, 1);
+                return String::new();
+            },
+        };
+
+        let mut substitution_streams = match streams.try_clone() {
+            Ok(substitution_streams) => substitution_streams,
+            Err(error) => {
+                self.exit_code = streams.report(error, 1);
+                return String::new();
+            },
+        };
+
+        substitution_streams.stdout = Some(writer.into());
+
+        let output_task = tokio::task::spawn_blocking(move || {
+            let mut data
+                = vec![];
+
+            let _ = reader.read_to_end(&mut data);
+
+            data
+        });
+
+        let mut shell
+            = self.clone();
+
+        self.exit_code
+            = shell.run_script(script, &substitution_streams).await;
+
+        drop(substitution_streams);
+
+        let output
+            = output_task.await.unwrap_or_default();
+
+        String::from_utf8_lossy(&output)
+            .trim_end_matches('\n')
+            .to_string()
+    }
+
+    async fn expand_word_to_string(&mut self, word: &Word, streams: &Streams) -> String {
+        self.expand_word_fields(word, streams).await
+            .into_iter()
+            .map(|field| field.value)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    async fn expand_word(&mut self, word: &Word, streams: &Streams) -> Vec<String> {
+        let mut values
+            = vec![];
+
+        for field in self.expand_word_fields(word, streams).await {
+            let matches = match field.has_glob {
+                true => self.expand_glob(&field.pattern),
+                false => vec![],
+            };
+
+            // Patterns that don't match anything are kept as-is, like in bash
+            match matches.is_empty() {
+                true => values.push(field.value),
+                false => values.extend(matches),
+            }
+        }
+
+        values
+    }
+
+    fn expand_word_fields<'a>(&'a mut self, word: &'a Word, streams: &'a Streams) -> BoxFuture<'a, Vec<Field>> {
+        async move {
+            let mut fields
+                = Fields::default();
+
+            for (idx, part) in word.parts.iter().enumerate() {
+                match part {
+                    WordPart::Text {value, quoted: true} => {
+                        fields.current().push_quoted(value);
+                    },
+
+                    WordPart::Text {value, quoted: false} => {
+                        let home = match idx == 0 && (value == "~" || value.starts_with("~/")) {
+                            true => self.get_variable("HOME"),
+                            false => None,
+                        };
+
+                        match home {
+                            Some(home) => {
+                                fields.current().push_quoted(&home);
+                                fields.current().push_unquoted(&value[1..]);
+                            },
+
+                            None => {
+                                fields.current().push_unquoted(value);
+                            },
+                        }
+                    },
+
+                    WordPart::Variable {name, fallback, quoted} => {
+                        let value = match (self.get_variable(name).filter(|value| !value.is_empty()), fallback) {
+                            (Some(value), _) => value,
+                            (None, Some(fallback)) => self.expand_word_to_string(fallback, streams).await,
+                            (None, None) => String::new(),
+                        };
+
+                        fields.push_expansion(&value, *quoted);
+                    },
+
+                    WordPart::Substitution {script, quoted} => {
+                        let output
+                            = self.run_substitution(script, streams).await;
+
+                        fields.push_expansion(&output, *quoted);
+                    },
+                }
+            }
+
+            fields.flush();
+            fields.fields
+        }.boxed()
+    }
+
+    fn expand_glob(&self, pattern: &str) -> Vec<String> {
+        let is_absolute
+            = pattern.starts_with('/');
+
+        let mut candidates = vec![match is_absolute {
+            true => (PathBuf::from("/"), "/".to_string()),
+            false => (self.cwd.to_path_buf(), String::new()),
+        }];
+
+        for segment in pattern.split('/').filter(|segment| !segment.is_empty()) {
+            let mut next_candidates
+                = vec![];
+
+            if !has_glob_chars(segment) {
+                let name
+                    = unescape(segment);
+
+                for (path, display) in candidates {
+                    next_candidates.push((path.join(&name), join_display(&display, &name)));
+                }
+
+                candidates = next_candidates;
+                continue;
+            }
+
+            let Ok(glob) = globset::GlobBuilder::new(segment).literal_separator(true).backslash_escape(true).build() else {
+                return vec![];
+            };
+
+            let matcher
+                = glob.compile_matcher();
+
+            for (path, display) in candidates {
+                let Ok(entries) = std::fs::read_dir(&path) else {
+                    continue;
+                };
+
+                for entry in entries.flatten() {
+                    let Ok(name) = entry.file_name().into_string() else {
+                        continue;
+                    };
+
+                    // Hidden files are only matched by patterns explicitly starting with a dot
+                    if name.starts_with('.') && !segment.starts_with('.') {
+                        continue;
+                    }
+
+                    if matcher.is_match(&name) {
+                        next_candidates.push((path.join(&name), join_display(&display, &name)));
+                    }
+                }
+            }
+
+            candidates = next_candidates;
+        }
+
+        let mut matches = candidates.into_iter()
+            .filter(|(path, _)| path.exists())
+            .filter(|(path, _)| !pattern.ends_with('/') || path.is_dir())
+            .map(|(_, display)| match pattern.ends_with('/') {
+                true => format!("{}/", display),
+                false => display,
+            })
+            .collect::<Vec<_>>();
+
+        matches.sort();
+        matches
+    }
+}
+
+fn join_display(display: &str, name: &str) -> String {
+    match display.is_empty() || display.ends_with('/') {
+        true => format!("{}{}", display, name),
+        false => format!("{}/{}", display, name),
+    }
+}
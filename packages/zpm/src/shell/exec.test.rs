@@ -0,0 +1,217 @@
+use super::*;
+
+struct ScriptResult {
+    exit_code: i32,
+    stdout: String,
+    stderr: String,
+}
+
+async fn run_in(cwd: &Path, source: &str) -> ScriptResult {
+    let mut env
+        = BTreeMap::new();
+
+    env.insert("PATH".to_string(), std::env::var("PATH").unwrap_or_default());
+    env.insert("HOME".to_string(), cwd.to_file_string());
+
+    let output = run_shell_script(source, ShellOptions {
+        cwd: cwd.clone(),
+        env,
+        stdin: None,
+        capture_output: true,
+        forward_output: false,
+    }).await.unwrap();
+
+    ScriptResult {
+        exit_code: output.status.code().unwrap(),
+        stdout: String::from_utf8(output.stdout).unwrap(),
+        stderr: String::from_utf8(output.stderr).unwrap(),
+    }
+}
+
+async fn run(source: &str) -> ScriptResult {
+    let cwd
+        = Path::temp_dir().unwrap();
+
+    let result
+        = run_in(&cwd, source).await;
+
+    cwd.fs_rm().unwrap();
+
+    result
+}
+
+#[tokio::test]
+async fn should_pipe_commands() {
+    let result
+        = run("echo hello world | tr a-z A-Z | cat").await;
+
+    assert_eq!(result.exit_code, 0);
+    assert_eq!(result.stdout, "HELLO WORLD\n");
+}
+
+#[tokio::test]
+async fn should_return_the_exit_code_of_the_last_piped_command() {
+    assert_eq!(run("false | true").await.exit_code, 0);
+    assert_eq!(run("true | false").await.exit_code, 1);
+}
+
+#[tokio::test]
+async fn should_negate_pipelines() {
+    assert_eq!(run("! false").await.exit_code, 0);
+    assert_eq!(run("! true").await.exit_code, 1);
+    assert_eq!(run("! echo foo | grep bar").await.exit_code, 0);
+}
+
+#[tokio::test]
+async fn should_chain_commands_depending_on_their_exit_code() {
+    let result
+        = run("true && echo a; false && echo b; false || echo c; true || echo d").await;
+
+    assert_eq!(result.stdout, "a\nc\n");
+
+    assert_eq!(run("true && false").await.exit_code, 1);
+    assert_eq!(run("false || false").await.exit_code, 1);
+    assert_eq!(run("false && true").await.exit_code, 1);
+    assert_eq!(run("false || true").await.exit_code, 0);
+
+    let result
+        = run("false; echo $?; true; echo $?").await;
+
+    assert_eq!(result.stdout, "1\n0\n");
+}
+
+#[tokio::test]
+async fn should_redirect_the_output_into_files() {
+    let cwd
+        = Path::temp_dir().unwrap();
+
+    let result
+        = run_in(&cwd, "echo foo > out.txt; echo bar > out.txt; echo baz >> out.txt").await;
+
+    assert_eq!(result.exit_code, 0);
+    assert_eq!(result.stdout, "");
+    assert_eq!(cwd.with_join_str("out.txt").fs_read_text().unwrap(), "bar\nbaz\n");
+
+    cwd.fs_rm().unwrap();
+}
+
+#[tokio::test]
+async fn should_redirect_the_input_from_files() {
+    let cwd
+        = Path::temp_dir().unwrap();
+
+    cwd.with_join_str("in.txt").fs_write("hello\n").unwrap();
+
+    let result
+        = run_in(&cwd, "tr a-z A-Z < in.txt").await;
+
+    assert_eq!(result.stdout, "HELLO\n");
+
+    let result
+        = run_in(&cwd, "cat < missing.txt").await;
+
+    assert_eq!(result.exit_code, 1);
+    assert!(result.stderr.contains("missing.txt"));
+
+    cwd.fs_rm().unwrap();
+}
+
+#[tokio::test]
+async fn should_duplicate_file_descriptors() {
+    let result
+        = run("ls /non-existent-path 2>&1").await;
+
+    assert_ne!(result.exit_code, 0);
+    assert!(result.stdout.contains("non-existent-path"));
+    assert_eq!(result.stderr, "");
+
+    let result
+        = run("echo foo 1>&2").await;
+
+    assert_eq!(result.stdout, "");
+    assert_eq!(result.stderr, "foo\n");
+}
+
+#[tokio::test]
+async fn should_only_glob_unquoted_patterns() {
+    let cwd
+        = Path::temp_dir().unwrap();
+
+    cwd.with_join_str("a.js").fs_write("").unwrap();
+    cwd.with_join_str("b.js").fs_write("").unwrap();
+    cwd.with_join_str(".hidden.js").fs_write("").unwrap();
+    cwd.with_join_str("c.ts").fs_write("").unwrap();
+
+    assert_eq!(run_in(&cwd, "echo *.js").await.stdout, "a.js b.js\n");
+    assert_eq!(run_in(&cwd, "echo \"*.js\"").await.stdout, "*.js\n");
+    assert_eq!(run_in(&cwd, "echo '*.js'").await.stdout, "*.js\n");
+    assert_eq!(run_in(&cwd, "echo \"a\"*.js").await.stdout, "a.js\n");
+
+    // Patterns that don't match anything are kept as-is
+    assert_eq!(run_in(&cwd, "echo *.rs").await.stdout, "*.rs\n");
+
+    cwd.fs_rm().unwrap();
+}
+
+#[tokio::test]
+async fn should_substitute_command_outputs() {
+    assert_eq!(run("echo $(echo foo)-bar").await.stdout, "foo-bar\n");
+    assert_eq!(run("echo `echo foo`-bar").await.stdout, "foo-bar\n");
+    assert_eq!(run("echo $(echo $(echo nested))").await.stdout, "nested\n");
+
+    // Unquoted substitutions are split into multiple arguments, quoted ones aren't
+    assert_eq!(run("printf '[%s]' $(echo a b)").await.stdout, "[a][b]");
+    assert_eq!(run("printf '[%s]' \"$(echo a b)\"").await.stdout, "[a b]");
+}
+
+#[tokio::test]
+async fn should_isolate_the_state_of_subshells() {
+    let cwd
+        = Path::temp_dir().unwrap();
+
+    cwd.with_join_str("sub").fs_create_dir_all().unwrap();
+
+    let result
+        = run_in(&cwd, "(cd sub; FOO=bar); basename \"$(pwd)\"; echo \"[$FOO]\"").await;
+
+    assert_eq!(result.stdout, format!("{}\n[]\n", cwd.basename().unwrap()));
+
+    let result
+        = run_in(&cwd, "{ cd sub; FOO=bar; }; basename \"$(pwd)\"; echo \"[$FOO]\"").await;
+
+    assert_eq!(result.stdout, "sub\n[bar]\n");
+
+    cwd.fs_rm().unwrap();
+}
+
+#[tokio::test]
+async fn should_exit_early() {
+    let result
+        = run("echo a; exit 3; echo b").await;
+
+    assert_eq!(result.exit_code, 3);
+    assert_eq!(result.stdout, "a\n");
+
+    // Exiting from a subshell only exits the subshell
+    let result
+        = run("(exit 4); echo $?").await;
+
+    assert_eq!(result.exit_code, 0);
+    assert_eq!(result.stdout, "4\n");
+
+    let result
+        = run("false; exit").await;
+
+    assert_eq!(result.exit_code, 1);
+}
+
+#[tokio::test]
+async fn should_report_unknown_commands() {
+    let result
+        = run("this-command-does-not-exist; echo $?").await;
+
+    assert_eq!(result.stdout, "127\n");
+    assert!(result.stderr.contains("this-command-does-not-exist: command not found"));
+
+    assert_eq!(run("this-command-does-not-exist").await.exit_code, 127);
+}
@@ -0,0 +1,845 @@
+use crate::error::Error;
+
+#[cfg(test)]
+#[path = "./parse.test.rs"]
+mod parse_tests;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WordPart {
+    /// Raw text; unquoted text is subject to globbing and tilde expansion
+    Text {
+        value: String,
+        quoted: bool,
+    },
+
+    /// `$NAME`, `${NAME}`, or `${NAME:-fallback}`; unquoted values are split
+    /// on whitespace
+    Variable {
+        name: String,
+        fallback: Option<Word>,
+        quoted: bool,
+    },
+
+    /// `$(...)` or `` `...` ``; unquoted outputs are split on whitespace
+    Substitution {
+        script: Script,
+        quoted: bool,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Word {
+    pub parts: Vec<WordPart>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedirectionKind {
+    /// `<`
+    Input,
+
+    /// `>`
+    Output,
+
+    /// `>>`
+    Append,
+
+    /// `>&`; the target is the file descriptor to duplicate
+    Duplicate,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redirection {
+    pub fd: u32,
+    pub kind: RedirectionKind,
+    pub target: Word,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Simple {
+        assignments: Vec<(String, Word)>,
+        args: Vec<Word>,
+        redirections: Vec<Redirection>,
+    },
+
+    /// `( ... )`; runs in a copy of the shell state
+    Subshell {
+        script: Script,
+        redirections: Vec<Redirection>,
+    },
+
+    /// `{ ...; }`; runs in the current shell state
+    Group {
+        script: Script,
+        redirections: Vec<Redirection>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pipeline {
+    pub negated: bool,
+    pub commands: Vec<Command>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connector {
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AndOr {
+    pub first: Pipeline,
+    pub rest: Vec<(Connector, Pipeline)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Statement {
+    pub and_or: AndOr,
+    pub background: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Script {
+    pub statements: Vec<Statement>,
+}
+
+/// Parse a script using a subset of the POSIX shell grammar: sequences (`;`,
+/// `&`, newlines), `&&`/`||` chains, pipelines, subshells, groups, variable
+/// assignments, redirections, quoting, and `$VAR`/`$(...)` expansions.
+pub fn parse_script(source: &str) -> Result<Script, Error> {
+    let mut parser
+        = Parser::new(source);
+
+    let script
+        = parser.parse_script(None)?;
+
+    if let Some(c) = parser.peek() {
+        return Err(parser.error(&format!("unexpected character '{}'", c)));
+    }
+
+    Ok(script)
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn is_word_boundary(c: char) -> bool {
+    c.is_whitespace() || matches!(c, ';' | '&' | '|' | '<' | '>' | '(' | ')')
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn new(source: &str) -> Self {
+        Self {
+            chars: source.chars().collect(),
+            pos: 0,
+        }
+    }
+
+    fn error(&self, message: &str) -> Error {
+        Error::ShellParseError(format!("{} (at offset {})", message, self.pos))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn starts_with(&self, pattern: &str) -> bool {
+        pattern.chars().enumerate().all(|(idx, c)| self.peek_at(idx) == Some(c))
+    }
+
+    fn skip_blanks(&mut self) {
+        while let Some(c) = self.peek() {
+            match c {
+                ' ' | '\t' | '\r' => {
+                    self.pos += 1;
+                },
+
+                '\\' if self.peek_at(1) == Some('\n') => {
+                    self.pos += 2;
+                },
+
+                '#' => {
+                    while self.peek().is_some_and(|c| c != '\n') {
+                        self.pos += 1;
+                    }
+                },
+
+                _ => break,
+            }
+        }
+    }
+
+    fn skip_blanks_and_newlines(&mut self) {
+        loop {
+            self.skip_blanks();
+
+            if self.peek() != Some('\n') {
+                break;
+            }
+
+            self.pos += 1;
+        }
+    }
+
+    /// Whether the next token is the given reserved word (`{` or `}`), which
+    /// are only recognized when they stand on their own
+    fn at_reserved_word(&self, word: char) -> bool {
+        self.peek() == Some(word) && self.peek_at(1).is_none_or(is_word_boundary)
+    }
+
+    fn parse_script(&mut self, terminator: Option<char>) -> Result<Script, Error> {
+        let mut statements
+            = vec![];
+
+        loop {
+            self.skip_blanks_and_newlines();
+
+            match (self.peek(), terminator) {
+                (None, None) => break,
+                (None, Some(terminator)) => return Err(self.error(&format!("expected '{}'", terminator))),
+                (Some(')'), Some(')')) => break,
+                (Some('}'), Some('}')) if self.at_reserved_word('}') => break,
+                _ => {},
+            }
+
+            let and_or
+                = self.parse_and_or()?;
+
+            self.skip_blanks();
+
+            let background = match self.peek() {
+                Some('&') => {
+                    self.pos += 1;
+                    true
+                },
+
+                Some(';') => {
+                    self.pos += 1;
+                    false
+                },
+
+                Some('\n') | None => {
+                    false
+                },
+
+                Some(')') if terminator == Some(')') => {
+                    false
+                },
+
+                Some('}') if terminator == Some('}') && self.at_reserved_word('}') => {
+                    false
+                },
+
+                Some(c) => {
+                    return Err(self.error(&format!("unexpected character '{}'", c)));
+                },
+            };
+
+            statements.push(Statement {
+                and_or,
+                background,
+            });
+        }
+
+        Ok(Script {
+            statements,
+        })
+    }
+
+    fn parse_and_or(&mut self) -> Result<AndOr, Error> {
+        let first
+            = self.parse_pipeline()?;
+
+        let mut rest
+            = vec![];
+
+        loop {
+            self.skip_blanks();
+
+            let connector = if self.starts_with("&&") {
+                Connector::And
+            } else if self.starts_with("||") {
+                Connector::Or
+            } else {
+                break;
+            };
+
+            self.pos += 2;
+            self.skip_blanks_and_newlines();
+
+            rest.push((connector, self.parse_pipeline()?));
+        }
+
+        Ok(AndOr {
+            first,
+            rest,
+        })
+    }
+
+    fn parse_pipeline(&mut self) -> Result<Pipeline, Error> {
+        self.skip_blanks();
+
+        let negated
+            = self.at_reserved_word('!');
+
+        if negated {
+            self.pos += 1;
+        }
+
+        let mut commands
+            = vec![self.parse_command()?];
+
+        loop {
+            self.skip_blanks();
+
+            if self.peek() != Some('|') || self.starts_with("||") {
+                break;
+            }
+
+            self.pos += 1;
+            self.skip_blanks_and_newlines();
+
+            commands.push(self.parse_command()?);
+        }
+
+        Ok(Pipeline {
+            negated,
+            commands,
+        })
+    }
+
+    fn parse_command(&mut self) -> Result<Command, Error> {
+        self.skip_blanks();
+
+        if self.peek() == Some('(') {
+            self.pos += 1;
+
+            let script
+                = self.parse_script(Some(')'))?;
+
+            self.pos += 1;
+
+            return Ok(Command::Subshell {
+                script,
+                redirections: self.parse_trailing_redirections()?,
+            });
+        }
+
+        if self.at_reserved_word('{') {
+            self.pos += 1;
+
+            let script
+                = self.parse_script(Some('}'))?;
+
+            self.pos += 1;
+
+            return Ok(Command::Group {
+                script,
+                redirections: self.parse_trailing_redirections()?,
+            });
+        }
+
+        let mut assignments
+            = vec![];
+        let mut args
+            = vec![];
+        let mut redirections
+            = vec![];
+
+        loop {
+            self.skip_blanks();
+
+            if let Some(redirection) = self.try_parse_redirection()? {
+                redirections.extend(redirection);
+                continue;
+            }
+
+            match self.peek() {
+                None => break,
+                Some(c) if is_word_boundary(c) => break,
+                Some('}') if self.at_reserved_word('}') && args.is_empty() && assignments.is_empty() => break,
+                _ => {},
+            }
+
+            let word
+                = self.parse_word()?;
+
+            if args.is_empty() {
+                if let Some(assignment) = split_assignment(&word) {
+                    assignments.push(assignment);
+                    continue;
+                }
+            }
+
+            args.push(word);
+        }
+
+        if assignments.is_empty() && args.is_empty() && redirections.is_empty() {
+            return Err(match self.peek() {
+                Some(c) => self.error(&format!("unexpected character '{}'", c)),
+                None => self.error("unexpected end of script"),
+            });
+        }
+
+        Ok(Command::Simple {
+            assignments,
+            args,
+            redirections,
+        })
+    }
+
+    fn parse_trailing_redirections(&mut self) -> Result<Vec<Redirection>, Error> {
+        let mut redirections
+            = vec![];
+
+        loop {
+            self.skip_blanks();
+
+            match self.try_parse_redirection()? {
+                Some(redirection) => redirections.extend(redirection),
+                None => break,
+            }
+        }
+
+        Ok(redirections)
+    }
+
+    fn try_parse_redirection(&mut self) -> Result<Option<Vec<Redirection>>, Error> {
+        let start
+            = self.pos;
+
+        let mut digits
+            = String::new();
+
+        while let Some(c) = self.peek().filter(|c| c.is_ascii_digit()) {
+            digits.push(c);
+            self.pos += 1;
+        }
+
+        let both_outputs
+            = digits.is_empty() && self.starts_with("&>");
+
+        if both_outputs {
+            self.pos += 1;
+        }
+
+        let (kind, default_fd) = if self.starts_with(">>") {
+            self.pos += 2;
+            (RedirectionKind::Append, 1)
+        } else if self.starts_with(">&") {
+            self.pos += 2;
+            (RedirectionKind::Duplicate, 1)
+        } else if self.starts_with(">") {
+            self.pos += 1;
+            (RedirectionKind::Output, 1)
+        } else if self.starts_with("<") {
+            self.pos += 1;
+            (RedirectionKind::Input, 0)
+        } else {
+            self.pos = start;
+            return Ok(None);
+        };
+
+        let fd = match digits.is_empty() {
+            true => default_fd,
+            false => digits.parse().map_err(|_| self.error("invalid file descriptor"))?,
+        };
+
+        self.skip_blanks();
+
+        if self.peek().is_none_or(is_word_boundary) {
+            return Err(self.error("expected a redirection target"));
+        }
+
+        let target
+            = self.parse_word()?;
+
+        let mut redirections = vec![Redirection {
+            fd,
+            kind,
+            target,
+        }];
+
+        // `&>file` is a shorthand for `>file 2>&1`
+        if both_outputs {
+            redirections.push(Redirection {
+                fd: 2,
+                kind: RedirectionKind::Duplicate,
+                target: Word {parts: vec![WordPart::Text {value: "1".to_string(), quoted: false}]},
+            });
+        }
+
+        Ok(Some(redirections))
+    }
+
+    fn parse_word(&mut self) -> Result<Word, Error> {
+        let mut word
+            = WordBuilder::default();
+
+        while let Some(c) = self.peek() {
+            if is_word_boundary(c) {
+                break;
+            }
+
+            match c {
+                '\\' => {
+                    self.pos += 1;
+
+                    match self.peek() {
+                        Some('\n') => {
+                            self.pos += 1;
+                        },
+
+                        Some(escaped) => {
+                            self.pos += 1;
+                            word.push_char(escaped, true);
+                        },
+
+                        None => {
+                            word.push_char('\\', true);
+                        },
+                    }
+                },
+
+                '\'' => {
+                    self.pos += 1;
+
+                    let mut value
+                        = String::new();
+
+                    loop {
+                        match self.peek() {
+                            Some('\'') => break,
+                            Some(c) => value.push(c),
+                            None => return Err(self.error("unterminated single quote")),
+                        }
+
+                        self.pos += 1;
+                    }
+
+                    self.pos += 1;
+                    word.push_str(&value, true);
+                },
+
+                '"' => {
+                    self.pos += 1;
+                    self.parse_double_quoted(&mut word)?;
+                },
+
+                '$' => {
+                    self.parse_dollar(&mut word, false)?;
+                },
+
+                '`' => {
+                    let script
+                        = self.parse_backticks()?;
+
+                    word.push_part(WordPart::Substitution {script, quoted: false});
+                },
+
+                _ => {
+                    self.pos += 1;
+                    word.push_char(c, false);
+                },
+            }
+        }
+
+        Ok(word.build())
+    }
+
+    fn parse_double_quoted(&mut self, word: &mut WordBuilder) -> Result<(), Error> {
+        // Ensures that `""` still produces an (empty) argument
+        word.push_str("", true);
+
+        loop {
+            match self.peek() {
+                None => {
+                    return Err(self.error("unterminated double quote"));
+                },
+
+                Some('"') => {
+                    self.pos += 1;
+                    return Ok(());
+                },
+
+                Some('\\') => {
+                    self.pos += 1;
+
+                    match self.peek() {
+                        Some('\n') => {
+                            self.pos += 1;
+                        },
+
+                        Some(c @ ('$' | '`' | '"' | '\\')) => {
+                            self.pos += 1;
+                            word.push_char(c, true);
+                        },
+
+                        _ => {
+                            word.push_char('\\', true);
+                        },
+                    }
+                },
+
+                Some('$') => {
+                    self.parse_dollar(word, true)?;
+                },
+
+                Some('`') => {
+                    let script
+                        = self.parse_backticks()?;
+
+                    word.push_part(WordPart::Substitution {script, quoted: true});
+                },
+
+                Some(c) => {
+                    self.pos += 1;
+                    word.push_char(c, true);
+                },
+            }
+        }
+    }
+
+    fn parse_dollar(&mut self, word: &mut WordBuilder, quoted: bool) -> Result<(), Error> {
+        self.pos += 1;
+
+        match self.peek() {
+            Some('(') if self.peek_at(1) == Some('(') => {
+                Err(self.error("arithmetic expansions aren't supported"))
+            },
+
+            Some('(') => {
+                self.pos += 1;
+
+                let script
+                    = self.parse_script(Some(')'))?;
+
+                self.pos += 1;
+                word.push_part(WordPart::Substitution {script, quoted});
+
+                Ok(())
+            },
+
+            Some('{') => {
+                self.pos += 1;
+
+                let name
+                    = self.parse_variable_name()
+                        .ok_or_else(|| self.error("invalid variable name"))?;
+
+                let fallback = if self.starts_with(":-") {
+                    self.pos += 2;
+                    Some(self.parse_fallback(quoted)?)
+                } else {
+                    None
+                };
+
+                if self.peek() != Some('}') {
+                    return Err(self.error("expected '}'"));
+                }
+
+                self.pos += 1;
+                word.push_part(WordPart::Variable {name, fallback, quoted});
+
+                Ok(())
+            },
+
+            _ => {
+                match self.parse_variable_name() {
+                    Some(name) => word.push_part(WordPart::Variable {name, fallback: None, quoted}),
+                    None => word.push_char('$', quoted),
+                }
+
+                Ok(())
+            },
+        }
+    }
+
+    fn parse_variable_name(&mut self) -> Option<String> {
+        match self.peek()? {
+            c @ ('?' | '#' | '@' | '*' | '$' | '0'..='9') => {
+                self.pos += 1;
+                Some(c.to_string())
+            },
+
+            c if is_name_start(c) => {
+                let mut name
+                    = String::new();
+
+                while let Some(c) = self.peek().filter(|c| is_name_char(*c)) {
+                    name.push(c);
+                    self.pos += 1;
+                }
+
+                Some(name)
+            },
+
+            _ => None,
+        }
+    }
+
+    fn parse_fallback(&mut self, quoted: bool) -> Result<Word, Error> {
+        let mut word
+            = WordBuilder::default();
+
+        loop {
+            match self.peek() {
+                None => {
+                    return Err(self.error("expected '}'"));
+                },
+
+                Some('}') => {
+                    return Ok(word.build());
+                },
+
+                Some('\\') => {
+                    self.pos += 1;
+
+                    if let Some(c) = self.peek() {
+                        self.pos += 1;
+                        word.push_char(c, true);
+                    }
+                },
+
+                Some('\'') if !quoted => {
+                    self.pos += 1;
+
+                    while let Some(c) = self.peek().filter(|c| *c != '\'') {
+                        word.push_char(c, true);
+                        self.pos += 1;
+                    }
+
+                    if self.peek().is_none() {
+                        return Err(self.error("unterminated single quote"));
+                    }
+
+                    self.pos += 1;
+                },
+
+                Some('"') if !quoted => {
+                    self.pos += 1;
+                    self.parse_double_quoted(&mut word)?;
+                },
+
+                Some('$') => {
+                    self.parse_dollar(&mut word, quoted)?;
+                },
+
+                Some(c) => {
+                    self.pos += 1;
+                    word.push_char(c, quoted);
+                },
+            }
+        }
+    }
+
+    fn parse_backticks(&mut self) -> Result<Script, Error> {
+        self.pos += 1;
+
+        let mut source
+            = String::new();
+
+        loop {
+            match self.peek() {
+                None => {
+                    return Err(self.error("unterminated backquote"));
+                },
+
+                Some('`') => {
+                    self.pos += 1;
+                    break;
+                },
+
+                Some('\\') if matches!(self.peek_at(1), Some('`' | '\\' | '$')) => {
+                    source.push(self.peek_at(1).unwrap());
+                    self.pos += 2;
+                },
+
+                Some(c) => {
+                    source.push(c);
+                    self.pos += 1;
+                },
+            }
+        }
+
+        parse_script(&source)
+    }
+}
+
+/// Split `NAME=value` words into variable assignments; the `=` must be unquoted
+fn split_assignment(word: &Word) -> Option<(String, Word)> {
+    let Some(WordPart::Text {value, quoted: false}) = word.parts.first() else {
+        return None;
+    };
+
+    let (name, rest)
+        = value.split_once('=')?;
+
+    let mut name_chars
+        = name.chars();
+
+    if !name_chars.next().is_some_and(is_name_start) || !name_chars.all(is_name_char) {
+        return None;
+    }
+
+    let mut parts
+        = vec![];
+
+    if !rest.is_empty() {
+        parts.push(WordPart::Text {value: rest.to_string(), quoted: false});
+    }
+
+    parts.extend(word.parts[1..].iter().cloned());
+
+    Some((name.to_string(), Word {parts}))
+}
+
+#[derive(Default)]
+struct WordBuilder {
+    parts: Vec<WordPart>,
+}
+
+impl WordBuilder {
+    fn push_char(&mut self, c: char, quoted: bool) {
+        let mut buffer
+            = [0; 4];
+
+        self.push_str(c.encode_utf8(&mut buffer), quoted);
+    }
+
+    fn push_str(&mut self, str: &str, quoted: bool) {
+        if let Some(WordPart::Text {value, quoted: last_quoted}) = self.parts.last_mut() {
+            if *last_quoted == quoted {
+                value.push_str(str);
+                return;
+            }
+        }
+
+        self.parts.push(WordPart::Text {value: str.to_string(), quoted});
+    }
+
+    fn push_part(&mut self, part: WordPart) {
+        self.parts.push(part);
+    }
+
+    fn build(self) -> Word {
+        Word {
+            parts: self.parts,
+        }
+    }
+}
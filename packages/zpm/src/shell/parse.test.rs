@@ -0,0 +1,151 @@
+use super::*;
+
+fn text(value: &str, quoted: bool) -> WordPart {
+    WordPart::Text {value: value.to_string(), quoted}
+}
+
+fn word(parts: Vec<WordPart>) -> Word {
+    Word {parts}
+}
+
+fn simple_args(script: &Script) -> Vec<Vec<Word>> {
+    script.statements.iter()
+        .flat_map(|statement| std::iter::once(&statement.and_or.first).chain(statement.and_or.rest.iter().map(|(_, pipeline)| pipeline)))
+        .flat_map(|pipeline| pipeline.commands.iter())
+        .map(|command| match command {
+            Command::Simple {args, ..} => args.clone(),
+            _ => vec![],
+        })
+        .collect()
+}
+
+#[test]
+fn should_parse_sequences_and_chains() {
+    let script
+        = parse_script("a && b || c; d &\ne").unwrap();
+
+    assert_eq!(script.statements.len(), 3);
+
+    assert_eq!(script.statements[0].and_or.rest.iter().map(|(connector, _)| *connector).collect::<Vec<_>>(), vec![Connector::And, Connector::Or]);
+    assert!(!script.statements[0].background);
+    assert!(script.statements[1].background);
+    assert!(!script.statements[2].background);
+}
+
+#[test]
+fn should_parse_pipelines() {
+    let script
+        = parse_script("! cat file | grep foo | wc -l").unwrap();
+
+    let pipeline
+        = &script.statements[0].and_or.first;
+
+    assert!(pipeline.negated);
+    assert_eq!(pipeline.commands.len(), 3);
+}
+
+#[test]
+fn should_parse_quotes() {
+    let script
+        = parse_script(r#"echo 'a b' "c $HOME d" e\ f"#).unwrap();
+
+    assert_eq!(simple_args(&script), vec![vec![
+        word(vec![text("echo", false)]),
+        word(vec![text("a b", true)]),
+        word(vec![
+            text("c ", true),
+            WordPart::Variable {name: "HOME".to_string(), fallback: None, quoted: true},
+            text(" d", true),
+        ]),
+        word(vec![text("e", false), text(" ", true), text("f", false)]),
+    ]]);
+}
+
+#[test]
+fn should_parse_empty_quotes_as_an_argument() {
+    let script
+        = parse_script(r#"echo """#).unwrap();
+
+    assert_eq!(simple_args(&script), vec![vec![
+        word(vec![text("echo", false)]),
+        word(vec![text("", true)]),
+    ]]);
+}
+
+#[test]
+fn should_parse_assignments() {
+    let script
+        = parse_script("FOO=bar BAZ= node index.js A=B").unwrap();
+
+    let Command::Simple {assignments, args, ..} = &script.statements[0].and_or.first.commands[0] else {
+        panic!("Expected a simple command");
+    };
+
+    assert_eq!(assignments, &vec![
+        ("FOO".to_string(), word(vec![text("bar", false)])),
+        ("BAZ".to_string(), word(vec![])),
+    ]);
+
+    assert_eq!(args.len(), 3);
+}
+
+#[test]
+fn should_parse_redirections() {
+    let script
+        = parse_script("cmd > out.txt 2>&1 < in.txt &> all.txt").unwrap();
+
+    let Command::Simple {redirections, ..} = &script.statements[0].and_or.first.commands[0] else {
+        panic!("Expected a simple command");
+    };
+
+    assert_eq!(redirections.iter().map(|redirection| (redirection.fd, redirection.kind)).collect::<Vec<_>>(), vec![
+        (1, RedirectionKind::Output),
+        (2, RedirectionKind::Duplicate),
+        (0, RedirectionKind::Input),
+        (1, RedirectionKind::Output),
+        (2, RedirectionKind::Duplicate),
+    ]);
+}
+
+#[test]
+fn should_parse_subshells_and_groups() {
+    let script
+        = parse_script("(cd foo && ls) > out; { echo a; echo b; }").unwrap();
+
+    assert!(matches!(&script.statements[0].and_or.first.commands[0], Command::Subshell {script, redirections} if script.statements.len() == 1 && redirections.len() == 1));
+    assert!(matches!(&script.statements[1].and_or.first.commands[0], Command::Group {script, ..} if script.statements.len() == 2));
+}
+
+#[test]
+fn should_parse_substitutions() {
+    let script
+        = parse_script("echo $(git rev-parse \"$(echo HEAD)\") `pwd` ${FOO:-bar}").unwrap();
+
+    let args
+        = &simple_args(&script)[0];
+
+    assert!(matches!(&args[1].parts[0], WordPart::Substitution {quoted: false, ..}));
+    assert!(matches!(&args[2].parts[0], WordPart::Substitution {quoted: false, ..}));
+    assert!(matches!(&args[3].parts[0], WordPart::Variable {name, fallback: Some(_), ..} if name == "FOO"));
+}
+
+#[test]
+fn should_ignore_comments() {
+    let script
+        = parse_script("# leading comment\necho a # trailing comment\necho b#c").unwrap();
+
+    assert_eq!(simple_args(&script), vec![
+        vec![word(vec![text("echo", false)]), word(vec![text("a", false)])],
+        vec![word(vec![text("echo", false)]), word(vec![text("b#c", false)])],
+    ]);
+}
+
+#[test]
+fn should_reject_invalid_scripts() {
+    assert!(parse_script("echo 'foo").is_err());
+    assert!(parse_script("echo \"foo").is_err());
+    assert!(parse_script("(echo foo").is_err());
+    assert!(parse_script("echo foo |").is_err());
+    assert!(parse_script("&& echo foo").is_err());
+    assert!(parse_script("echo $((1 + 2))").is_err());
+}
@@ -0,0 +1,432 @@
+use std::{collections::{BTreeMap, BTreeSet}, fs::{FileType, Metadata}, io::Write, os::unix::process::ExitStatusExt, process::{ExitStatus, Output}, time::SystemTime};
+
+use zpm_primitives::Locator;
+use zpm_utils::{CollectHash, Glob, Hash64, IoResultExt, Path, ToFileString};
+
+use crate::{
+    build::ArtifactFinder,
+    diff_finder::{CacheEntry, CacheState, DiffController, DiffFinder},
+    error::Error,
+    project::{Project, Workspace},
+};
+
+const STDOUT_LOG: &str = "stdout.log";
+const STDERR_LOG: &str = "stderr.log";
+const OUTPUTS_FOLDER: &str = "outputs";
+
+pub struct TaskInputFinder;
+
+impl DiffController for TaskInputFinder {
+    type Data = Hash64;
+
+    fn is_relevant_entry(file_name: &str, file_type: &FileType) -> bool {
+        if file_type.is_dir() {
+            return file_name != ".git" && file_name != ".yarn" && file_name != "node_modules";
+        }
+
+        file_type.is_file()
+    }
+
+    fn get_file_data(path: &Path, _metadata: &Metadata) -> Result<Self::Data, Error> {
+        Ok(Hash64::from_data(path.fs_read_prealloc()?))
+    }
+}
+
+/// A script invocation whose results can be stored in the task cache.
+pub struct CachedTask {
+    pub key: Hash64,
+    pub entry_path: Path,
+    outputs: Vec<Glob>,
+}
+
+/**
+ * The TaskCache stores the outputs and logs of the scripts whose inputs have
+ * been declared through the `scriptsMeta` field of their workspace. The cache
+ * key covers the content of the input files (tracked through a DiffFinder, so
+ * that only the files whose mtime changed are hashed again), the script itself,
+ * the declared environment variables, and the resolved dependency closure of
+ * the workspace, including the content of the workspaces it depends on.
+ */
+pub struct TaskCache<'a> {
+    project: &'a Project,
+    cache_path: Path,
+}
+
+impl<'a> TaskCache<'a> {
+    pub fn new(project: &'a Project) -> Self {
+        let cache_path
+            = project.project_cwd.with_join_str(".yarn/ignore/tasks");
+
+        Self {
+            project,
+            cache_path,
+        }
+    }
+
+    /// Returns `None` when the script doesn't declare its inputs, or when some
+    /// of them couldn't be read; such scripts must always run.
+    pub fn prepare(&self, locator: &Locator, script_name: &str, script: &str, args: &[String]) -> Result<Option<CachedTask>, Error> {
+        let Some(workspace) = self.project.try_workspace_by_locator(locator)? else {
+            return Ok(None);
+        };
+
+        let Some(meta) = workspace.manifest.scripts_meta.get(script_name) else {
+            return Ok(None);
+        };
+
+        if meta.inputs.is_empty() {
+            return Ok(None);
+        }
+
+        let inputs
+            = project_globs(workspace, &meta.inputs)?;
+        let outputs
+            = project_globs(workspace, &meta.outputs)?;
+
+        let Some(inputs_hash) = self.hash_inputs(locator, script_name, &inputs)? else {
+            return Ok(None);
+        };
+
+        let Some(dependencies_hash) = self.hash_dependencies(workspace, locator)? else {
+            return Ok(None);
+        };
+
+        let key = [
+            Hash64::from_data(script),
+            Hash64::from_data(args.join("\0")),
+            Hash64::from_data(meta.outputs.join("\0")),
+            hash_env(&meta.env),
+            inputs_hash,
+            dependencies_hash,
+        ].iter().collect_hash();
+
+        let entry_path
+            = self.cache_path
+                .with_join_str("entries")
+                .with_join_str(key.short());
+
+        Ok(Some(CachedTask {
+            key,
+            entry_path,
+            outputs,
+        }))
+    }
+
+    /// Restores the outputs of a previous successful run and replays its logs;
+    /// returns `None` if the task isn't in the cache.
+    pub fn restore(&self, task: &CachedTask) -> Result<Option<ExitStatus>, Error> {
+        let stdout_path
+            = task.entry_path.with_join_str(STDOUT_LOG);
+
+        let Some(stdout) = stdout_path.fs_read_prealloc().ok_missing()? else {
+            return Ok(None);
+        };
+
+        let stderr
+            = task.entry_path
+                .with_join_str(STDERR_LOG)
+                .fs_read_prealloc()?;
+
+        let outputs_path
+            = task.entry_path.with_join_str(OUTPUTS_FOLDER);
+
+        if outputs_path.fs_exists() {
+            outputs_path.fs_copy(&self.project.project_cwd)?;
+        }
+
+        // The log mtime is what the pruning uses to find the least recently used entries
+        std::fs::File::options()
+            .append(true)
+            .open(stdout_path.to_path_buf())?
+            .set_modified(SystemTime::now())?;
+
+        std::io::stdout().write_all(&stdout)?;
+        std::io::stderr().write_all(&stderr)?;
+
+        Ok(Some(ExitStatus::from_raw(0)))
+    }
+
+    pub fn store(&self, task: &CachedTask, output: &Output) -> Result<(), Error> {
+        let temp_dir
+            = Path::temp_dir()?;
+
+        temp_dir
+            .with_join_str(STDOUT_LOG)
+            .fs_write(&output.stdout)?;
+
+        temp_dir
+            .with_join_str(STDERR_LOG)
+            .fs_write(&output.stderr)?;
+
+        if !task.outputs.is_empty() {
+            let roots = task.outputs.iter()
+                .map(|glob| glob.prefix())
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let mut artifact_finder
+                = DiffFinder::<ArtifactFinder>::new(self.project.project_cwd.clone(), roots, Default::default());
+
+            let (_, output_files)
+                = artifact_finder.rsync()?;
+
+            for rel_path in output_files {
+                if !task.outputs.iter().any(|glob| glob.is_match(rel_path.as_str())) {
+                    continue;
+                }
+
+                let target_path
+                    = temp_dir
+                        .with_join_str(OUTPUTS_FOLDER)
+                        .with_join(&rel_path);
+
+                target_path
+                    .fs_create_parent()?;
+
+                self.project.project_cwd
+                    .with_join(&rel_path)
+                    .fs_copy_file(&target_path)?;
+            }
+        }
+
+        task.entry_path
+            .fs_create_parent()?;
+
+        temp_dir
+            .fs_concurrent_move(&task.entry_path)?;
+
+        temp_dir
+            .fs_rm()
+            .ok_missing()?;
+
+        self.prune()
+    }
+
+    fn hash_inputs(&self, locator: &Locator, script_name: &str, inputs: &[Glob]) -> Result<Option<Hash64>, Error> {
+        let roots = inputs.iter()
+            .map(|glob| glob.prefix())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let state_key
+            = format!("{}/{}", locator.to_file_string(), script_name);
+
+        self.hash_files(&state_key, roots, |rel_path| inputs.iter().any(|glob| glob.is_match(rel_path.as_str())))
+    }
+
+    /// Hashes the files found under the given roots that match the filter. The
+    /// DiffFinder state is persisted under the given key, so that only the
+    /// files modified since the last call are read again. Returns `None` if
+    /// some of the files couldn't be read.
+    fn hash_files(&self, state_key: &str, roots: Vec<Path>, filter: impl Fn(&Path) -> bool) -> Result<Option<Hash64>, Error> {
+        let save_state_path
+            = self.cache_path
+                .with_join_str("inputs")
+                .with_join_str(Hash64::from_data(state_key).short());
+
+        let save_state
+            = save_state_path
+                .fs_read_prealloc()
+                .ok()
+                .and_then(|save_data| CacheState::from_slice(&save_data).ok())
+                .unwrap_or_default();
+
+        let mut input_finder
+            = DiffFinder::<TaskInputFinder>::new(self.project.project_cwd.clone(), roots, save_state);
+
+        let (has_changed, _)
+            = input_finder.rsync()?;
+
+        if has_changed {
+            let data
+                = input_finder.state.to_vec()?;
+
+            save_state_path
+                .fs_create_parent()?
+                .fs_write(&data)?;
+        }
+
+        let mut hashes
+            = vec![];
+
+        for (rel_path, entry) in &input_finder.state.cache {
+            if !filter(rel_path) {
+                continue;
+            }
+
+            match entry {
+                CacheEntry::File(_, hash) => {
+                    hashes.push(Hash64::from_data(rel_path.as_str()));
+                    hashes.push(hash.clone());
+                },
+
+                CacheEntry::Error(_) => {
+                    return Ok(None);
+                },
+
+                CacheEntry::Directory(_) => {
+                },
+            }
+        }
+
+        Ok(Some(hashes.iter().collect_hash()))
+    }
+
+    /// Hashes the dependency closure of the workspace. The workspaces found in
+    /// the closure aren't versioned like the other packages, so the content
+    /// of their files is hashed as well; returns `None` if it couldn't be read.
+    fn hash_dependencies(&self, workspace: &Workspace, locator: &Locator) -> Result<Option<Hash64>, Error> {
+        let install_state
+            = self.project.install_state.as_ref()
+                .ok_or(Error::InstallStateNotFound)?;
+
+        let mut queue
+            = vec![locator.clone()];
+
+        let mut seen
+            = BTreeSet::new();
+
+        while let Some(locator) = queue.pop() {
+            if !seen.insert(locator.clone()) {
+                continue;
+            }
+
+            let Some(resolution) = install_state.resolution_tree.locator_resolutions.get(&locator) else {
+                continue;
+            };
+
+            for descriptor in resolution.dependencies.values() {
+                if let Some(dependency) = install_state.resolution_tree.descriptor_to_locator.get(descriptor) {
+                    queue.push(dependency.clone());
+                }
+            }
+        }
+
+        let mut hashes = seen.iter()
+            .map(Hash64::from_string)
+            .collect::<Vec<_>>();
+
+        let mut dependency_workspaces
+            = BTreeMap::new();
+
+        for locator in &seen {
+            if let Some(dependency_workspace) = self.project.try_workspace_by_locator(&locator.physical_locator())? {
+                dependency_workspaces.insert(dependency_workspace.rel_path.clone(), dependency_workspace);
+            }
+        }
+
+        for dependency_workspace in dependency_workspaces.into_values() {
+            // The files of the workspace running the script are covered by its declared inputs
+            if dependency_workspace.rel_path == workspace.rel_path {
+                continue;
+            }
+
+            let state_key
+                = format!("workspace/{}", dependency_workspace.rel_path.as_str());
+
+            let Some(content_hash) = self.hash_files(&state_key, vec![dependency_workspace.rel_path.clone()], |_| true)? else {
+                return Ok(None);
+            };
+
+            hashes.push(content_hash);
+        }
+
+        Ok(Some(hashes.iter().collect_hash()))
+    }
+
+    /// Removes the least recently used entries until the cache fits within the
+    /// `taskCacheMaxSize` setting.
+    fn prune(&self) -> Result<(), Error> {
+        let max_size
+            = self.project.config.settings.task_cache_max_size.value;
+
+        let mut entries
+            = vec![];
+
+        for entry in self.cache_path.with_join_str("entries").fs_read_dir()? {
+            let entry_path
+                = Path::try_from(entry?.path())?;
+
+            let last_used
+                = entry_path
+                    .with_join_str(STDOUT_LOG)
+                    .fs_metadata()
+                    .ok()
+                    .and_then(|metadata| metadata.modified().ok())
+                    .unwrap_or(SystemTime::UNIX_EPOCH);
+
+            let size
+                = directory_size(&entry_path)?;
+
+            entries.push((last_used, size, entry_path));
+        }
+
+        let mut total_size
+            = entries.iter().map(|(_, size, _)| size).sum::<u64>();
+
+        entries.sort();
+
+        for (_, size, entry_path) in entries {
+            if total_size <= max_size {
+                break;
+            }
+
+            entry_path
+                .fs_rm()
+                .ok_missing()?;
+
+            total_size -= size;
+        }
+
+        Ok(())
+    }
+}
+
+/// Hashes the names and values of the environment variables declared by the
+/// script; unset variables are distinguished from empty ones.
+fn hash_env(names: &[String]) -> Hash64 {
+    let hashes = names.iter()
+        .map(|name| {
+            let value = std::env::var(name)
+                .map_or_else(|_| "\0unset".to_string(), |value| format!("={}", value));
+
+            Hash64::from_data(format!("{}{}", name, value))
+        })
+        .collect::<Vec<_>>();
+
+    hashes.iter().collect_hash()
+}
+
+pub(crate) fn project_globs(workspace: &Workspace, patterns: &[String]) -> Result<Vec<Glob>, Error> {
+    patterns.iter()
+        .map(|pattern| {
+            let pattern
+                = pattern.trim_start_matches("./");
+
+            let project_pattern = match workspace.rel_path.is_empty() {
+                true => pattern.to_string(),
+                false => format!("{}/{}", workspace.rel_path.as_str(), pattern),
+            };
+
+            Glob::parse(project_pattern)
+                .map_err(|_| Error::InvalidGlob(pattern.to_string()))
+        })
+        .collect()
+}
+
+fn directory_size(path: &Path) -> Result<u64, Error> {
+    let mut size
+        = 0;
+
+    for entry in path.fs_read_dir()? {
+        let entry
+            = entry?;
+        let metadata
+            = entry.metadata()?;
+
+        size += match metadata.is_dir() {
+            true => directory_size(&Path::try_from(entry.path())?)?,
+            false => metadata.len(),
+        };
+    }
+
+    Ok(size)
+}
@@ -9,6 +9,7 @@ interface Options {
   cwd: PortablePath;
   env?: Record<string, string | undefined>;
   stdin?: string;
+  signal?: AbortSignal;
 }
 
 export type ExecResult = {
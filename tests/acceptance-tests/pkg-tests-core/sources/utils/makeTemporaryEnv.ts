@@ -14,7 +14,7 @@ const mte = generatePkgDriver({
   async runDriver(
     path,
     [command, ...args],
-    {cwd, execArgv = [], projectFolder, registryUrl, env, stdin, ...config},
+    {cwd, execArgv = [], projectFolder, registryUrl, env, stdin, signal, ...config},
   ) {
     const rcEnv: Record<string, any> = {};
     for (const [key, value] of Object.entries(config))
@@ -37,6 +37,7 @@ const mte = generatePkgDriver({
     const res = await execFile(yarnBinaryArgs[0]!, [...execArgv, ...yarnBinaryArgs.slice(1), ...cwdArgs, command, ...args], {
       cwd: cwd || path,
       stdin,
+      signal,
       env: {
         [`HOME`]: nativeHomePath,
         [`USERPROFILE`]: nativeHomePath,
@@ -58,6 +59,9 @@ const mte = generatePkgDriver({
         [`FORCE_COLOR`]: `0`,
         // Otherwise the output wouldn't be the same on CI vs non-CI
         [`YARN_ENABLE_INLINE_BUILDS`]: `false`,
+        // Otherwise every test relying on build scripts would need to approve them first; the
+        // tests covering the default behavior unset it through their env
+        [`YARN_ENABLE_BUILD_APPROVALS`]: `false`,
         // Otherwise we would more often test the fallback rather than the real logic
         [`YARN_PNP_FALLBACK_MODE`]: `none`,
         // Otherwise tests fail on systems where this is globally set to true
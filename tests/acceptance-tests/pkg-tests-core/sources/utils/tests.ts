@@ -58,6 +58,7 @@ interface RunDriverOptions extends Record<string, any> {
   registryUrl: string;
   env?: Record<string, string | undefined>;
   stdin?: string;
+  signal?: AbortSignal;
 }
 
 export type PackageRunDriver = (
@@ -0,0 +1,90 @@
+import {ppath, xfs} from '@yarnpkg/fslib';
+
+describe(`Commands`, () => {
+  describe(`approve-builds`, () => {
+    test(
+      `it should block the builds that haven't been approved`,
+      makeTemporaryEnv({
+        dependencies: {
+          [`no-deps-scripted`]: `1.0.0`,
+        },
+      }, {
+        enableBuildApprovals: true,
+      }, async ({path, run, source}) => {
+        const {stdout} = await run(`install`);
+
+        expect(stdout).toContain(`no-deps-scripted@npm:1.0.0 won't be built, as its build scripts haven't been approved`);
+        await expect(source(`require('no-deps-scripted/log')`)).resolves.toEqual([]);
+      }),
+    );
+
+    test(
+      `it should build the approved packages`,
+      makeTemporaryEnv({
+        dependencies: {
+          [`no-deps-scripted`]: `1.0.0`,
+        },
+      }, {
+        enableBuildApprovals: true,
+      }, async ({path, run, source}) => {
+        await run(`install`);
+        await run(`approve-builds`, `--all`);
+
+        const lockfile = await xfs.readJsonPromise(ppath.join(path, `yarn.lock`));
+        expect(Object.keys(lockfile.approvedBuilds)).toEqual([`no-deps-scripted@npm:1.0.0`]);
+
+        await expect(source(`require('no-deps-scripted/log')`)).resolves.toEqual([
+          `preinstall`,
+          `install`,
+          `postinstall`,
+        ]);
+
+        const {stdout} = await run(`install`);
+        expect(stdout).not.toContain(`won't be built`);
+      }),
+    );
+
+    test(
+      `it should block the builds again when their scripts change`,
+      makeTemporaryEnv({
+        dependencies: {
+          [`no-deps-scripted`]: `1.0.0`,
+        },
+      }, {
+        enableBuildApprovals: true,
+      }, async ({path, run, source}) => {
+        await run(`install`);
+        await run(`approve-builds`, `--all`);
+
+        // Simulates a change of the build scripts since their approval
+        const lockfile = await xfs.readJsonPromise(ppath.join(path, `yarn.lock`));
+        const approvedHash: string = lockfile.approvedBuilds[`no-deps-scripted@npm:1.0.0`];
+        lockfile.approvedBuilds[`no-deps-scripted@npm:1.0.0`] = `${approvedHash.slice(0, -1)}${approvedHash.endsWith(`0`) ? `1` : `0`}`;
+        await xfs.writeJsonPromise(ppath.join(path, `yarn.lock`), lockfile);
+
+        const {stdout} = await run(`install`);
+        expect(stdout).toContain(`no-deps-scripted@npm:1.0.0 won't be built, as its build scripts changed since they got approved`);
+      }),
+    );
+
+    test(
+      `it should require --all when not running in a terminal`,
+      makeTemporaryEnv({
+        dependencies: {
+          [`no-deps-scripted`]: `1.0.0`,
+        },
+      }, {
+        enableBuildApprovals: true,
+      }, async ({path, run, source}) => {
+        await run(`install`);
+
+        await expect(run(`approve-builds`)).rejects.toMatchObject({
+          stdout: expect.stringContaining(`pass --all to approve them without prompting`),
+        });
+
+        const lockfile = await xfs.readJsonPromise(ppath.join(path, `yarn.lock`));
+        expect(lockfile.approvedBuilds).toBeUndefined();
+      }),
+    );
+  });
+});
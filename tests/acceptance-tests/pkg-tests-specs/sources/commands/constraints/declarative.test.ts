@@ -0,0 +1,155 @@
+import {Filename, PortablePath, ppath, xfs} from '@yarnpkg/fslib';
+
+async function setupWorkspaces(path: PortablePath, workspaces: Record<string, Record<string, any>>) {
+  for (const [name, manifest] of Object.entries(workspaces)) {
+    const workspacePath = ppath.join(path, `packages`, name);
+
+    await xfs.mkdirpPromise(workspacePath);
+    await xfs.writeJsonPromise(ppath.join(workspacePath, Filename.manifest), {name, ...manifest});
+  }
+}
+
+describe(`Commands`, () => {
+  describe(`constraints (yarn.constraints.yml)`, () => {
+    test(
+      `it should report the inconsistent dependencies`,
+      makeTemporaryEnv({
+        private: true,
+        workspaces: [`packages/*`],
+      }, async ({path, run, source}) => {
+        await setupWorkspaces(path, {
+          [`a`]: {dependencies: {[`no-deps`]: `1.0.0`}},
+          [`b`]: {dependencies: {[`no-deps`]: `1.0.0`}},
+          [`c`]: {dependencies: {[`no-deps`]: `2.0.0`}},
+        });
+
+        await xfs.writeFilePromise(ppath.join(path, `yarn.constraints.yml`), `enforceConsistentDependencies: true\n`);
+
+        await run(`install`);
+
+        const {stdout} = await run(`constraints`, `--json`).catch(error => error);
+        const output = JSON.parse(stdout);
+
+        expect(output.allWorkspaceErrors).toEqual([
+          [`packages/c`, [{
+            type: `invalidField`,
+            fieldPath: `dependencies["no-deps"]`,
+            expected: `1.0.0`,
+            currentValue: `2.0.0`,
+          }]],
+        ]);
+      }),
+    );
+
+    test(
+      `it should fix the inconsistent dependencies using the most common range`,
+      makeTemporaryEnv({
+        private: true,
+        workspaces: [`packages/*`],
+      }, async ({path, run, source}) => {
+        await setupWorkspaces(path, {
+          [`a`]: {dependencies: {[`no-deps`]: `1.0.0`}},
+          [`b`]: {dependencies: {[`no-deps`]: `1.0.0`}},
+          [`c`]: {devDependencies: {[`no-deps`]: `2.0.0`}},
+        });
+
+        await xfs.writeFilePromise(ppath.join(path, `yarn.constraints.yml`), `enforceConsistentDependencies: true\n`);
+
+        await run(`install`);
+        await run(`constraints`, `--fix`);
+
+        await expect(xfs.readJsonPromise(ppath.join(path, `packages/c`, Filename.manifest))).resolves.toMatchObject({
+          devDependencies: {
+            [`no-deps`]: `1.0.0`,
+          },
+        });
+
+        await run(`constraints`);
+      }),
+    );
+
+    test(
+      `it should enforce fields and ban dependencies`,
+      makeTemporaryEnv({
+        private: true,
+        workspaces: [`packages/*`],
+      }, async ({path, run, source}) => {
+        await setupWorkspaces(path, {
+          [`a`]: {license: `BSD-2-Clause`, dependencies: {[`no-deps`]: `1.0.0`}},
+        });
+
+        await xfs.writeFilePromise(ppath.join(path, `yarn.constraints.yml`), [
+          `enforcedFields:`,
+          `  - field: license`,
+          `    value: MIT`,
+          `bannedDependencies:`,
+          `  - ident: no-deps`,
+          `    workspaces: ["a"]`,
+          ``,
+        ].join(`\n`));
+
+        await run(`install`);
+        await run(`constraints`, `--fix`);
+
+        const manifest = await xfs.readJsonPromise(ppath.join(path, `packages/a`, Filename.manifest));
+
+        expect(manifest.license).toEqual(`MIT`);
+        expect(manifest.dependencies?.[`no-deps`]).toBeUndefined();
+
+        await expect(xfs.readJsonPromise(ppath.join(path, Filename.manifest))).resolves.toMatchObject({
+          license: `MIT`,
+        });
+      }),
+    );
+
+    test(
+      `it should report the conflicting rules`,
+      makeTemporaryEnv({
+        private: true,
+        workspaces: [`packages/*`],
+      }, async ({path, run, source}) => {
+        await setupWorkspaces(path, {
+          [`a`]: {dependencies: {[`no-deps`]: `1.0.0`}},
+        });
+
+        await xfs.writeFilePromise(ppath.join(path, `yarn.constraints.yml`), [
+          `enforcedFields:`,
+          `  - field: 'dependencies["no-deps"]'`,
+          `    value: 2.0.0`,
+          `    workspaces: ["a"]`,
+          `bannedDependencies:`,
+          `  - ident: no-deps`,
+          ``,
+        ].join(`\n`));
+
+        await run(`install`);
+
+        const {stdout} = await run(`constraints`, `--json`).catch(error => error);
+        const output = JSON.parse(stdout);
+
+        expect(output.allWorkspaceErrors).toEqual([
+          [`packages/a`, [{
+            type: `conflictingValues`,
+            fieldPath: `dependencies["no-deps"]`,
+            setValues: [[`2.0.0`, {callers: [expect.objectContaining({file: `yarn.constraints.yml`, methodName: `enforcedFields`})]}]],
+            unsetValues: {callers: [expect.objectContaining({file: `yarn.constraints.yml`, methodName: `bannedDependencies`})]},
+          }]],
+        ]);
+      }),
+    );
+
+    test(
+      `it should refuse to run when a JavaScript configuration also exists`,
+      makeTemporaryEnv({}, async ({path, run, source}) => {
+        await xfs.writeFilePromise(ppath.join(path, `yarn.constraints.yml`), `enforceConsistentDependencies: true\n`);
+        await xfs.writeFilePromise(ppath.join(path, `yarn.config.cjs`), `exports.constraints = () => {};\n`);
+
+        await run(`install`);
+
+        await expect(run(`constraints`)).rejects.toMatchObject({
+          stdout: expect.stringContaining(`merge them into a single file`),
+        });
+      }),
+    );
+  });
+});
@@ -952,5 +952,31 @@ module.exports = {
         });
       }),
     );
+
+    test(
+      `it should block the build scripts of the dependencies by default`,
+      makeTemporaryEnv({
+        dependencies: {
+          [`no-deps-scripted`]: `1.0.0`,
+        },
+        scripts: {
+          postinstall: `echo workspace-built > workspace.log`,
+        },
+      }, async ({path, run, source}) => {
+        // Build approvals are disabled by default in the test environment
+        const env = {YARN_ENABLE_BUILD_APPROVALS: undefined};
+
+        const {stdout} = await run(`install`, {env});
+
+        expect(stdout).toContain(`no-deps-scripted@npm:1.0.0 won't be built, as its build scripts haven't been approved`);
+        await expect(source(`require('no-deps-scripted/log')`, {env})).resolves.toEqual([]);
+
+        // The workspaces are part of the project, so their scripts don't need approvals
+        await expect(xfs.readFilePromise(ppath.join(path, `workspace.log`), `utf8`)).resolves.toEqual(`workspace-built\n`);
+
+        const lockfile = await xfs.readJsonPromise(ppath.join(path, Filename.lockfile));
+        expect(lockfile.approvedBuilds ?? {}).toEqual({});
+      }),
+    );
   });
 });
@@ -0,0 +1,90 @@
+import {parseSyml} from '@yarnpkg/parsers';
+
+describe(`Commands`, () => {
+  describe(`lockfile export`, () => {
+    test(
+      `it should export npm lockfiles with plain ranges, aliases, and nested conflicts`,
+      makeTemporaryEnv({
+        dependencies: {
+          [`provides-peer-deps-1-0-0`]: `1.0.0`,
+          [`no-deps`]: `2.0.0`,
+          [`my-alias`]: `npm:no-deps@1.0.0`,
+        },
+      }, async ({path, run, source}) => {
+        await run(`install`);
+
+        const {stdout} = await run(`lockfile`, `export`, `--format`, `npm`);
+        const {packages} = JSON.parse(stdout);
+
+        expect(packages[``].dependencies).toEqual({
+          [`provides-peer-deps-1-0-0`]: `1.0.0`,
+          [`no-deps`]: `2.0.0`,
+          [`my-alias`]: `npm:no-deps@1.0.0`,
+        });
+
+        expect(packages[`node_modules/my-alias`]).toMatchObject({
+          name: `no-deps`,
+          version: `1.0.0`,
+        });
+
+        expect(packages[`node_modules/no-deps`]).toMatchObject({
+          version: `2.0.0`,
+        });
+
+        expect(packages[`node_modules/provides-peer-deps-1-0-0`].dependencies).toEqual({
+          [`no-deps`]: `1.0.0`,
+          [`peer-deps`]: `1.0.0`,
+        });
+
+        // Conflicts with the top-level no-deps, so it must be nested
+        expect(packages[`node_modules/provides-peer-deps-1-0-0/node_modules/no-deps`]).toMatchObject({
+          version: `1.0.0`,
+        });
+
+        expect(packages[`node_modules/peer-deps`]).toMatchObject({
+          version: `1.0.0`,
+          peerDependencies: {
+            [`no-deps`]: `*`,
+          },
+        });
+      }),
+    );
+
+    test(
+      `it should export pnpm lockfiles with plain specifiers and peer-suffixed snapshots`,
+      makeTemporaryEnv({
+        dependencies: {
+          [`provides-peer-deps-1-0-0`]: `1.0.0`,
+          [`my-alias`]: `npm:no-deps@1.0.0`,
+        },
+      }, async ({path, run, source}) => {
+        await run(`install`);
+
+        const {stdout} = await run(`lockfile`, `export`, `--format`, `pnpm`);
+        const lockfile = parseSyml(stdout);
+
+        expect(lockfile.importers[`.`].dependencies).toEqual({
+          [`provides-peer-deps-1-0-0`]: {
+            specifier: `1.0.0`,
+            version: `1.0.0`,
+          },
+          [`my-alias`]: {
+            specifier: `npm:no-deps@1.0.0`,
+            version: `no-deps@1.0.0`,
+          },
+        });
+
+        expect(lockfile.packages[`peer-deps@1.0.0`].peerDependencies).toEqual({
+          [`no-deps`]: `*`,
+        });
+
+        expect(lockfile.snapshots[`provides-peer-deps-1-0-0@1.0.0`].dependencies).toEqual({
+          [`no-deps`]: `1.0.0`,
+          [`peer-deps`]: `1.0.0(no-deps@1.0.0)`,
+        });
+
+        expect(lockfile.snapshots).toHaveProperty([`peer-deps@1.0.0(no-deps@1.0.0)`]);
+      }),
+    );
+  });
+});
@@ -1,6 +1,44 @@
 import {Filename, ppath, xfs} from '@yarnpkg/fslib';
+import cp                     from 'child_process';
 import {tests}                from 'pkg-tests-core';
 
+async function startMockProxy(remoteUrl: string) {
+  const yarnBinary = process.env.TEST_BINARY
+    ?? require.resolve(`${__dirname}/../../../../../../target/release/yarn-bin`);
+
+  const yarnBinaryArgs = yarnBinary.match(/\.[cm]?js$/)
+    ? [process.execPath, yarnBinary]
+    : [yarnBinary];
+
+  const proxy = cp.spawn(yarnBinaryArgs[0]!, [...yarnBinaryArgs.slice(1), `debug`, `mock-proxy`, remoteUrl], {
+    env: {...process.env, [`YARN_ENABLE_TELEMETRY`]: `0`},
+  });
+
+  let output = ``;
+  proxy.stdout.on(`data`, chunk => {
+    output += chunk.toString();
+  });
+
+  const url = await new Promise<string>((resolve, reject) => {
+    proxy.stdout.on(`data`, () => {
+      const match = output.match(/listening on (http:\/\/\S+)/);
+      if (match) {
+        resolve(match[1]);
+      }
+    });
+
+    proxy.on(`exit`, code => {
+      reject(new Error(`The mock proxy exited early (${code}): ${output}`));
+    });
+  });
+
+  return {
+    url,
+    getOutput: () => output,
+    stop: () => proxy.kill(),
+  };
+}
+
 describe(`Commands`, () => {
   describe(`npm audit`, () => {
     test(
@@ -259,6 +297,30 @@ describe(`Commands`, () => {
       }),
     );
 
+    test(
+      `it should work through the mock proxy`,
+      makeTemporaryEnv({
+        dependencies: {
+          [`vulnerable`]: `1.0.0`,
+        },
+      }, async ({path, run, source}) => {
+        await run(`install`);
+
+        const proxy = await startMockProxy(await tests.startPackageServer());
+
+        try {
+          await expect(run(`npm`, `audit`, `--json`, {registryUrl: proxy.url})).rejects.toThrow(/"https:\/\/example\.com\/advisories\/1"/);
+          await expect(run(`npm`, `audit`, `--json`, {registryUrl: proxy.url})).rejects.toThrow(/"https:\/\/example\.com\/advisories\/1"/);
+
+          // The second audit must have been answered from the proxy cache
+          expect(proxy.getOutput()).toMatch(/\[FETCH\] POST \S*\/advisories\/bulk/);
+          expect(proxy.getOutput()).toMatch(/\[CACHE\] POST \S*\/advisories\/bulk/);
+        } finally {
+          proxy.stop();
+        }
+      }),
+    );
+
     test(
       `it shouldn't report deprecations when they're set to empty strings`,
       makeTemporaryEnv({
@@ -0,0 +1,63 @@
+import {MOCK_AUTH_TOKEN, makePackument, mockRegistryServer} from './registry.utility';
+
+describe(`Commands`, () => {
+  describe(`npm deprecate`, () => {
+    test(
+      `it should deprecate the versions matching the range`,
+      makeTemporaryEnv({}, async ({path, run, source}) => {
+        await mockRegistryServer({
+          [`no-deps`]: makePackument(`no-deps`, [`1.0.0`, `1.1.0`, `2.0.0`]),
+        }, {}, async registry => {
+          await run(`npm`, `deprecate`, `no-deps@^1.0.0`, `Use 2.x instead`, {
+            registryUrl: registry.url,
+            npmAuthToken: MOCK_AUTH_TOKEN,
+          });
+
+          const {versions} = registry.packuments.get(`no-deps`);
+
+          expect(versions[`1.0.0`].deprecated).toEqual(`Use 2.x instead`);
+          expect(versions[`1.1.0`].deprecated).toEqual(`Use 2.x instead`);
+          expect(versions[`2.0.0`]).not.toHaveProperty(`deprecated`);
+        });
+      }),
+    );
+
+    test(
+      `it should only list the versions that would be deprecated when using --dry-run`,
+      makeTemporaryEnv({}, async ({path, run, source}) => {
+        await mockRegistryServer({
+          [`no-deps`]: makePackument(`no-deps`, [`1.0.0`, `1.1.0`, `2.0.0`]),
+        }, {}, async registry => {
+          const {stdout} = await run(`npm`, `deprecate`, `no-deps@^1.0.0`, `Use 2.x instead`, `--dry-run`, {
+            registryUrl: registry.url,
+            npmAuthToken: MOCK_AUTH_TOKEN,
+          });
+
+          expect(stdout).toContain(`Would deprecate the following versions of no-deps:`);
+          expect(stdout).toContain(`- 1.0.0`);
+          expect(stdout).toContain(`- 1.1.0`);
+          expect(stdout).not.toContain(`- 2.0.0`);
+
+          expect(registry.requests.filter(request => request.method !== `GET`)).toEqual([]);
+          expect(registry.packuments.get(`no-deps`).versions[`1.0.0`]).not.toHaveProperty(`deprecated`);
+        });
+      }),
+    );
+
+    test(
+      `it should report ranges that don't match any version`,
+      makeTemporaryEnv({}, async ({path, run, source}) => {
+        await mockRegistryServer({
+          [`no-deps`]: makePackument(`no-deps`, [`1.0.0`]),
+        }, {}, async registry => {
+          await expect(run(`npm`, `deprecate`, `no-deps@^3.0.0`, `Gone`, {
+            registryUrl: registry.url,
+            npmAuthToken: MOCK_AUTH_TOKEN,
+          })).rejects.toThrow(/No candidates found for/);
+
+          expect(registry.requests.filter(request => request.method !== `GET`)).toEqual([]);
+        });
+      }),
+    );
+  });
+});
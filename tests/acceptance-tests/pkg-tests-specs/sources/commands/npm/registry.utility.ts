@@ -0,0 +1,169 @@
+import http          from 'http';
+import {AddressInfo} from 'net';
+
+export const MOCK_AUTH_TOKEN = `mock-registry-token`;
+export const MOCK_OTP = `123456`;
+
+export type MockRegistryRequest = {
+  method: string;
+  url: string;
+  otp: string | undefined;
+  body: any;
+};
+
+export type MockRegistry = {
+  url: string;
+  packuments: Map<string, any>;
+  requests: Array<MockRegistryRequest>;
+};
+
+export function makePackument(name: string, versions: Array<string>, distTags: Record<string, string> = {}) {
+  return {
+    name,
+    versions: Object.fromEntries(versions.map(version => [version, {name, version}])),
+    time: Object.fromEntries(versions.map(version => [version, new Date(0).toISOString()])),
+    [`dist-tags`]: {
+      latest: versions[versions.length - 1],
+      ...distTags,
+    },
+  };
+}
+
+/**
+ * Starts a stateful registry that only implements the routes used by the
+ * commands writing package metadata (unpublish, deprecate, dist-tags). Unlike
+ * the shared package server, it keeps track of the documents it serves so that
+ * the tests can check what got modified, and requires the `npm-otp` header on
+ * writes when `otp` is set.
+ */
+export async function mockRegistryServer(packuments: Record<string, any>, {otp = false}: {otp?: boolean}, asyncFn: (registry: MockRegistry) => Promise<void>) {
+  const state = new Map<string, any>(Object.entries(packuments).map(([name, packument]) => [name, {_rev: `1-0`, ...packument}]));
+  const requests: Array<MockRegistryRequest> = [];
+
+  const bumpRevision = (name: string) => {
+    const packument = state.get(name);
+    const [count] = packument._rev.split(`-`);
+
+    packument._rev = `${Number(count) + 1}-0`;
+  };
+
+  const send = (res: http.ServerResponse, statusCode: number, data: any) => {
+    res.writeHead(statusCode, {[`Content-Type`]: `application/json`});
+    res.end(JSON.stringify(data));
+  };
+
+  const handle = (req: http.IncomingMessage, res: http.ServerResponse, body: any) => {
+    const url = decodeURIComponent(req.url!.replace(/\?.*$/, ``));
+    const isWrite = req.method !== `GET`;
+
+    if (isWrite && req.headers.authorization !== `Bearer ${MOCK_AUTH_TOKEN}`)
+      return send(res, 401, {error: `Authentication required`});
+
+    if (isWrite && otp && req.headers[`npm-otp`] !== MOCK_OTP) {
+      res.writeHead(401, {[`www-authenticate`]: `OTP`});
+      return res.end();
+    }
+
+    let match: RegExpMatchArray | null;
+
+    if (url === `/-/whoami`)
+      return send(res, 200, {username: `mock-user`});
+
+    if ((match = url.match(/^\/-\/package\/((?:@[^/]+\/)?[^@/]+)\/dist-tags(?:\/([^/]+))?$/))) {
+      const [, name, tag] = match;
+
+      const packument = state.get(name!);
+      if (!packument)
+        return send(res, 404, {error: `Not found`});
+
+      const distTags = packument[`dist-tags`];
+
+      if (req.method === `GET` && !tag)
+        return send(res, 200, distTags);
+
+      if (req.method === `PUT` && tag) {
+        distTags[tag] = body;
+        bumpRevision(name!);
+        return send(res, 200, {ok: true});
+      }
+
+      if (req.method === `DELETE` && tag) {
+        delete distTags[tag];
+        bumpRevision(name!);
+        return send(res, 200, {ok: true});
+      }
+    }
+
+    if ((match = url.match(/^\/((?:@[^/]+\/)?[^@/]+)\/-\/[^/]+\.tgz\/-rev\/([^/]+)$/)) && req.method === `DELETE`) {
+      const [, name, rev] = match;
+
+      const packument = state.get(name!);
+      if (!packument)
+        return send(res, 404, {error: `Not found`});
+
+      if (packument._rev !== rev)
+        return send(res, 409, {error: `Document update conflict`});
+
+      bumpRevision(name!);
+      return send(res, 200, {ok: true});
+    }
+
+    if ((match = url.match(/^\/((?:@[^/]+\/)?[^@/]+)(?:\/-rev\/([^/]+))?$/))) {
+      const [, name, rev] = match;
+
+      const packument = state.get(name!);
+      if (!packument)
+        return send(res, 404, {error: `Not found`});
+
+      if (rev && packument._rev !== rev)
+        return send(res, 409, {error: `Document update conflict`});
+
+      if (req.method === `GET`)
+        return send(res, 200, packument);
+
+      if (req.method === `PUT`) {
+        state.set(name!, {...body, _rev: packument._rev});
+        bumpRevision(name!);
+        return send(res, 200, {ok: true});
+      }
+
+      if (req.method === `DELETE` && rev) {
+        state.delete(name!);
+        return send(res, 200, {ok: true});
+      }
+    }
+
+    return send(res, 404, {error: `Invalid route: ${req.method} ${req.url}`});
+  };
+
+  const server = http.createServer((req, res) => {
+    let rawData = ``;
+
+    req.on(`data`, chunk => rawData += chunk);
+    req.on(`end`, () => {
+      const body = rawData.length > 0 ? JSON.parse(rawData) : undefined;
+
+      requests.push({
+        method: req.method!,
+        url: req.url!,
+        otp: req.headers[`npm-otp`] as string | undefined,
+        body,
+      });
+
+      handle(req, res, body);
+    });
+  });
+
+  const url = await new Promise<string>(resolve => {
+    server.listen(() => {
+      const {port} = server.address() as AddressInfo;
+      resolve(`http://localhost:${port}`);
+    });
+  });
+
+  try {
+    await asyncFn({url, packuments: state, requests});
+  } finally {
+    server.close();
+  }
+}
@@ -0,0 +1,185 @@
+import {MOCK_AUTH_TOKEN, MOCK_OTP, makePackument, mockRegistryServer} from './registry.utility';
+
+describe(`Commands`, () => {
+  describe(`npm tag`, () => {
+    test(
+      `it should list the tags from the publish registry`,
+      makeTemporaryEnv({}, async ({path, run, source}) => {
+        await mockRegistryServer({
+          [`no-deps`]: makePackument(`no-deps`, [`1.0.0`, `2.0.0`], {next: `2.0.0`, legacy: `1.0.0`}),
+        }, {}, async registry => {
+          // The install registry stays the shared package server, which doesn't know about these tags
+          const {stdout} = await run(`npm`, `tag`, `list`, `no-deps`, `--json`, {
+            npmPublishRegistry: registry.url,
+          });
+
+          const tags = stdout.trim().split(`\n`).map(line => JSON.parse(line));
+
+          expect(tags).toEqual([
+            {tag: `latest`, version: `2.0.0`},
+            {tag: `legacy`, version: `1.0.0`},
+            {tag: `next`, version: `2.0.0`},
+          ]);
+
+          expect(registry.requests).toEqual([
+            expect.objectContaining({method: `GET`, url: `/-/package/no-deps/dist-tags`}),
+          ]);
+        });
+      }),
+    );
+
+    test(
+      `it should list the tags of the current workspace`,
+      makeTemporaryEnv({
+        name: `no-deps`,
+      }, async ({path, run, source}) => {
+        await mockRegistryServer({
+          [`no-deps`]: makePackument(`no-deps`, [`1.0.0`]),
+        }, {}, async registry => {
+          await expect(run(`npm`, `tag`, `list`, {
+            registryUrl: registry.url,
+          })).resolves.toMatchObject({
+            stdout: expect.stringContaining(`latest: 1.0.0\n`),
+          });
+        });
+      }),
+    );
+
+    test(
+      `it should add a tag on the publish registry`,
+      makeTemporaryEnv({}, async ({path, run, source}) => {
+        await mockRegistryServer({
+          [`no-deps`]: makePackument(`no-deps`, [`1.0.0`, `2.0.0`]),
+        }, {}, async registry => {
+          await run(`npm`, `tag`, `add`, `no-deps@1.0.0`, `legacy`, {
+            npmPublishRegistry: registry.url,
+            npmAuthToken: MOCK_AUTH_TOKEN,
+          });
+
+          expect(registry.packuments.get(`no-deps`)[`dist-tags`]).toEqual({
+            latest: `2.0.0`,
+            legacy: `1.0.0`,
+          });
+
+          expect(registry.requests).toContainEqual(expect.objectContaining({
+            method: `PUT`,
+            url: `/-/package/no-deps/dist-tags/legacy`,
+            body: `1.0.0`,
+          }));
+        });
+      }),
+    );
+
+    test(
+      `it should skip adding a tag that already points to the version`,
+      makeTemporaryEnv({}, async ({path, run, source}) => {
+        await mockRegistryServer({
+          [`no-deps`]: makePackument(`no-deps`, [`1.0.0`]),
+        }, {}, async registry => {
+          await expect(run(`npm`, `tag`, `add`, `no-deps@1.0.0`, `latest`, {
+            registryUrl: registry.url,
+            npmAuthToken: MOCK_AUTH_TOKEN,
+          })).resolves.toMatchObject({
+            stdout: expect.stringContaining(`Tag latest already points to 1.0.0; skipping.`),
+          });
+
+          expect(registry.requests.filter(request => request.method !== `GET`)).toEqual([]);
+        });
+      }),
+    );
+
+    test(
+      `it should refuse tags that could be mistaken for ranges`,
+      makeTemporaryEnv({}, async ({path, run, source}) => {
+        await mockRegistryServer({
+          [`no-deps`]: makePackument(`no-deps`, [`1.0.0`]),
+        }, {}, async registry => {
+          for (const tag of [`1.0.0`, `^1.0.0`, `>=2`]) {
+            await expect(run(`npm`, `tag`, `add`, `no-deps@1.0.0`, tag, {
+              registryUrl: registry.url,
+              npmAuthToken: MOCK_AUTH_TOKEN,
+            })).rejects.toThrow(/tags cannot be valid semver ranges/);
+          }
+
+          await expect(run(`npm`, `tag`, `add`, `no-deps`, `beta`, {
+            registryUrl: registry.url,
+            npmAuthToken: MOCK_AUTH_TOKEN,
+          })).rejects.toThrow(/Expected a package name followed by a version/);
+
+          expect(registry.requests).toEqual([]);
+        });
+      }),
+    );
+
+    test(
+      `it should remove a tag from the publish registry`,
+      makeTemporaryEnv({}, async ({path, run, source}) => {
+        await mockRegistryServer({
+          [`no-deps`]: makePackument(`no-deps`, [`1.0.0`, `2.0.0`], {next: `2.0.0`}),
+        }, {}, async registry => {
+          await run(`npm`, `tag`, `remove`, `no-deps`, `next`, {
+            npmPublishRegistry: registry.url,
+            npmAuthToken: MOCK_AUTH_TOKEN,
+          });
+
+          expect(registry.packuments.get(`no-deps`)[`dist-tags`]).toEqual({
+            latest: `2.0.0`,
+          });
+
+          await expect(run(`npm`, `tag`, `remove`, `no-deps`, `next`, {
+            npmPublishRegistry: registry.url,
+            npmAuthToken: MOCK_AUTH_TOKEN,
+          })).rejects.toThrow(/Tag not found \(next\)/);
+        });
+      }),
+    );
+
+    test(
+      `it should refuse to remove the latest tag`,
+      makeTemporaryEnv({}, async ({path, run, source}) => {
+        await mockRegistryServer({
+          [`no-deps`]: makePackument(`no-deps`, [`1.0.0`]),
+        }, {}, async registry => {
+          await expect(run(`npm`, `tag`, `remove`, `no-deps`, `latest`, {
+            registryUrl: registry.url,
+            npmAuthToken: MOCK_AUTH_TOKEN,
+          })).rejects.toThrow(/The latest dist-tag cannot be removed/);
+
+          expect(registry.requests).toEqual([]);
+        });
+      }),
+    );
+
+    test(
+      `it should send the OTP token when using --otp`,
+      makeTemporaryEnv({}, async ({path, run, source}) => {
+        await mockRegistryServer({
+          [`no-deps`]: makePackument(`no-deps`, [`1.0.0`, `2.0.0`], {next: `2.0.0`}),
+        }, {otp: true}, async registry => {
+          await expect(run(`npm`, `tag`, `add`, `no-deps@1.0.0`, `legacy`, {
+            registryUrl: registry.url,
+            npmAuthToken: MOCK_AUTH_TOKEN,
+          })).rejects.toThrow(/Invalid OTP token/);
+
+          await run(`npm`, `tag`, `add`, `no-deps@1.0.0`, `legacy`, `--otp`, MOCK_OTP, {
+            registryUrl: registry.url,
+            npmAuthToken: MOCK_AUTH_TOKEN,
+          });
+
+          await run(`npm`, `tag`, `remove`, `no-deps`, `next`, `--otp`, MOCK_OTP, {
+            registryUrl: registry.url,
+            npmAuthToken: MOCK_AUTH_TOKEN,
+          });
+
+          expect(registry.packuments.get(`no-deps`)[`dist-tags`]).toEqual({
+            latest: `2.0.0`,
+            legacy: `1.0.0`,
+          });
+
+          const writes = registry.requests.filter(request => request.method !== `GET`);
+          expect(writes.filter(request => request.otp === MOCK_OTP).map(request => request.method)).toEqual([`PUT`, `DELETE`]);
+        });
+      }),
+    );
+  });
+});
@@ -0,0 +1,124 @@
+import {MOCK_AUTH_TOKEN, makePackument, mockRegistryServer} from './registry.utility';
+
+describe(`Commands`, () => {
+  describe(`npm unpublish`, () => {
+    test(
+      `it should only list the versions that would be removed when using --dry-run`,
+      makeTemporaryEnv({}, async ({path, run, source}) => {
+        await mockRegistryServer({
+          [`no-deps`]: makePackument(`no-deps`, [`1.0.0`, `1.1.0`, `2.0.0`]),
+        }, {}, async registry => {
+          const {stdout} = await run(`npm`, `unpublish`, `no-deps@2.0.0`, `--dry-run`, {
+            registryUrl: registry.url,
+            npmAuthToken: MOCK_AUTH_TOKEN,
+          });
+
+          expect(stdout).toContain(`Would remove the following versions of no-deps:`);
+          expect(stdout).toContain(`- 2.0.0`);
+          expect(stdout).not.toContain(`- 1.1.0`);
+
+          const {stdout: forceStdout} = await run(`npm`, `unpublish`, `no-deps`, `--force`, `--dry-run`, {
+            registryUrl: registry.url,
+            npmAuthToken: MOCK_AUTH_TOKEN,
+          });
+
+          expect(forceStdout).toContain(`- 1.0.0`);
+          expect(forceStdout).toContain(`- 1.1.0`);
+          expect(forceStdout).toContain(`- 2.0.0`);
+          expect(forceStdout).toContain(`No version would remain; the package itself will be removed`);
+
+          expect(registry.requests.filter(request => request.method !== `GET`)).toEqual([]);
+          expect(Object.keys(registry.packuments.get(`no-deps`).versions)).toEqual([`1.0.0`, `1.1.0`, `2.0.0`]);
+        });
+      }),
+    );
+
+    test(
+      `it should require --force to remove a whole package`,
+      makeTemporaryEnv({}, async ({path, run, source}) => {
+        await mockRegistryServer({
+          [`no-deps`]: makePackument(`no-deps`, [`1.0.0`]),
+        }, {}, async registry => {
+          await expect(run(`npm`, `unpublish`, `no-deps`, {
+            registryUrl: registry.url,
+            npmAuthToken: MOCK_AUTH_TOKEN,
+          })).rejects.toThrow(/No version specified; pass --force to remove every version of no-deps/);
+
+          expect(registry.requests).toEqual([]);
+          expect(registry.packuments.has(`no-deps`)).toEqual(true);
+        });
+      }),
+    );
+
+    test(
+      `it should move latest to the highest remaining version`,
+      makeTemporaryEnv({}, async ({path, run, source}) => {
+        await mockRegistryServer({
+          [`no-deps`]: makePackument(`no-deps`, [`1.0.0`, `1.1.0`, `2.0.0`], {next: `2.0.0`, legacy: `1.0.0`}),
+        }, {}, async registry => {
+          await run(`npm`, `unpublish`, `no-deps@2.0.0`, {
+            registryUrl: registry.url,
+            npmAuthToken: MOCK_AUTH_TOKEN,
+          });
+
+          const packument = registry.packuments.get(`no-deps`);
+
+          expect(Object.keys(packument.versions)).toEqual([`1.0.0`, `1.1.0`]);
+          expect(Object.keys(packument.time)).toEqual([`1.0.0`, `1.1.0`]);
+
+          // The tags pointing to the removed version are dropped, except latest
+          expect(packument[`dist-tags`]).toEqual({
+            latest: `1.1.0`,
+            legacy: `1.0.0`,
+          });
+
+          expect(registry.requests).toContainEqual(expect.objectContaining({
+            method: `DELETE`,
+            url: expect.stringMatching(/^\/no-deps\/-\/no-deps-2\.0\.0\.tgz\/-rev\//),
+          }));
+        });
+      }),
+    );
+
+    test(
+      `it should delete the whole package when no version remains`,
+      makeTemporaryEnv({}, async ({path, run, source}) => {
+        await mockRegistryServer({
+          [`no-deps`]: makePackument(`no-deps`, [`1.0.0`]),
+          [`one-fixed-dep`]: makePackument(`one-fixed-dep`, [`1.0.0`, `2.0.0`]),
+        }, {}, async registry => {
+          await run(`npm`, `unpublish`, `no-deps@1.0.0`, {
+            registryUrl: registry.url,
+            npmAuthToken: MOCK_AUTH_TOKEN,
+          });
+
+          expect(registry.packuments.has(`no-deps`)).toEqual(false);
+          expect(registry.requests.filter(request => request.method === `PUT`)).toEqual([]);
+
+          await run(`npm`, `unpublish`, `one-fixed-dep`, `--force`, {
+            registryUrl: registry.url,
+            npmAuthToken: MOCK_AUTH_TOKEN,
+          });
+
+          expect(registry.packuments.has(`one-fixed-dep`)).toEqual(false);
+        });
+      }),
+    );
+
+    test(
+      `it should refuse to remove a version that isn't published`,
+      makeTemporaryEnv({}, async ({path, run, source}) => {
+        await mockRegistryServer({
+          [`no-deps`]: makePackument(`no-deps`, [`1.0.0`]),
+        }, {}, async registry => {
+          await expect(run(`npm`, `unpublish`, `no-deps@3.0.0`, {
+            registryUrl: registry.url,
+            npmAuthToken: MOCK_AUTH_TOKEN,
+          })).rejects.toThrow(/Version 3\.0\.0 of no-deps isn't published on the registry/);
+
+          expect(registry.requests.filter(request => request.method !== `GET`)).toEqual([]);
+        });
+      }),
+    );
+  });
+});
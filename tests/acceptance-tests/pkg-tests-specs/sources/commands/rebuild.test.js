@@ -72,5 +72,36 @@ describe(`Commands`, () => {
         ]);
       }),
     );
+
+    test(
+      `it shouldn't rebuild the packages whose builds haven't been approved`,
+      makeTemporaryEnv({
+        dependencies: {
+          [`no-deps-scripted`]: `1.0.0`,
+        },
+      }, async ({path, run, source}) => {
+        // Build approvals are disabled by default in the test environment
+        const env = {YARN_ENABLE_BUILD_APPROVALS: undefined};
+
+        await run(`install`, {env});
+
+        const {stdout} = await run(`rebuild`, {env});
+        expect(stdout).toContain(`no-deps-scripted@npm:1.0.0 won't be built, as its build scripts haven't been approved`);
+
+        await expect(source(`require('no-deps-scripted/log')`, {env})).resolves.toEqual([]);
+
+        await run(`approve-builds`, `--all`, {env});
+        await run(`rebuild`, {env});
+
+        await expect(source(`require('no-deps-scripted/log')`, {env})).resolves.toEqual([
+          `preinstall`,
+          `install`,
+          `postinstall`,
+          `preinstall`,
+          `install`,
+          `postinstall`,
+        ]);
+      }),
+    );
   });
 });
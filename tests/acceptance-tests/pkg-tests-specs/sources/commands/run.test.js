@@ -2,54 +2,74 @@ import {ppath, xfs} from '@yarnpkg/fslib';
 import {misc}       from 'pkg-tests-core';
 
 describe(`Commands`, () => {
-  for (const [description, args] of [[`with prefix`, [`run`]], [`without prefix`, []]]) {
-    describe(`run ${description}`, () => {
-      test(`it should run the selected script if available`, makeTemporaryEnv({
-        scripts: {
-          foo: `echo hello`,
-        },
-      }, async ({path, run, source}) => {
-        await run(`install`);
+  // The script tests run with both the system shell and the builtin one
+  for (const [shellDescription, shellConfig] of [[``, {}], [` (builtin shell)`, {scriptShell: `builtin`}]]) {
+    for (const [description, args] of [[`with prefix`, [`run`]], [`without prefix`, []]]) {
+      describe(`run ${description}${shellDescription}`, () => {
+        test(`it should run the selected script if available`, makeTemporaryEnv({
+          scripts: {
+            foo: `echo hello`,
+          },
+        }, shellConfig, async ({path, run, source}) => {
+          await run(`install`);
 
-        await expect(run(...args, `foo`)).resolves.toMatchObject({
-          stdout: `hello\n`,
-        });
-      }));
+          await expect(run(...args, `foo`)).resolves.toMatchObject({
+            stdout: `hello\n`,
+          });
+        }));
 
-      test(`it should properly forward the script exit codes`, makeTemporaryEnv({
-        scripts: {
-          foo: `exit 42`,
-        },
-      }, async ({path, run, source}) => {
-        await run(`install`);
+        test(`it should properly forward the script exit codes`, makeTemporaryEnv({
+          scripts: {
+            foo: `exit 42`,
+          },
+        }, shellConfig, async ({path, run, source}) => {
+          await run(`install`);
 
-        await expect(run(...args, `foo`)).rejects.toMatchObject({
-          code: 42,
-        });
-      }));
+          await expect(run(...args, `foo`)).rejects.toMatchObject({
+            code: 42,
+          });
+        }));
+
+        test(`it should properly forward the script exit codes when calling into another yarnPath binary`, makeTemporaryEnv({
+          scripts: {
+            foo: `exit 0`,
+          },
+        }, shellConfig, async ({path, run, source}) => {
+          await xfs.writeFilePromise(ppath.join(path, `yarn-test-secondary-binary.js`), [
+            `#!/usr/bin/env node`,
+            `process.exit(42);`,
+          ].join(`\n`));
+
+          await run(`install`);
+
+          await expect(run(...args, `foo`, {
+            yarnPath: `./yarn-test-secondary-binary.js`,
+          })).rejects.toMatchObject({
+            code: 42,
+          });
+        }));
+      });
+    }
+  }
 
-      test(`it should properly forward the script exit codes when calling into another yarnPath binary`, makeTemporaryEnv({
+  describe(`run misc`, () => {
+    test(
+      `it should support pipes, redirections and substitutions with the builtin shell`,
+      makeTemporaryEnv({
         scripts: {
-          foo: `exit 0`,
+          foo: `echo $(echo hello) | node -p "require('fs').readFileSync(0, 'utf8').toUpperCase()" > out.txt && cat out.txt`,
         },
+      }, {
+        scriptShell: `builtin`,
       }, async ({path, run, source}) => {
-        await xfs.writeFilePromise(ppath.join(path, `yarn-test-secondary-binary.js`), [
-          `#!/usr/bin/env node`,
-          `process.exit(42);`,
-        ].join(`\n`));
-
         await run(`install`);
 
-        await expect(run(...args, `foo`, {
-          yarnPath: `./yarn-test-secondary-binary.js`,
-        })).rejects.toMatchObject({
-          code: 42,
+        await expect(run(`foo`)).resolves.toMatchObject({
+          stdout: `HELLO\n\n`,
         });
-      }));
-    });
-  }
+      }),
+    );
 
-  describe(`run misc`, () => {
     test(
       `it should run the binaries from our dependencies if available`,
       makeTemporaryEnv(
@@ -0,0 +1,84 @@
+import {Filename, ppath, xfs} from '@yarnpkg/fslib';
+
+describe(`Commands`, () => {
+  describe(`sbom`, () => {
+    test(
+      `it should report the registry packages as npm packages`,
+      makeTemporaryEnv({
+        name: `my-app`,
+        version: `1.0.0`,
+        dependencies: {
+          [`no-deps`]: `1.0.0`,
+          [`@scoped/no-deps`]: `1.0.0`,
+        },
+      }, async ({path, run, source}) => {
+        await run(`install`);
+
+        const {stdout} = await run(`sbom`);
+        const document = JSON.parse(stdout);
+
+        expect(document.components).toEqual(expect.arrayContaining([
+          expect.objectContaining({name: `no-deps`, version: `1.0.0`, purl: `pkg:npm/no-deps@1.0.0`}),
+          expect.objectContaining({group: `@scoped`, name: `no-deps`, version: `1.0.0`, purl: `pkg:npm/%40scoped/no-deps@1.0.0`}),
+        ]));
+
+        const lockfile = await xfs.readJsonPromise(ppath.join(path, Filename.lockfile));
+        const noDeps = document.components.find((component: any) => component.purl === `pkg:npm/no-deps@1.0.0`);
+
+        const checksum = lockfile.entries[`no-deps@npm:1.0.0`].checksum;
+        expect(checksum).toEqual(expect.any(String));
+
+        // The checksums are the ones from the lockfile, computed over the archives from the cache
+        expect(noDeps.hashes).toEqual([{
+          alg: `BLAKE2b-512`,
+          content: checksum,
+        }]);
+
+        const {stdout: spdxStdout} = await run(`sbom`, `--format`, `spdx`);
+        const spdxDocument = JSON.parse(spdxStdout);
+
+        const spdxNoDeps = spdxDocument.packages.find((pkg: any) => pkg.externalRefs[0].referenceLocator === `pkg:npm/no-deps@1.0.0`);
+
+        expect(spdxNoDeps.checksums).toEqual([{
+          algorithm: `BLAKE2b-512`,
+          checksumValue: checksum,
+        }]);
+      }),
+    );
+
+    test(
+      `it should report the packages that don't come from the registry as generic packages`,
+      makeTemporaryEnv({
+        name: `my-app`,
+        version: `1.0.0`,
+        private: true,
+        workspaces: [`packages/*`],
+        dependencies: {
+          [`my-lib`]: `workspace:*`,
+        },
+      }, async ({path, run, source}) => {
+        await xfs.mkdirpPromise(ppath.join(path, `packages/my-lib`));
+        await xfs.writeJsonPromise(ppath.join(path, `packages/my-lib`, Filename.manifest), {
+          name: `my-lib`,
+          version: `2.0.0`,
+        });
+
+        await run(`install`);
+
+        const {stdout} = await run(`sbom`, `--format`, `spdx`);
+        const document = JSON.parse(stdout);
+
+        const purls = document.packages.map((pkg: any) => pkg.externalRefs[0].referenceLocator);
+
+        expect(purls).toContain(`pkg:generic/my-app@1.0.0`);
+        expect(purls).toContain(`pkg:generic/my-lib@2.0.0`);
+        expect(purls.filter((purl: string) => purl.startsWith(`pkg:npm/`))).toEqual([]);
+
+        // Workspaces aren't stored in the cache, so they have no checksum
+        for (const pkg of document.packages) {
+          expect(pkg).not.toHaveProperty(`checksums`);
+        }
+      }),
+    );
+  });
+});
@@ -908,6 +908,63 @@ describe(`Commands`, () => {
         },
       ),
     );
+
+    test(
+      `it should re-run the command in the changed workspaces and their dependents when using --watch`,
+      makeTemporaryEnv(
+        {
+          private: true,
+          workspaces: [`packages/*`],
+        },
+        async ({path, run}) => {
+          // Outside of the project, so that writing it doesn't trigger new runs
+          const logPath = ppath.join(ppath.dirname(path), `log.txt`);
+
+          for (const [name, dependencies] of [[`workspace-a`, {}], [`workspace-b`, {[`workspace-a`]: `workspace:*`}], [`workspace-c`, {}]]) {
+            await writeJson(`${path}/packages/${name}/package.json`, {
+              name,
+              version: `1.0.0`,
+              scripts: {
+                log: `echo ${name} >> ${npath.fromPortablePath(logPath)}`,
+              },
+              dependencies,
+            });
+          }
+
+          await run(`install`);
+
+          const readLog = async () => xfs.existsSync(logPath)
+            ? (await xfs.readFilePromise(logPath, `utf8`)).trim().split(`\n`)
+            : [];
+
+          const controller = new AbortController();
+          const watch = run(`workspaces`, `foreach`, `--all`, `--watch`, `run`, `log`, {signal: controller.signal}).catch(error => error);
+
+          try {
+            while ((await readLog()).length < 3)
+              await new Promise(resolve => setTimeout(resolve, 100));
+
+            // The watcher only starts once the first run completed, so we keep
+            // editing the file until the changes get picked up
+            for (let iteration = 0; !(await readLog()).slice(3).includes(`workspace-b`); ++iteration) {
+              await xfs.mkdirpPromise(ppath.join(path, `packages/workspace-c/node_modules`));
+              await xfs.writeFilePromise(ppath.join(path, `packages/workspace-c/node_modules/ignored.js`), `// ${iteration}\n`);
+              await xfs.writeFilePromise(ppath.join(path, `packages/workspace-a/index.js`), `// ${iteration}\n`);
+
+              await new Promise(resolve => setTimeout(resolve, 500));
+            }
+          } finally {
+            controller.abort();
+            await watch;
+          }
+
+          const log = await readLog();
+
+          expect(log.slice(0, 3).sort()).toEqual([`workspace-a`, `workspace-b`, `workspace-c`]);
+          expect([...new Set(log.slice(3))].sort()).toEqual([`workspace-a`, `workspace-b`]);
+        },
+      ),
+    );
   });
 });
 
@@ -11,6 +11,22 @@ describe(`Features`, () => {
       }),
     );
 
+    test(
+      `it shouldn't blame the offline mirror when it isn't enabled`,
+      makeTemporaryEnv({
+        dependencies: {
+          [`no-deps`]: `1.0.0`,
+        },
+      }, async ({path, run, source}) => {
+        const {stdout} = await run(`install`, {enableNetwork: false}).then(() => {
+          throw new Error(`The install should have failed`);
+        }, error => error);
+
+        expect(stdout).toContain(`has been blocked because of your configuration settings`);
+        expect(stdout).not.toContain(`offline mirror`);
+      }),
+    );
+
     test(
       `it should fail to make requests to a blocked hostname`,
       makeTemporaryEnv({}, async ({path, run, source}) => {
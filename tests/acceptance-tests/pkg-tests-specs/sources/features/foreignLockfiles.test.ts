@@ -0,0 +1,183 @@
+import {PortablePath, ppath, xfs} from '@yarnpkg/fslib';
+import crypto                     from 'crypto';
+import stream                     from 'stream';
+
+const {
+  tests: {getPackageArchiveStream, startPackageServer},
+} = require(`pkg-tests-core`);
+
+async function getPackageIntegrity(name: string, version: string) {
+  const archiveStream = await getPackageArchiveStream(name, version);
+  const hash = crypto.createHash(`sha512`);
+
+  await stream.promises.pipeline(archiveStream, hash);
+
+  return `sha512-${hash.digest(`base64`)}`;
+}
+
+async function writeNpmLockfile(path: PortablePath, {integrity}: {integrity: string}) {
+  const registryUrl = await startPackageServer();
+
+  await xfs.writeJsonPromise(ppath.join(path, `package-lock.json`), {
+    name: `my-package`,
+    lockfileVersion: 3,
+    packages: {
+      [``]: {
+        dependencies: {
+          [`one-range-dep`]: `1.0.0`,
+        },
+      },
+      [`node_modules/one-range-dep`]: {
+        version: `1.0.0`,
+        resolved: `${registryUrl}/one-range-dep/-/one-range-dep-1.0.0.tgz`,
+        integrity: await getPackageIntegrity(`one-range-dep`, `1.0.0`),
+        dependencies: {
+          [`no-deps`]: `^1.0.0`,
+        },
+      },
+      [`node_modules/no-deps`]: {
+        version: `1.0.0`,
+        resolved: `${registryUrl}/no-deps/-/no-deps-1.0.0.tgz`,
+        integrity,
+      },
+    },
+  });
+}
+
+async function writePnpmLockfile(path: PortablePath) {
+  const oneRangeDepIntegrity = await getPackageIntegrity(`one-range-dep`, `1.0.0`);
+  const noDepsIntegrity = await getPackageIntegrity(`no-deps`, `1.0.0`);
+
+  await xfs.writeFilePromise(ppath.join(path, `pnpm-lock.yaml`), [
+    `lockfileVersion: '9.0'`,
+    ``,
+    `importers:`,
+    `  .:`,
+    `    dependencies:`,
+    `      one-range-dep:`,
+    `        specifier: 1.0.0`,
+    `        version: 1.0.0`,
+    ``,
+    `packages:`,
+    `  no-deps@1.0.0:`,
+    `    resolution: {integrity: ${noDepsIntegrity}}`,
+    ``,
+    `  one-range-dep@1.0.0:`,
+    `    resolution: {integrity: ${oneRangeDepIntegrity}}`,
+    ``,
+    `snapshots:`,
+    `  no-deps@1.0.0: {}`,
+    ``,
+    `  one-range-dep@1.0.0:`,
+    `    dependencies:`,
+    `      no-deps: 1.0.0`,
+    ``,
+  ].join(`\n`));
+}
+
+describe(`Features`, () => {
+  describe(`Foreign lockfiles`, () => {
+    test(
+      `it should pin the packages from a package-lock.json`,
+      makeTemporaryEnv({
+        dependencies: {
+          [`one-range-dep`]: `1.0.0`,
+        },
+      }, async ({path, run, source}) => {
+        await writeNpmLockfile(path, {
+          integrity: await getPackageIntegrity(`no-deps`, `1.0.0`),
+        });
+
+        await run(`install`);
+
+        await expect(source(`require('one-range-dep')`)).resolves.toMatchObject({
+          dependencies: {
+            [`no-deps`]: {
+              name: `no-deps`,
+              version: `1.0.0`,
+            },
+          },
+        });
+      }),
+    );
+
+    test(
+      `it should reject the tarballs that don't match the integrity from a package-lock.json`,
+      makeTemporaryEnv({
+        dependencies: {
+          [`one-range-dep`]: `1.0.0`,
+        },
+      }, async ({path, run, source}) => {
+        await writeNpmLockfile(path, {
+          integrity: await getPackageIntegrity(`no-deps`, `1.1.0`),
+        });
+
+        await expect(run(`install`)).rejects.toMatchObject({
+          stdout: expect.stringContaining(`Checksum mismatch`),
+        });
+      }),
+    );
+
+    test(
+      `it should pin the transitive packages from a pnpm-lock.yaml`,
+      makeTemporaryEnv({
+        dependencies: {
+          [`one-range-dep`]: `1.0.0`,
+        },
+      }, async ({path, run, source}) => {
+        await writePnpmLockfile(path);
+
+        await run(`install`);
+
+        // The registry would resolve ^1.0.0 to 1.1.0 if the package wasn't pinned
+        await expect(source(`require('one-range-dep')`)).resolves.toMatchObject({
+          dependencies: {
+            [`no-deps`]: {
+              name: `no-deps`,
+              version: `1.0.0`,
+            },
+          },
+        });
+      }),
+    );
+
+    test(
+      `it should reject the tarballs that don't match the integrity from a package-lock.json in migration mode`,
+      makeTemporaryEnv({
+        dependencies: {
+          [`one-range-dep`]: `1.0.0`,
+        },
+      }, async ({path, run, source}) => {
+        await writeNpmLockfile(path, {
+          integrity: await getPackageIntegrity(`no-deps`, `1.1.0`),
+        });
+
+        await expect(run(`install`, {env: {YARN_ENABLE_MIGRATION_MODE: `true`}})).rejects.toMatchObject({
+          stdout: expect.stringContaining(`Checksum mismatch`),
+        });
+      }),
+    );
+
+    test(
+      `it should pin the transitive packages from a pnpm-lock.yaml in migration mode`,
+      makeTemporaryEnv({
+        dependencies: {
+          [`one-range-dep`]: `1.0.0`,
+        },
+      }, async ({path, run, source}) => {
+        await writePnpmLockfile(path);
+
+        await run(`install`, {env: {YARN_ENABLE_MIGRATION_MODE: `true`}});
+
+        await expect(source(`require('one-range-dep')`)).resolves.toMatchObject({
+          dependencies: {
+            [`no-deps`]: {
+              name: `no-deps`,
+              version: `1.0.0`,
+            },
+          },
+        });
+      }),
+    );
+  });
+});
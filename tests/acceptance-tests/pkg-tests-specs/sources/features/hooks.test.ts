@@ -0,0 +1,28 @@
+import {ppath, xfs} from '@yarnpkg/fslib';
+
+describe(`Features`, () => {
+  describe(`Hooks`, () => {
+    test(
+      `it should run the afterResolution hooks before fetching the packages`,
+      makeTemporaryEnv({
+        dependencies: {
+          [`no-deps`]: `1.0.0`,
+        },
+      }, {
+        hooks: {
+          afterResolution: [`exit 1`],
+        },
+      }, async ({path, run, source}) => {
+        await expect(run(`install`)).rejects.toMatchObject({
+          stdout: expect.stringContaining(`afterResolution`),
+        });
+
+        const cacheFiles = xfs.existsSync(ppath.join(path, `.yarn/cache`))
+          ? await xfs.readdirPromise(ppath.join(path, `.yarn/cache`))
+          : [];
+
+        expect(cacheFiles.filter(file => file.startsWith(`no-deps-`))).toEqual([]);
+      }),
+    );
+  });
+});
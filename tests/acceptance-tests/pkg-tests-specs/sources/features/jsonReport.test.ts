@@ -0,0 +1,82 @@
+function parseEvents(stdout: string) {
+  return stdout.trim().split(`\n`).map(line => JSON.parse(line));
+}
+
+describe(`Features`, () => {
+  describe(`JSON report`, () => {
+    test(
+      `it should print the install events as NDJSON`,
+      makeTemporaryEnv({
+        dependencies: {
+          [`no-deps`]: `1.0.0`,
+        },
+      }, async ({path, run, source}) => {
+        const {stdout} = await run(`--json`, `install`);
+        const events = parseEvents(stdout);
+
+        const types = new Set(events.map(event => event.type));
+        for (const type of types)
+          expect([`version`, `info`, `warning`, `error`, `sectionStart`, `sectionEnd`, `progress`, `logFile`]).toContain(type);
+
+        const sectionStarts = events.filter(event => event.type === `sectionStart`);
+        const sectionEnds = events.filter(event => event.type === `sectionEnd`);
+
+        expect(sectionStarts.length).toBeGreaterThan(0);
+        expect(sectionEnds.map(event => event.name)).toEqual(expect.arrayContaining(sectionStarts.map(event => event.name)));
+
+        for (const event of sectionStarts)
+          expect(event).toEqual({type: `sectionStart`, name: expect.any(String)});
+
+        for (const event of events.filter(event => event.type === `progress`)) {
+          expect(event).toEqual({
+            type: `progress`,
+            resolutionCount: expect.any(Number),
+            fetchCount: expect.any(Number),
+            fetchSize: expect.any(Number),
+          });
+        }
+
+        for (const event of events.filter(event => event.type === `info`)) {
+          expect(event).toMatchObject({indent: expect.any(Number), message: expect.any(String)});
+          expect(event.message).not.toMatch(/\x1b\[/);
+        }
+      }),
+    );
+
+    test(
+      `it should report the error codes separately from the messages`,
+      makeTemporaryEnv({
+        dependencies: {
+          [`no-deps`]: `42.0.0`,
+        },
+      }, async ({path, run, source}) => {
+        const {stdout} = await run(`--json`, `install`).catch(error => error);
+        const events = parseEvents(stdout);
+
+        const errors = events.filter(event => event.type === `error`);
+        expect(errors.length).toBeGreaterThan(0);
+
+        for (const event of errors) {
+          expect(event.code).toMatch(/^YN\d{4}$/);
+          expect(event.message).not.toMatch(/^\[YN\d{4}\]/);
+        }
+      }),
+    );
+
+    test(
+      `it should accept the flag anywhere before the command path`,
+      makeTemporaryEnv({
+        dependencies: {
+          [`no-deps`]: `1.0.0`,
+        },
+      }, async ({path, run, source}) => {
+        for (const args of [[`--json`, `./`, `install`], [`./`, `--json`, `install`]]) {
+          const {stdout} = await run(...args);
+          const events = parseEvents(stdout);
+
+          expect(events).toContainEqual(expect.objectContaining({type: `sectionStart`}));
+        }
+      }),
+    );
+  });
+});
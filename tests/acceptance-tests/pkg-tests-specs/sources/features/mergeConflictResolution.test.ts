@@ -284,5 +284,67 @@ describe(`Features`, () => {
         },
       ),
     );
+
+    test(
+      `it should install from a lockfile containing conflict markers outside of a git operation`,
+      makeTemporaryEnv(
+        {},
+        async ({path, run, source}) => {
+          await xfs.writeJsonPromise(ppath.join(path, Filename.manifest), {dependencies: {[`no-deps`]: `1.0.0`}});
+          await run(`install`);
+          const noDepsLockfile = await xfs.readFilePromise(ppath.join(path, Filename.lockfile), `utf8`);
+
+          await xfs.writeJsonPromise(ppath.join(path, Filename.manifest), {dependencies: {[`one-fixed-dep`]: `1.0.0`}});
+          await run(`install`);
+          const oneFixedDepLockfile = await xfs.readFilePromise(ppath.join(path, Filename.lockfile), `utf8`);
+
+          // Each side of the conflict only knows about one of the two dependencies
+          await xfs.writeFilePromise(ppath.join(path, Filename.lockfile), [
+            `<<<<<<< HEAD\n`,
+            noDepsLockfile,
+            `=======\n`,
+            oneFixedDepLockfile,
+            `>>>>>>> other-branch\n`,
+          ].join(``));
+
+          await xfs.writeJsonPromise(ppath.join(path, Filename.manifest), {dependencies: {[`no-deps`]: `1.0.0`, [`one-fixed-dep`]: `1.0.0`}});
+
+          await run(`install`, {
+            enableNetwork: false,
+          });
+
+          const postFixLockfile = await xfs.readJsonPromise(ppath.join(path, Filename.lockfile));
+          expect(Object.keys(postFixLockfile.entries)).toEqual(expect.arrayContaining([
+            `no-deps@npm:1.0.0`,
+            `one-fixed-dep@npm:1.0.0`,
+          ]));
+
+          await expect(source(`require('one-fixed-dep')`)).resolves.toMatchObject({
+            name: `one-fixed-dep`,
+            version: `1.0.0`,
+          });
+        },
+      ),
+    );
+
+    test(
+      `it should refuse to fix a lockfile containing conflict markers in immutable mode`,
+      makeTemporaryEnv(
+        {
+          dependencies: {[`no-deps`]: `1.0.0`},
+        },
+        async ({path, run, source}) => {
+          await run(`install`);
+          const lockfile = await xfs.readFilePromise(ppath.join(path, Filename.lockfile), `utf8`);
+
+          await xfs.writeFilePromise(ppath.join(path, Filename.lockfile), `<<<<<<< HEAD\n${lockfile}=======\n${lockfile}>>>>>>> other-branch\n`);
+
+          await expect(run(`install`, `--immutable`)).rejects.toThrow(/Cannot autofix a lockfile when running an immutable install/);
+
+          const preservedLockfile = await xfs.readFilePromise(ppath.join(path, Filename.lockfile), `utf8`);
+          expect(preservedLockfile).toContain(`<<<<<<<`);
+        },
+      ),
+    );
   });
 });
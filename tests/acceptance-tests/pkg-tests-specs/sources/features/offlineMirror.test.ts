@@ -0,0 +1,43 @@
+import {Filename, ppath, xfs} from '@yarnpkg/fslib';
+
+describe(`Features`, () => {
+  describe(`Offline mirror`, () => {
+    test(
+      `it should install from the offline mirror without network access`,
+      makeTemporaryEnv({
+        dependencies: {
+          [`no-deps`]: `^1.0.0`,
+        },
+      }, {
+        enableOfflineMirror: true,
+      }, async ({path, run, source}) => {
+        await run(`install`);
+        await xfs.removePromise(ppath.join(path, Filename.lockfile));
+
+        await run(`install`, `--offline`);
+
+        await expect(source(`require('no-deps')`)).resolves.toMatchObject({
+          name: `no-deps`,
+          version: `1.1.0`,
+        });
+      }),
+    );
+
+    test(
+      `it shouldn't use the global metadata cache when installing from the offline mirror`,
+      makeTemporaryEnv({
+        dependencies: {
+          [`no-deps`]: `^1.0.0`,
+        },
+      }, async ({path, run, source}) => {
+        // Warms the metadata cache from the global folder, but not the mirror
+        await run(`install`);
+        await xfs.removePromise(ppath.join(path, Filename.lockfile));
+
+        await expect(run(`install`, `--offline`, {enableOfflineMirror: true})).rejects.toMatchObject({
+          stdout: expect.stringMatching(/The offline mirror is missing 1 package\(s\): no-deps@/),
+        });
+      }),
+    );
+  });
+});
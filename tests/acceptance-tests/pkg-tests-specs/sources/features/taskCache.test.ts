@@ -0,0 +1,89 @@
+import {Filename, PortablePath, ppath, xfs} from '@yarnpkg/fslib';
+
+const manifest = {
+  private: true,
+  workspaces: [`packages/*`],
+  dependencies: {
+    [`dep`]: `workspace:*`,
+  },
+  scripts: {
+    build: `echo executed >> runs.log && echo built`,
+  },
+  scriptsMeta: {
+    build: {
+      inputs: [`src/**`],
+      env: [`MY_VAR`],
+    },
+  },
+};
+
+async function setupProject(path: PortablePath) {
+  await xfs.mkdirpPromise(ppath.join(path, `src`));
+  await xfs.writeFilePromise(ppath.join(path, `src/index.js`), `module.exports = 42;\n`);
+
+  await xfs.mkdirpPromise(ppath.join(path, `packages/dep`));
+  await xfs.writeJsonPromise(ppath.join(path, `packages/dep`, Filename.manifest), {name: `dep`});
+  await xfs.writeFilePromise(ppath.join(path, `packages/dep/index.js`), `module.exports = 1;\n`);
+}
+
+async function getRunCount(path: PortablePath) {
+  const log = await xfs.readFilePromise(ppath.join(path, `runs.log`), `utf8`);
+  return log.trim().split(`\n`).length;
+}
+
+describe(`Features`, () => {
+  describe(`Task cache`, () => {
+    test(
+      `it should replay the scripts whose inputs didn't change`,
+      makeTemporaryEnv(manifest, async ({path, run, source}) => {
+        await setupProject(path);
+        await run(`install`);
+
+        await expect(run(`build`)).resolves.toMatchObject({stdout: `built\n`});
+        await expect(run(`build`)).resolves.toMatchObject({stdout: `built\n`});
+
+        expect(await getRunCount(path)).toEqual(1);
+
+        await xfs.writeFilePromise(ppath.join(path, `src/index.js`), `module.exports = 43;\n`);
+        await run(`build`);
+
+        expect(await getRunCount(path)).toEqual(2);
+      }),
+    );
+
+    test(
+      `it should run the scripts again when their declared environment variables change`,
+      makeTemporaryEnv(manifest, async ({path, run, source}) => {
+        await setupProject(path);
+        await run(`install`);
+
+        await run(`build`, {env: {MY_VAR: `foo`}});
+        await run(`build`, {env: {MY_VAR: `foo`}});
+
+        expect(await getRunCount(path)).toEqual(1);
+
+        await run(`build`, {env: {MY_VAR: `bar`}});
+
+        expect(await getRunCount(path)).toEqual(2);
+      }),
+    );
+
+    test(
+      `it should run the scripts again when the workspaces they depend on change`,
+      makeTemporaryEnv(manifest, async ({path, run, source}) => {
+        await setupProject(path);
+        await run(`install`);
+
+        await run(`build`);
+        await run(`build`);
+
+        expect(await getRunCount(path)).toEqual(1);
+
+        await xfs.writeFilePromise(ppath.join(path, `packages/dep/index.js`), `module.exports = 2;\n`);
+        await run(`build`);
+
+        expect(await getRunCount(path)).toEqual(2);
+      }),
+    );
+  });
+});
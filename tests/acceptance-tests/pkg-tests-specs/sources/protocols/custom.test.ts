@@ -0,0 +1,144 @@
+import {Filename, PortablePath, npath, ppath, xfs} from '@yarnpkg/fslib';
+import {tests}                                    from 'pkg-tests-core';
+
+const {getPackageArchivePath} = tests;
+
+async function setupStubProtocol(path: PortablePath) {
+  const archivePath = await getPackageArchivePath(`no-deps`, `1.0.0`);
+
+  await xfs.writeFilePromise(ppath.join(path, `stub-protocol.js`), `
+    const fs = require('fs');
+    const path = require('path');
+
+    let input = '';
+
+    process.stdin.on('data', chunk => input += chunk);
+    process.stdin.on('end', () => {
+      const request = JSON.parse(input);
+      fs.appendFileSync(path.join(__dirname, 'requests.ndjson'), JSON.stringify(request) + '\\n');
+
+      switch (request.action) {
+        case 'resolve': {
+          process.stdout.write(JSON.stringify({reference: request.selector + '-resolved'}));
+        } break;
+
+        case 'fetch': {
+          const archivePath = path.join(request.tempDirectory, 'package.tgz');
+          fs.copyFileSync(${JSON.stringify(npath.fromPortablePath(archivePath))}, archivePath);
+
+          // Used to check that the temporary folder is removed when the archive can't be read
+          const reportedPath = request.reference.startsWith('missing-archive')
+            ? path.join(request.tempDirectory, 'missing.tgz')
+            : archivePath;
+
+          process.stdout.write(JSON.stringify({archivePath: reportedPath}));
+        } break;
+      }
+    });
+  `);
+
+  await xfs.writeFilePromise(ppath.join(path, Filename.rc), [
+    `customProtocols:\n`,
+    `  stub: "node stub-protocol.js"\n`,
+  ].join(``));
+}
+
+async function readRequests(path: PortablePath) {
+  const content = await xfs.readFilePromise(ppath.join(path, `requests.ndjson`), `utf8`);
+  await xfs.removePromise(ppath.join(path, `requests.ndjson`));
+
+  return content.trim().split(`\n`).map(line => JSON.parse(line));
+}
+
+describe(`Protocols`, () => {
+  describe(`custom protocols`, () => {
+    test(
+      `it should resolve and fetch packages through the executable`,
+      makeTemporaryEnv({
+        dependencies: {
+          [`pkg`]: `stub:my-pkg`,
+        },
+      }, async ({path, run, source}) => {
+        await setupStubProtocol(path);
+
+        await run(`install`);
+
+        await expect(source(`require('pkg/package.json')`)).resolves.toMatchObject({
+          name: `no-deps`,
+          version: `1.0.0`,
+        });
+
+        const requests = await readRequests(path);
+
+        expect(requests).toEqual([{
+          action: `resolve`,
+          descriptor: `pkg@stub:my-pkg`,
+          ident: `pkg`,
+          protocol: `stub`,
+          selector: `my-pkg`,
+        }, {
+          action: `fetch`,
+          locator: `pkg@stub:my-pkg-resolved`,
+          ident: `pkg`,
+          protocol: `stub`,
+          reference: `my-pkg-resolved`,
+          tempDirectory: expect.any(String),
+        }]);
+
+        expect(xfs.existsSync(npath.toPortablePath(requests[1].tempDirectory))).toEqual(false);
+      }),
+    );
+
+    test(
+      `it should only fetch the locked references on subsequent installs`,
+      makeTemporaryEnv({
+        dependencies: {
+          [`pkg`]: `stub:my-pkg`,
+        },
+      }, async ({path, run, source}) => {
+        await setupStubProtocol(path);
+
+        await run(`install`);
+        await readRequests(path);
+
+        const lockfile = await xfs.readJsonPromise(ppath.join(path, Filename.lockfile));
+        expect(lockfile.entries[`pkg@stub:my-pkg`]).toMatchObject({
+          checksum: expect.any(String),
+          resolution: {resolution: `pkg@stub:my-pkg-resolved`},
+        });
+
+        await xfs.removePromise(ppath.join(path, `.yarn/cache`));
+        await run(`install`, `--immutable`);
+
+        expect(await readRequests(path)).toEqual([expect.objectContaining({
+          action: `fetch`,
+          locator: `pkg@stub:my-pkg-resolved`,
+          reference: `my-pkg-resolved`,
+        })]);
+
+        await expect(source(`require('pkg/package.json')`)).resolves.toMatchObject({
+          name: `no-deps`,
+          version: `1.0.0`,
+        });
+      }),
+    );
+
+    test(
+      `it should remove the temporary folder when the archive can't be read`,
+      makeTemporaryEnv({
+        dependencies: {
+          [`pkg`]: `stub:missing-archive`,
+        },
+      }, async ({path, run, source}) => {
+        await setupStubProtocol(path);
+
+        await expect(run(`install`)).rejects.toThrow();
+
+        const fetchRequest = (await readRequests(path)).find(request => request.action === `fetch`);
+        expect(fetchRequest).toBeDefined();
+
+        expect(xfs.existsSync(npath.toPortablePath(fetchRequest.tempDirectory))).toEqual(false);
+      }),
+    );
+  });
+});